@@ -0,0 +1,164 @@
+//! In-memory TLS fixtures shared by the TLS benches
+
+#![allow(dead_code)]
+
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::io::{Error, ErrorKind, Read, Result, Write};
+use std::net::{Shutdown, SocketAddr};
+use std::rc::Rc;
+use std::sync::Arc;
+
+use mio::{event::Source, Interest, Registry, Token};
+use rask::connection::{Connection, ConnectionBuilder, TlsConnection};
+use rask::net::tcp_stream::TcpStream;
+use rustls::pki_types::{CertificateDer, PrivateKeyDer, PrivatePkcs8KeyDer, ServerName};
+use rustls::{ClientConfig, ClientConnection, RootCertStore, ServerConfig};
+
+const CA_CERT: &[u8] = include_bytes!("../../certs/ca.der");
+const SERVER_CERT: &[u8] = include_bytes!("../../certs/server.der");
+const SERVER_KEY: &[u8] = include_bytes!("../../certs/server.key.der");
+
+/// Returns a server configuration using the test certificate for `localhost`
+pub fn server_config() -> Arc<ServerConfig> {
+    Arc::new(
+        ServerConfig::builder()
+            .with_no_client_auth()
+            .with_single_cert(
+                vec![CertificateDer::from(SERVER_CERT)],
+                PrivateKeyDer::Pkcs8(PrivatePkcs8KeyDer::from(SERVER_KEY)),
+            )
+            .unwrap(),
+    )
+}
+
+/// In-memory transport. Clones share the same buffers, so the bench can feed records to a stream
+/// owned by the connection.
+#[derive(Debug, Default, Clone)]
+pub struct MemoryStream {
+    pub incoming: Rc<RefCell<VecDeque<u8>>>,
+    pub outgoing: Rc<RefCell<Vec<u8>>>,
+}
+
+impl Read for MemoryStream {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        let mut incoming = self.incoming.borrow_mut();
+        if incoming.is_empty() {
+            return Err(ErrorKind::WouldBlock.into());
+        }
+
+        incoming.read(buf)
+    }
+}
+
+impl Write for MemoryStream {
+    fn write(&mut self, buf: &[u8]) -> Result<usize> {
+        self.outgoing.borrow_mut().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        Ok(())
+    }
+}
+
+impl Source for MemoryStream {
+    fn register(&mut self, _: &Registry, _: Token, _: Interest) -> Result<()> {
+        Ok(())
+    }
+
+    fn reregister(&mut self, _: &Registry, _: Token, _: Interest) -> Result<()> {
+        Ok(())
+    }
+
+    fn deregister(&mut self, _: &Registry) -> Result<()> {
+        Ok(())
+    }
+}
+
+impl TcpStream for MemoryStream {
+    fn connect(_: SocketAddr) -> Result<Self> {
+        Ok(Self::default())
+    }
+
+    fn peer_addr(&self) -> Result<SocketAddr> {
+        Ok(SocketAddr::from(([127, 0, 0, 1], 0)))
+    }
+
+    fn local_addr(&self) -> Result<SocketAddr> {
+        Ok(SocketAddr::from(([127, 0, 0, 1], 0)))
+    }
+
+    fn shutdown(&self, _: Shutdown) -> Result<()> {
+        Ok(())
+    }
+
+    fn set_nodelay(&self, _: bool) -> Result<()> {
+        Ok(())
+    }
+
+    fn nodelay(&self) -> Result<bool> {
+        Ok(true)
+    }
+
+    fn set_ttl(&self, _: u32) -> Result<()> {
+        Ok(())
+    }
+
+    fn ttl(&self) -> Result<u32> {
+        Ok(64)
+    }
+
+    fn take_error(&self) -> Result<Option<Error>> {
+        Ok(None)
+    }
+
+    fn peek(&self, buf: &mut [u8]) -> Result<usize> {
+        let incoming = self.incoming.borrow();
+        let n = buf.len().min(incoming.len());
+        for (dst, src) in buf.iter_mut().zip(incoming.iter()) {
+            *dst = *src;
+        }
+
+        Ok(n)
+    }
+}
+
+/// Completes a handshake between a client and a server connection over an in-memory transport.
+pub fn connect() -> (TlsConnection<MemoryStream>, ClientConnection, MemoryStream) {
+    let mut roots = RootCertStore::empty();
+    roots.add(CertificateDer::from(CA_CERT)).unwrap();
+    let client_config = ClientConfig::builder()
+        .with_root_certificates(roots)
+        .with_no_client_auth();
+
+    let transport = MemoryStream::default();
+    let mut server = ConnectionBuilder::new(transport.clone(), Token(0))
+        .with_tls(server_config())
+        .build()
+        .unwrap();
+    let mut client = ClientConnection::new(
+        Arc::new(client_config),
+        ServerName::try_from("localhost").unwrap(),
+    )
+    .unwrap();
+
+    while client.is_handshaking() {
+        client
+            .write_tls(&mut *transport.incoming.borrow_mut())
+            .unwrap();
+        if !transport.incoming.borrow().is_empty() {
+            server.read().unwrap();
+        }
+
+        server.write().unwrap();
+        let outgoing = std::mem::take(&mut *transport.outgoing.borrow_mut());
+        let mut records = outgoing.as_slice();
+        while !records.is_empty() {
+            client.read_tls(&mut records).unwrap();
+        }
+        client.process_new_packets().unwrap();
+    }
+
+    (server, client, transport)
+}
@@ -0,0 +1,64 @@
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use rask::parser::{active_simd, h1::request::has_complete_headers};
+
+const REQ: &[u8] = b"\
+GET /api/v1.0/weather/forecast HTTP/1.1\r\n\
+Host: www.example.org\r\n\r\n";
+
+const REQ_MED: &[u8] = b"\
+GET /api/v1.0/weather/forecast HTTP/1.1\r\n\
+Accept: */*\r\n\
+Accept-Encoding: gzip,deflate,br\r\n\
+Accept-Language: en-US,en;q=0.5\r\n\
+Cache-Control: no-cache\r\n\
+Connection: keep-alive\r\n\
+DNT: 1\r\n\
+Host: www.example.org\r\n\
+Pragma: no-cache\r\n\
+Referrer: https://www.example.org\r\n\
+Sec-Fetch-Dest: empty\r\n\
+Sec-Fetch-Mode: cors\r\n\
+Sec-Fetch-Site: same-origin\r\n\
+User-Agent: Mozilla/5.0 (X11; Linux x86_64; rv:109.0) Gecko/20100101 Firefox/109.0\r\n\r\n";
+
+const REQ_COMP: &[u8] = b"\
+GET /wp-content/uploads/2010/03/darth-vader-jedi-battle-lightsaber.jpg HTTP/1.1\r\n\
+Host: www.example.org\r\n\
+User-Agent: Mozilla/5.0 (Macintosh; U; Intel Mac OS X 10.6; ja-JP-mac; rv:1.9.2.3) Gecko/20100401 Firefox/3.6.3 Pathtraq/0.9\r\n\
+Accept: text/html,application/xhtml+xml,application/xml;q=0.9,*/*;q=0.8\r\n\
+Accept-Language: ja,en-us;q=0.7,en;q=0.3\r\n\
+Accept-Encoding: gzip,deflate\r\n\
+Accept-Charset: Shift_JIS,utf-8;q=0.7,*;q=0.7\r\n\
+Keep-Alive: 115\r\n\
+Connection: keep-alive\r\n\
+Cookie: wp_ozh_wsa_visits=2; wp_ozh_wsa_visit_lasttime=xxxxxxxxxx; __utma=xxxxxxxxx.xxxxxxxxxx.xxxxxxxxxx.xxxxxxxxxx.xxxxxxxxxx.x; __utmz=xxxxxxxxx.xxxxxxxxxx.x.x.utmccn=(referral)|utmcsr=reader.livedoor.com|utmcct=/reader/|utmcmd=referral|padding=under256\r\n\r\n";
+
+#[inline(never)]
+fn naive(buf: &[u8]) -> Option<usize> {
+    buf.windows(4).position(|w| w == b"\r\n\r\n").map(|n| n + 4)
+}
+
+fn benchmark(c: &mut Criterion) {
+    println!("SIMD: {:?}", active_simd());
+
+    let inputs = [REQ, REQ_MED, REQ_COMP];
+
+    let mut group = c.benchmark_group("header_terminator");
+    for &input in inputs.iter() {
+        group.throughput(Throughput::Bytes(input.len() as u64));
+        group.bench_with_input(
+            BenchmarkId::new("simd", input.len() as u64),
+            input,
+            |b, i| b.iter(|| has_complete_headers(black_box(i))),
+        );
+        group.bench_with_input(
+            BenchmarkId::new("naive", input.len() as u64),
+            input,
+            |b, i| b.iter(|| naive(black_box(i))),
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(benches, benchmark);
+criterion_main!(benches);
@@ -3,7 +3,9 @@ use std::ops::Range;
 use criterion::{
     black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput::Bytes,
 };
-use rask::parser::{h1::tokens::is_request_target_token, ParseError, ParseResult, Status};
+use rask::parser::{
+    active_simd, h1::tokens::is_request_target_token, ParseError, ParseResult, Status,
+};
 
 const TARGETS: [&[u8]; 4] = [
     b"/",
@@ -13,6 +15,8 @@ const TARGETS: [&[u8]; 4] = [
 ];
 
 fn benchmark(c: &mut Criterion) {
+    println!("SIMD: {:?}", active_simd());
+
     let mut group = c.benchmark_group("target");
     for target in TARGETS {
         group.throughput(Bytes(target.len() as u64));
@@ -146,6 +150,50 @@ fn parse_target_vectorized_ssse3(buf: &[u8], mut pos: usize) -> Result<usize, us
     Err(pos)
 }
 
+#[cfg(target_arch = "aarch64")]
+#[inline]
+fn parse_target_vectorized_neon(buf: &[u8], mut pos: usize) -> Result<usize, usize> {
+    use core::arch::aarch64::*;
+
+    const ROW_MAP: [u8; 16] = [
+        0x01, 0x02, 0x04, 0x08, 0x10, 0x20, 0x40, 0x80, // prevent fmt
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    ];
+    const COL_MAP: [u8; 16] = [
+        0xf8, 0xfc, 0xfc, 0xfc, 0xfc, 0xfc, 0xfc, 0xfc, // prevent fmt
+        0xfc, 0xfc, 0xfc, 0xfc, 0xf4, 0xfc, 0xf4, 0x7c,
+    ];
+
+    unsafe {
+        let row_map = vld1q_u8(ROW_MAP.as_ptr());
+        let col_map = vld1q_u8(COL_MAP.as_ptr());
+        let lower_mask = vdupq_n_u8(0x0f);
+
+        while buf[pos..].len() >= 16 {
+            let data = vld1q_u8(buf[pos..].as_ptr());
+
+            // table lookups yield zero for out of range indices, so bytes above 0x7f have no row
+            let row_mask = vqtbl1q_u8(row_map, vshrq_n_u8(data, 4));
+            let col_mask = vqtbl1q_u8(col_map, vandq_u8(data, lower_mask));
+
+            let valid = vtstq_u8(row_mask, col_mask);
+
+            // NEON has no equivalent of movemask, so each lane is narrowed to four bits instead
+            let invalid = vshrn_n_u16(vreinterpretq_u16_u8(vmvnq_u8(valid)), 4);
+            let bits = vget_lane_u64(vreinterpret_u64_u8(invalid), 0);
+            let num_valid = (bits.trailing_zeros() / 4) as usize;
+
+            pos += num_valid;
+
+            if num_valid != 16 {
+                return Ok(pos);
+            }
+        }
+    }
+
+    Err(pos)
+}
+
 fn parse_target(buf: &[u8], mut pos: usize) -> ParseResult<(usize, Range<usize>)> {
     let start = pos;
 
@@ -167,6 +215,12 @@ fn parse_target(buf: &[u8], mut pos: usize) -> ParseResult<(usize, Range<usize>)
         Err(n) => pos = n,
     };
 
+    #[cfg(target_arch = "aarch64")]
+    match parse_target_vectorized_neon(buf, pos) {
+        Ok(n) => return Ok(Status::Complete((n, start..n))),
+        Err(n) => pos = n,
+    };
+
     for &b in &buf[pos..] {
         if !is_request_target_token(b) {
             if pos == start {
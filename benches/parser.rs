@@ -1,5 +1,5 @@
 use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
-use rask::parser::h1::request::H1Request;
+use rask::parser::{active_simd, h1::request::H1Request};
 
 const REQ: &[u8] = b"\
 GET /api/v1.0/weather/forecast HTTP/1.1\r\n\
@@ -54,6 +54,8 @@ Connection: keep-alive\r\n\
 Cookie: wp_ozh_wsa_visits=2; wp_ozh_wsa_visit_lasttime=xxxxxxxxxx; __utma=xxxxxxxxx.xxxxxxxxxx.xxxxxxxxxx.xxxxxxxxxx.xxxxxxxxxx.x; __utmz=xxxxxxxxx.xxxxxxxxxx.x.x.utmccn=(referral)|utmcsr=reader.livedoor.com|utmcct=/reader/|utmcmd=referral|padding=under256\r\n\r\n";
 
 fn benchmark(c: &mut Criterion) {
+    println!("SIMD: {:?}", active_simd());
+
     let inputs = [REQ, REQ_MED, REQ_COMP, REQ_LONG];
 
     let mut group = c.benchmark_group("parse");
@@ -0,0 +1,35 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use rask::parser::{
+    h1::response::{Response, ResponseTemplate},
+    status::Status,
+    Version,
+};
+
+fn benchmark(c: &mut Criterion) {
+    let mut group = c.benchmark_group("response");
+    let mut sink = Vec::with_capacity(1024);
+
+    group.bench_function("serialize", |b| {
+        b.iter(|| {
+            sink.clear();
+            let response = Response::new_with_status_line(Version::H1_1, Status::NoContent);
+            sink.extend_from_slice(black_box(&response.serialize()));
+        })
+    });
+
+    let template = ResponseTemplate::new(&Response::new_with_status_line(
+        Version::H1_1,
+        Status::NoContent,
+    ));
+    group.bench_function("template", |b| {
+        b.iter(|| {
+            sink.clear();
+            sink.extend_from_slice(black_box(template.as_bytes()));
+        })
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, benchmark);
+criterion_main!(benches);
@@ -0,0 +1,44 @@
+use criterion::{criterion_group, criterion_main, Criterion};
+use mio::Token;
+use rask::connection::ConnectionBuilder;
+
+mod common;
+
+use common::MemoryStream;
+
+fn benchmark(c: &mut Criterion) {
+    let config = common::server_config();
+
+    let mut group = c.benchmark_group("tls_accept");
+    group.bench_function("new", |b| {
+        b.iter(|| {
+            ConnectionBuilder::new(MemoryStream::default(), Token(0))
+                .with_tls(config.clone())
+                .build()
+                .unwrap()
+        })
+    });
+
+    let mut pool = vec![ConnectionBuilder::new(MemoryStream::default(), Token(0))
+        .with_tls(config.clone())
+        .build()
+        .unwrap()
+        .into_tls_allocation()];
+    group.bench_function("reused", |b| {
+        b.iter_custom(|iters| {
+            let start = std::time::Instant::now();
+            for _ in 0..iters {
+                let connection = ConnectionBuilder::new(MemoryStream::default(), Token(0))
+                    .with_tls(config.clone())
+                    .build_in(pool.pop().unwrap())
+                    .unwrap();
+                pool.push(connection.into_tls_allocation());
+            }
+            start.elapsed()
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, benchmark);
+criterion_main!(benches);
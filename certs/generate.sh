@@ -0,0 +1,38 @@
+#!/bin/sh
+# Generates the self-signed test certificates used by the benches and tests. These are for local
+# use only and must never be trusted outside of this repository.
+set -e
+cd "$(dirname "$0")"
+
+openssl req -x509 -newkey ec -pkeyopt ec_paramgen_curve:prime256v1 -nodes -days 3650 \
+    -subj "/CN=rask test CA" -keyout ca.key.pem -out ca.pem \
+    -addext "basicConstraints=critical,CA:TRUE" -addext "keyUsage=critical,keyCertSign"
+
+openssl req -newkey ec -pkeyopt ec_paramgen_curve:prime256v1 -nodes \
+    -subj "/CN=localhost" -keyout server.key.pem -out server.csr
+openssl x509 -req -in server.csr -CA ca.pem -CAkey ca.key.pem -CAcreateserial -days 3650 \
+    -extfile /dev/stdin -out server.pem <<EXT
+basicConstraints=critical,CA:FALSE
+keyUsage=critical,digitalSignature
+extendedKeyUsage=serverAuth
+subjectAltName=DNS:localhost
+EXT
+
+openssl req -newkey ec -pkeyopt ec_paramgen_curve:prime256v1 -nodes \
+    -subj "/CN=rask test client" -keyout client.key.pem -out client.csr
+openssl x509 -req -in client.csr -CA ca.pem -CAkey ca.key.pem -CAcreateserial -days 3650 \
+    -extfile /dev/stdin -out client.pem <<EXT
+basicConstraints=critical,CA:FALSE
+keyUsage=critical,digitalSignature
+extendedKeyUsage=clientAuth
+subjectAltName=DNS:client.localhost
+EXT
+
+openssl x509 -in ca.pem -outform der -out ca.der
+openssl x509 -in server.pem -outform der -out server.der
+openssl pkcs8 -topk8 -nocrypt -in server.key.pem -outform der -out server.key.der
+openssl x509 -in client.pem -outform der -out client.der
+openssl pkcs8 -topk8 -nocrypt -in client.key.pem -outform der -out client.key.der
+
+rm -f ca.key.pem ca.pem ca.srl server.csr server.pem server.key.pem client.csr client.pem \
+    client.key.pem
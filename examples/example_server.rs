@@ -22,6 +22,10 @@ fn main() -> Result<()> {
                 tls: None,
                 http_port: 8080,
                 https_port: 8443,
+                max_requests: None,
+                proxy_mode: false,
+                body_read_timeout: None,
+                max_queued_responses: None,
             };
 
             let mut listener = MultiListener::<_, _, PlainConnection<_>>::new(mio_listener, config);
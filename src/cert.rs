@@ -0,0 +1,220 @@
+// Copyright 2022 Ryan Seipp
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Inspection of the X.509 certificates presented by TLS peers
+
+/// The names a certificate identifies its subject by, borrowed from its DER encoding. Intended
+/// for logging and authorization decisions once the certificate chain has been verified.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct SubjectNames<'a> {
+    /// The first common name (CN) attribute of the subject, if any.
+    pub common_name: Option<&'a str>,
+    /// The DNS names listed in the subject alternative name extension.
+    pub dns_names: Vec<&'a str>,
+}
+
+/// Reads the subject names of a DER encoded certificate, or returns `None` if it is malformed.
+/// Only the structure needed to locate the names is decoded, so the certificate is not validated.
+///
+/// Defined by RFC 5280 Section 4.1 by ASN.1
+/// ```asn1
+/// Certificate  ::=  SEQUENCE  {
+///      tbsCertificate       TBSCertificate,
+///      signatureAlgorithm   AlgorithmIdentifier,
+///      signatureValue       BIT STRING  }
+///
+/// TBSCertificate  ::=  SEQUENCE  {
+///      version         [0]  EXPLICIT Version DEFAULT v1,
+///      serialNumber         CertificateSerialNumber,
+///      signature            AlgorithmIdentifier,
+///      issuer               Name,
+///      validity             Validity,
+///      subject              Name,
+///      subjectPublicKeyInfo SubjectPublicKeyInfo,
+///      issuerUniqueID  [1]  IMPLICIT UniqueIdentifier OPTIONAL,
+///      subjectUniqueID [2]  IMPLICIT UniqueIdentifier OPTIONAL,
+///      extensions      [3]  EXPLICIT Extensions OPTIONAL  }
+/// ```
+pub fn subject_names(der: &[u8]) -> Option<SubjectNames<'_>> {
+    let (certificate, _) = expect(der, SEQUENCE)?;
+    let (tbs, _) = expect(certificate, SEQUENCE)?;
+
+    let mut rest = tbs;
+    if rest.first() == Some(&VERSION) {
+        rest = read(rest)?.2;
+    }
+
+    // serialNumber, signature, issuer, validity
+    for _ in 0..4 {
+        rest = read(rest)?.2;
+    }
+
+    let (subject, mut rest) = expect(rest, SEQUENCE)?;
+    let mut names = SubjectNames {
+        common_name: common_name(subject)?,
+        dns_names: Vec::new(),
+    };
+
+    while !rest.is_empty() {
+        let (tag, contents, next) = read(rest)?;
+        if tag == EXTENSIONS {
+            names.dns_names = dns_names(contents)?;
+        }
+
+        rest = next;
+    }
+
+    Some(names)
+}
+
+const BOOLEAN: u8 = 0x01;
+const OCTET_STRING: u8 = 0x04;
+const OID: u8 = 0x06;
+const SEQUENCE: u8 = 0x30;
+const SET: u8 = 0x31;
+const VERSION: u8 = 0xa0;
+const EXTENSIONS: u8 = 0xa3;
+const DNS_NAME: u8 = 0x82;
+
+/// id-at-commonName, 2.5.4.3
+const COMMON_NAME_OID: &[u8] = &[0x55, 0x04, 0x03];
+/// id-ce-subjectAltName, 2.5.29.17
+const SUBJECT_ALT_NAME_OID: &[u8] = &[0x55, 0x1d, 0x11];
+
+/// Finds the first common name in a `Name`, a sequence of sets of attribute type and value pairs.
+fn common_name(mut name: &[u8]) -> Option<Option<&str>> {
+    while !name.is_empty() {
+        let (set, next) = expect(name, SET)?;
+        name = next;
+
+        let mut attributes = set;
+        while !attributes.is_empty() {
+            let (attribute, next) = expect(attributes, SEQUENCE)?;
+            attributes = next;
+
+            let (oid, value) = expect(attribute, OID)?;
+            if oid == COMMON_NAME_OID {
+                // any of the directory string types, all of which are read as UTF-8
+                let (_, value, _) = read(value)?;
+                return std::str::from_utf8(value).ok().map(Some);
+            }
+        }
+    }
+
+    Some(None)
+}
+
+/// Collects the DNS names of the subject alternative name extension, if present.
+fn dns_names(extensions: &[u8]) -> Option<Vec<&str>> {
+    let (mut extensions, _) = expect(extensions, SEQUENCE)?;
+
+    while !extensions.is_empty() {
+        let (extension, next) = expect(extensions, SEQUENCE)?;
+        extensions = next;
+
+        let (oid, mut rest) = expect(extension, OID)?;
+        if oid != SUBJECT_ALT_NAME_OID {
+            continue;
+        }
+
+        if rest.first() == Some(&BOOLEAN) {
+            rest = read(rest)?.2;
+        }
+
+        let (value, _) = expect(rest, OCTET_STRING)?;
+        let (mut general_names, _) = expect(value, SEQUENCE)?;
+
+        let mut names = Vec::new();
+        while !general_names.is_empty() {
+            let (tag, name, next) = read(general_names)?;
+            general_names = next;
+
+            if tag == DNS_NAME {
+                names.push(std::str::from_utf8(name).ok()?);
+            }
+        }
+
+        return Some(names);
+    }
+
+    Some(Vec::new())
+}
+
+/// Reads a DER element with the given tag, returning its contents and the input following it.
+fn expect(input: &[u8], tag: u8) -> Option<(&[u8], &[u8])> {
+    match read(input)? {
+        (actual, contents, rest) if actual == tag => Some((contents, rest)),
+        _ => None,
+    }
+}
+
+/// Reads a DER element, returning its tag, contents, and the input following it. Only single
+/// byte tags and lengths of up to four bytes are supported, which covers any certificate.
+fn read(input: &[u8]) -> Option<(u8, &[u8], &[u8])> {
+    let (&tag, rest) = input.split_first()?;
+    let (&first, mut rest) = rest.split_first()?;
+
+    let len = if first < 0x80 {
+        first as usize
+    } else {
+        let count = (first & 0x7f) as usize;
+        if count == 0 || count > 4 || rest.len() < count {
+            return None;
+        }
+
+        let (bytes, remaining) = rest.split_at(count);
+        rest = remaining;
+        bytes.iter().fold(0, |len, &b| (len << 8) | b as usize)
+    };
+
+    if rest.len() < len {
+        return None;
+    }
+
+    let (contents, rest) = rest.split_at(len);
+    Some((tag, contents, rest))
+}
+
+#[cfg(test)]
+mod test {
+    use super::{subject_names, SubjectNames};
+
+    const CA_CERT: &[u8] = include_bytes!("../certs/ca.der");
+    const CLIENT_CERT: &[u8] = include_bytes!("../certs/client.der");
+
+    #[test]
+    fn reads_common_name_and_dns_names() {
+        assert_eq!(
+            Some(SubjectNames {
+                common_name: Some("rask test client"),
+                dns_names: vec!["client.localhost"],
+            }),
+            subject_names(CLIENT_CERT)
+        );
+    }
+
+    #[test]
+    fn certificate_without_alt_names_has_no_dns_names() {
+        let names = subject_names(CA_CERT).unwrap();
+
+        assert_eq!(Some("rask test CA"), names.common_name);
+        assert!(names.dns_names.is_empty());
+    }
+
+    #[test]
+    fn truncated_certificate_is_rejected() {
+        assert_eq!(None, subject_names(&CLIENT_CERT[..CLIENT_CERT.len() / 2]));
+        assert_eq!(None, subject_names(&[]));
+    }
+}
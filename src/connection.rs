@@ -1,19 +1,95 @@
 //! TODO
 use std::{
-    fmt::Debug,
+    collections::VecDeque,
+    fmt::{Debug, Display},
+    fs::File,
     io::{self, ErrorKind, Read, Result, Write},
     sync::Arc,
+    time::{Duration, Instant},
 };
 
 use mio::{event::Source, Interest, Registry, Token};
-use rustls::{IoState, ServerConfig, ServerConnection};
+use rustls::{pki_types::CertificateDer, IoState, ServerConfig, ServerConnection};
 
+use crate::cert::{subject_names, SubjectNames};
+use crate::extensions::Extensions;
+use crate::parser::h2::Handshake;
 use crate::parser::{
-    h1::{request::H1Request, response::Response},
-    ParseError, ParseResult, Status,
+    h1::{
+        config::ParserConfig,
+        request::H1Request,
+        response::{Body, Response},
+    },
+    status, ParseError, ParseResult, Status, Version,
 };
+use crate::sequencer::ResponseSequencer;
 
-use super::net::tcp_stream::TcpStream;
+use super::net::tcp_stream::{copy_file_to, TcpStream};
+
+/// Protocols offered during TLS ALPN negotiation, in order of preference
+/// [RFC 9113 Section 3.2](https://www.rfc-editor.org/rfc/rfc9113#section-3.2)
+pub const ALPN_PROTOCOLS: [&[u8]; 2] = [b"h2", b"http/1.1"];
+
+/// Configures `config` to offer [`ALPN_PROTOCOLS`], so clients supporting HTTP/2 negotiate it
+/// during the TLS handshake. Connections on which `h2` is negotiated are parsed as HTTP/2, and
+/// all others as HTTP/1.1.
+pub fn with_alpn(mut config: ServerConfig) -> Arc<ServerConfig> {
+    config.alpn_protocols = ALPN_PROTOCOLS.iter().map(|p| p.to_vec()).collect();
+    Arc::new(config)
+}
+
+/// Represents possible failures while servicing a connection
+#[derive(Debug)]
+pub enum ConnectionError {
+    /// I/O failure on the underlying stream.
+    Io(io::Error),
+    /// TLS failure, such as a failed handshake or a malformed record.
+    Tls(rustls::Error),
+    /// The peer sent a request that violates the protocol.
+    Parse(ParseError),
+    /// The peer did not complete an operation in time.
+    Timeout,
+}
+
+impl Display for ConnectionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ConnectionError::Io(err) => f.write_fmt(format_args!("I/O error: {}", err)),
+            ConnectionError::Tls(err) => f.write_fmt(format_args!("TLS error: {}", err)),
+            ConnectionError::Parse(err) => f.write_fmt(format_args!("Parse error: {}", err)),
+            ConnectionError::Timeout => f.write_str("Connection timed out"),
+        }
+    }
+}
+
+impl std::error::Error for ConnectionError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ConnectionError::Io(err) => Some(err),
+            ConnectionError::Tls(err) => Some(err),
+            ConnectionError::Parse(err) => Some(err),
+            ConnectionError::Timeout => None,
+        }
+    }
+}
+
+impl From<io::Error> for ConnectionError {
+    fn from(err: io::Error) -> Self {
+        ConnectionError::Io(err)
+    }
+}
+
+impl From<rustls::Error> for ConnectionError {
+    fn from(err: rustls::Error) -> Self {
+        ConnectionError::Tls(err)
+    }
+}
+
+impl From<ParseError> for ConnectionError {
+    fn from(err: ParseError) -> Self {
+        ConnectionError::Parse(err)
+    }
+}
 
 /// TODO
 #[derive(Debug)]
@@ -32,24 +108,68 @@ where
 pub enum ConnectionVersion {
     /// TODO
     Http11(Option<H1Request>),
-    /// TODO
-    H2,
+    /// HTTP/2, completing the connection preface
+    H2(Handshake),
     /// TODO
     H3,
 }
 
 /// TODO
 pub trait Connection {
-    /// TODO
-    fn read(&mut self) -> Result<()>;
+    /// Reads the data the peer has sent. If the peer closes the connection partway through a
+    /// request, fails with [`ConnectionError::Parse`], as the request can never complete. Nothing
+    /// is read while the response queue is full, see [`Connection::wants_read`].
+    fn read(&mut self) -> std::result::Result<(), ConnectionError>;
     /// TODO
     fn write(&mut self) -> Result<usize>;
-    /// TODO
+    /// Parses the next request received. Each call moves on from the request completed by the
+    /// previous call, consuming its bytes, so a complete request should be answered or handed off
+    /// with its [`Connection::sequence`] before parsing again.
     fn parse(&mut self) -> ParseResult<usize>;
-    /// TODO
+    /// Queues the response to the earliest request received that has not been answered.
     fn prepare_response(&mut self, response: Response);
+    /// Returns the sequence number assigned to the request last parsed, so it can be handed to a
+    /// worker that may finish after those handling later pipelined requests. See
+    /// [`Connection::prepare_response_in_order`]. Returns `None` until the request is complete.
+    fn sequence(&self) -> Option<u64>;
+    /// Queues the response to the request numbered `sequence`. Responses are written in the order
+    /// their requests were received, so a response completed before those to earlier requests is
+    /// held until they have been queued.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `sequence` was not returned by [`Connection::sequence`], or its request has
+    /// already been answered.
+    fn prepare_response_in_order(&mut self, sequence: u64, response: Response);
+    /// Queues already serialized response bytes, such as a
+    /// [`ResponseTemplate`](crate::parser::h1::response::ResponseTemplate), to be written to the
+    /// peer in place of a [`Response`], answering the earliest request that has not been
+    /// answered. As the bytes are not modified, the response to the final request served over the
+    /// connection does not carry `Connection: close`, though the connection still closes once it
+    /// has been written.
+    fn write_raw(&mut self, buf: &[u8]);
+    /// Returns the state attached to this connection, shared by every request it serves.
+    fn extensions(&mut self) -> &mut Extensions;
+    /// Checks the connection's deadlines against `now`. If the body of the current request was
+    /// not received in time, `408 Request Timeout` is written to the peer, the connection is
+    /// closed, and [`ConnectionError::Timeout`] is returned.
+    fn check_timeout(&mut self, now: Instant) -> std::result::Result<(), ConnectionError>;
     /// TODO
     fn is_closed(&self) -> bool;
+    /// Writes as much of the queued responses as the peer accepts before the connection is
+    /// closed, so responses to requests received before the peer closed its side are not lost.
+    /// If writing would block, [`Connection::wants_write`] remains true, and the connection should
+    /// be kept registered until a writable event lets it finish.
+    fn finalize(&mut self) -> Result<()> {
+        self.write().map(|_| ())
+    }
+    /// Returns true if a read was skipped while the response queue was full, and writing has since
+    /// made room. As readiness is reported when it changes, the peer's data may already be
+    /// waiting without a further readable event, so the connection should be read again.
+    fn wants_read(&self) -> bool;
+    /// Returns true if data is queued to be written to the peer, so an event loop can register
+    /// interest in the connection becoming writable only while there is something to write.
+    fn wants_write(&self) -> bool;
     /// TODO
     fn token(&self) -> Token;
     /// TODO
@@ -58,11 +178,445 @@ pub trait Connection {
     fn deregister(&mut self, registry: &Registry) -> Result<()>;
 }
 
+/// Starts the deadline for receiving the body of `request` once its head has been parsed, and
+/// clears it once the request is complete or has failed to parse.
+#[inline]
+fn track_body_deadline(
+    result: &ParseResult<usize>,
+    request: &H1Request,
+    timeout: Option<Duration>,
+    deadline: &mut Option<Instant>,
+) {
+    match (result, timeout) {
+        (Ok(Status::Partial), Some(timeout)) if request.is_head_complete() => {
+            deadline.get_or_insert_with(|| Instant::now() + timeout);
+        }
+        _ => *deadline = None,
+    }
+}
+
+/// Checks the request being received once the peer has closed the connection, failing with
+/// [`ConnectionError::Parse`] if it was cut short. A peer closing between requests is not an
+/// error.
+#[inline]
+fn check_final_request(
+    state: &mut Option<ConnectionVersion>,
+) -> std::result::Result<(), ConnectionError> {
+    if let Some(ConnectionVersion::Http11(Some(request))) = state {
+        request.parse_final()?;
+    }
+
+    Ok(())
+}
+
+/// Interim response asking the client to send the request body it is withholding
+const CONTINUE: &[u8] = b"HTTP/1.1 100 Continue\r\n\r\n";
+
+/// Progress answering the `Expect` header of the request being parsed
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+enum Expectation {
+    /// The request head has not been received yet
+    #[default]
+    Pending,
+    /// `100 Continue` is owed, and is sent once the responses to every earlier request have been
+    /// queued, so it isn't written ahead of them
+    Continue,
+    /// The request carries no expectation, or `100 Continue` has been sent
+    Met,
+    /// The request is answered with `417 Expectation Failed`, and the connection closes once it
+    /// is written
+    Failed,
+}
+
+/// Determines how to answer the `Expect` header of `request` once its head has been parsed.
+/// `100 Continue` is only owed while the client is still withholding the body, and
+/// `417 Expectation Failed` closes the connection, as the client may send the body regardless.
+/// [RFC 9110 Section 10.1.1](https://www.rfc-editor.org/rfc/rfc9110#section-10.1.1)
+#[inline]
+fn expectation_of(result: &ParseResult<usize>, request: &H1Request) -> Expectation {
+    if request.unsupported_expectation() {
+        return Expectation::Failed;
+    }
+
+    match result {
+        Ok(Status::Partial) if request.expects_continue() => Expectation::Continue,
+        _ => Expectation::Met,
+    }
+}
+
+/// Serialized response sent to a request with an expectation that can't be met
+#[inline]
+fn expectation_failed_response() -> Vec<u8> {
+    let mut response =
+        Response::new_with_status_line(Version::H1_1, status::Status::ExpectationFailed);
+    response.set_close(true);
+    response.serialize()
+}
+
+/// Returns true if `queued` responses have reached the limit `max`, so no further requests
+/// should be parsed until they have been written
+#[inline]
+fn is_queue_full(queued: usize, max: Option<usize>) -> bool {
+    max.is_some_and(|max| queued >= max)
+}
+
+/// Returns true if `deadline` has passed as of `now`
+#[inline]
+fn is_expired(deadline: Option<Instant>, now: Instant) -> bool {
+    deadline.is_some_and(|deadline| now >= deadline)
+}
+
+/// Serialized response sent to a peer that failed to send a request body in time
+#[inline]
+fn request_timeout_response() -> Vec<u8> {
+    let mut response =
+        Response::new_with_status_line(Version::H1_1, status::Status::RequestTimeout);
+    response.set_close(true);
+    response.serialize()
+}
+
+/// Response data queued to be written to the peer
+#[derive(Debug)]
+enum Outgoing {
+    /// Serialized bytes, ending a response unless its body follows in a file
+    Bytes {
+        buf: Vec<u8>,
+        written: usize,
+        ends_response: bool,
+    },
+    /// The body of a response, read from a file
+    File { file: File, offset: u64, len: u64 },
+}
+
+impl Outgoing {
+    #[inline]
+    fn is_done(&self) -> bool {
+        match self {
+            Outgoing::Bytes { buf, written, .. } => *written == buf.len(),
+            Outgoing::File { offset, len, .. } => offset == len,
+        }
+    }
+
+    /// Returns true if the response is fully written once this has been written
+    #[inline]
+    fn ends_response(&self) -> bool {
+        match self {
+            Outgoing::Bytes { ends_response, .. } => *ends_response,
+            Outgoing::File { .. } => true,
+        }
+    }
+
+    /// Records `n` more bytes as written, returning true once everything has been written
+    #[inline]
+    fn advance(&mut self, n: usize) -> bool {
+        match self {
+            Outgoing::Bytes { written, .. } => *written += n,
+            Outgoing::File { offset, .. } => *offset += n as u64,
+        }
+
+        self.is_done()
+    }
+}
+
+/// Answer to a request, held until the requests received before it have been answered
+#[derive(Debug)]
+enum Reply {
+    Response(Response),
+    /// Serialized response bytes, see [`Connection::write_raw`]
+    Raw(Vec<u8>),
+}
+
+/// Requests parsed from a connection that are awaiting their responses
+#[derive(Debug, Default)]
+struct Pipeline {
+    sequencer: ResponseSequencer<Reply>,
+    /// Whether the connection persists after the response to each request awaiting one, in the
+    /// order the requests were received
+    keep_alive: VecDeque<bool>,
+    /// Number of further requests served over the connection, or `None` for no limit
+    requests_remaining: Option<usize>,
+    /// Sequence number of the request last parsed
+    parsed: Option<u64>,
+    /// Whether a request after which the connection closes has been parsed
+    final_parsed: bool,
+}
+
+impl Pipeline {
+    /// Assigns the next sequence number to `request` once it has been parsed, recording whether
+    /// the connection persists after its response. The connection closes once the request budget
+    /// is exhausted, or if the request does not permit it to persist.
+    #[inline]
+    fn push(&mut self, request: &H1Request) {
+        let mut keep_alive = request.is_keep_alive();
+        if let Some(remaining) = &mut self.requests_remaining {
+            *remaining = remaining.saturating_sub(1);
+            keep_alive &= *remaining > 0;
+        }
+
+        self.assign(keep_alive);
+    }
+
+    /// Assigns the next sequence number to a request that failed to parse, so the error response
+    /// follows those to earlier requests. The connection closes after it, as the end of the
+    /// request can't be located.
+    #[inline]
+    fn push_failed(&mut self) {
+        self.assign(false);
+    }
+
+    #[inline]
+    fn assign(&mut self, keep_alive: bool) {
+        self.keep_alive.push_back(keep_alive);
+        self.final_parsed |= !keep_alive;
+        self.parsed = Some(self.sequencer.next_sequence());
+    }
+
+    /// Releases the answer to the earliest request awaiting one, if it has been completed, with
+    /// whether the connection persists after it.
+    #[inline]
+    fn pop_ready(&mut self) -> Option<(Reply, bool)> {
+        let reply = self.sequencer.pop_ready()?;
+        Some((reply, self.keep_alive.pop_front().unwrap_or(true)))
+    }
+}
+
+/// Request and response state shared by every transport: the requests parsed from the
+/// connection that await their responses, and the responses queued to be written to the peer
+#[derive(Debug, Default)]
+struct Exchange {
+    parser_config: ParserConfig,
+    body_read_timeout: Option<Duration>,
+    body_deadline: Option<Instant>,
+    max_queued_responses: Option<usize>,
+    /// Number of responses queued that have not been fully written
+    queued_responses: usize,
+    /// Whether reading was skipped while the response queue was full, so it resumes once there is
+    /// room
+    read_deferred: bool,
+    /// Whether the connection closes once the queued responses have been written
+    close_after_write: bool,
+    expectation: Expectation,
+    outgoing: VecDeque<Outgoing>,
+    pipeline: Pipeline,
+}
+
+impl Exchange {
+    /// Returns true if no further request should be parsed, either as the connection is closing,
+    /// or as the queued responses have reached the limit
+    #[inline]
+    fn is_parsing_paused(&self) -> bool {
+        // no further request is processed once the connection is closing
+        // [RFC 9112 Section 9.6](https://www.rfc-editor.org/rfc/rfc9112#section-9.6)
+        self.close_after_write
+            || self.pipeline.final_parsed
+            || is_queue_full(self.queued_responses, self.max_queued_responses)
+    }
+
+    /// Parses the next request received into `request`. If the peer has `closed` the connection,
+    /// the request must be complete with the data already received.
+    fn parse(&mut self, request: &mut H1Request, closed: bool) -> ParseResult<usize> {
+        // the request last parsed is consumed, keeping any pipelined request received after it
+        if self.pipeline.parsed.take().is_some() {
+            request.reset();
+            self.expectation = Expectation::Pending;
+        }
+
+        if closed {
+            let result = request.parse_final();
+            match result {
+                Ok(Status::Complete(_)) => self.pipeline.push(request),
+                Err(_) => self.pipeline.push_failed(),
+                Ok(Status::Partial) => {}
+            }
+            return result;
+        }
+
+        let result = request.parse();
+        if result.is_err() {
+            self.pipeline.push_failed();
+        }
+        track_body_deadline(
+            &result,
+            request,
+            self.body_read_timeout,
+            &mut self.body_deadline,
+        );
+
+        if self.expectation == Expectation::Pending && request.is_head_complete() {
+            self.expectation = expectation_of(&result, request);
+            if self.expectation == Expectation::Failed {
+                // the request is answered in turn, and the connection closes after it
+                self.body_deadline = None;
+                self.pipeline.push_failed();
+                let sequence = self.pipeline.parsed.take();
+                self.answer(sequence, Reply::Raw(expectation_failed_response()));
+            }
+        }
+        match (self.expectation, &result) {
+            (Expectation::Failed, _) => return Ok(Status::Partial),
+            (Expectation::Continue, Ok(Status::Partial)) => self.send_continue(),
+            // the client sent the body without waiting, or it failed to parse
+            (Expectation::Continue, _) => self.expectation = Expectation::Met,
+            _ => {}
+        }
+
+        // requests to upgrade to h2c are served as HTTP/1.1, as the upgrade request would have to
+        // be answered as HTTP/2 stream 1, which isn't supported
+        // [RFC 9110 Section 7.8](https://www.rfc-editor.org/rfc/rfc9110#section-7.8)
+        if let Ok(Status::Complete(_)) = result {
+            self.pipeline.push(request);
+        }
+        result
+    }
+
+    /// Records `reply` as the answer to the request numbered `sequence`, queuing every answer
+    /// that no longer waits on an earlier request. Without a `sequence`, the reply does not
+    /// answer a parsed request, and is queued immediately.
+    fn answer(&mut self, sequence: Option<u64>, reply: Reply) {
+        let Some(sequence) = sequence else {
+            return self.queue_reply(reply, true);
+        };
+
+        self.pipeline.sequencer.complete(sequence, reply);
+        while let Some((reply, keep_alive)) = self.pipeline.pop_ready() {
+            self.queue_reply(reply, keep_alive);
+        }
+        self.send_continue();
+    }
+
+    /// Queues the `100 Continue` owed to the request being received once the responses to every
+    /// earlier request have been queued
+    #[inline]
+    fn send_continue(&mut self) {
+        if self.expectation == Expectation::Continue && self.pipeline.sequencer.in_flight() == 0 {
+            self.expectation = Expectation::Met;
+            self.queue_bytes(CONTINUE.to_vec());
+        }
+    }
+
+    /// Answers the earliest request that has not been answered with `reply`
+    #[inline]
+    fn answer_next(&mut self, reply: Reply) {
+        let sequence = self.pipeline.sequencer.next_unanswered();
+        self.answer(sequence, reply);
+    }
+
+    #[inline]
+    fn queue_reply(&mut self, reply: Reply, keep_alive: bool) {
+        match reply {
+            Reply::Response(mut response) => {
+                if !keep_alive {
+                    response.set_close(true);
+                }
+                self.close_after_write |= response.is_close();
+                let head = response.serialize();
+
+                match response.into_body() {
+                    Body::File { file, len } => {
+                        self.push_outgoing(Outgoing::Bytes {
+                            buf: head,
+                            written: 0,
+                            ends_response: false,
+                        });
+                        self.queue_file(file, len);
+                    }
+                    _ => self.queue_bytes(head),
+                }
+            }
+            Reply::Raw(buf) => {
+                self.close_after_write |= !keep_alive;
+                self.queue_bytes(buf);
+            }
+        }
+    }
+
+    /// Queues `buf` to be written. Each transport hands the bytes to the peer as it accepts them
+    /// in `write`.
+    #[inline]
+    fn queue_bytes(&mut self, buf: Vec<u8>) {
+        self.push_outgoing(Outgoing::Bytes {
+            buf,
+            written: 0,
+            ends_response: true,
+        });
+    }
+
+    /// Queues `len` bytes of `file` to follow the response head. Plaintext connections send it
+    /// with [`TcpStream::send_file`] so the file isn't copied through userspace where supported,
+    /// while TLS connections read it through a buffer in chunks as the session drains, as it must
+    /// be encrypted.
+    #[inline]
+    fn queue_file(&mut self, file: File, len: u64) {
+        self.push_outgoing(Outgoing::File {
+            file,
+            offset: 0,
+            len,
+        });
+    }
+
+    /// Queues `outgoing`, counting every response queued, including interim responses, against
+    /// the limit
+    #[inline]
+    fn push_outgoing(&mut self, outgoing: Outgoing) {
+        if outgoing.ends_response() {
+            self.queued_responses += 1;
+        }
+        self.outgoing.push_back(outgoing);
+    }
+
+    /// Removes the data at the front of the queue once it has been written, making room for a
+    /// further response once one has been written in full
+    #[inline]
+    fn pop_outgoing(&mut self) {
+        if self.outgoing.pop_front().is_some_and(|o| o.ends_response()) {
+            self.queued_responses -= 1;
+        }
+    }
+
+    /// Returns true if reading should be skipped as the response queue is full, so a peer that
+    /// isn't reading its responses can't make the connection buffer its requests without bound
+    #[inline]
+    fn defer_read(&mut self) -> bool {
+        self.read_deferred = is_queue_full(self.queued_responses, self.max_queued_responses);
+        self.read_deferred
+    }
+
+    /// Returns true if reading was skipped while the response queue was full, and there is now
+    /// room
+    #[inline]
+    fn wants_read(&self) -> bool {
+        self.read_deferred && !is_queue_full(self.queued_responses, self.max_queued_responses)
+    }
+
+    /// Queues `408 Request Timeout` if the body of the current request was not received by
+    /// `now`, returning true if so
+    #[inline]
+    fn expire_body(&mut self, now: Instant) -> bool {
+        if !is_expired(self.body_deadline, now) {
+            return false;
+        }
+
+        self.body_deadline = None;
+        self.queue_bytes(request_timeout_response());
+        true
+    }
+
+    /// Returns true if the connection should close, as the final response has been written
+    #[inline]
+    fn is_finished(&self) -> bool {
+        self.close_after_write && self.outgoing.is_empty()
+    }
+}
+
 /// TODO
 #[derive(Debug)]
 pub struct ConnectionBuilder<S> {
     stream: S,
     token: Token,
+    max_requests: Option<usize>,
+    parser_config: ParserConfig,
+    body_read_timeout: Option<Duration>,
+    max_queued_responses: Option<usize>,
 }
 
 impl<S> ConnectionBuilder<S>
@@ -71,17 +625,69 @@ where
 {
     /// TODO
     pub fn new(stream: S, token: Token) -> Self {
-        Self { stream, token }
+        Self {
+            stream,
+            token,
+            max_requests: None,
+            parser_config: ParserConfig::default(),
+            body_read_timeout: None,
+            max_queued_responses: None,
+        }
+    }
+
+    /// Limits the number of requests served over the connection. The response to the final
+    /// request carries `Connection: close`, and the connection is closed once it has been written.
+    pub fn with_max_requests(mut self, max_requests: usize) -> Self {
+        self.max_requests = Some(max_requests);
+        self
+    }
+
+    /// Sets the configuration every request received over the connection is parsed with
+    pub fn with_parser_config(mut self, parser_config: ParserConfig) -> Self {
+        self.parser_config = parser_config;
+        self
+    }
+
+    /// Limits the time allowed to receive the body of a request once its head has been received.
+    /// A peer that exceeds it is sent `408 Request Timeout` and disconnected, protecting against
+    /// clients that send the body slowly to hold the connection open.
+    pub fn with_body_read_timeout(mut self, timeout: Duration) -> Self {
+        self.body_read_timeout = Some(timeout);
+        self
+    }
+
+    /// Limits the number of responses queued for a peer that is not reading them. Once reached, no
+    /// further requests are read or parsed until a queued response has been written, so a client
+    /// pipelining requests without reading the responses cannot make the server buffer them
+    /// without bound. Interim responses, such as `100 Continue`, count against the limit.
+    pub fn with_max_queued_responses(mut self, max_queued_responses: usize) -> Self {
+        self.max_queued_responses = Some(max_queued_responses);
+        self
     }
 
     /// TODO
     pub fn with_plaintext(self) -> PlaintextConnectionBuilder<S> {
-        PlaintextConnectionBuilder::new(self.stream, self.token)
+        PlaintextConnectionBuilder::new(
+            self.stream,
+            self.token,
+            self.max_requests,
+            self.parser_config,
+            self.body_read_timeout,
+            self.max_queued_responses,
+        )
     }
 
     /// TODO
     pub fn with_tls(self, config: Arc<ServerConfig>) -> TlsConnectionBuilder<S> {
-        TlsConnectionBuilder::new(self.stream, self.token, config)
+        TlsConnectionBuilder::new(
+            self.stream,
+            self.token,
+            self.max_requests,
+            self.parser_config,
+            self.body_read_timeout,
+            self.max_queued_responses,
+            config,
+        )
     }
 }
 
@@ -90,19 +696,42 @@ where
 pub struct PlaintextConnectionBuilder<S> {
     stream: S,
     token: Token,
+    max_requests: Option<usize>,
+    parser_config: ParserConfig,
+    body_read_timeout: Option<Duration>,
+    max_queued_responses: Option<usize>,
 }
 
 impl<S> PlaintextConnectionBuilder<S>
 where
     S: TcpStream + Read + Write + Source,
 {
-    fn new(stream: S, token: Token) -> Self {
-        PlaintextConnectionBuilder { stream, token }
+    fn new(
+        stream: S,
+        token: Token,
+        max_requests: Option<usize>,
+        parser_config: ParserConfig,
+        body_read_timeout: Option<Duration>,
+        max_queued_responses: Option<usize>,
+    ) -> Self {
+        PlaintextConnectionBuilder {
+            stream,
+            token,
+            max_requests,
+            parser_config,
+            body_read_timeout,
+            max_queued_responses,
+        }
     }
 
     /// TODO
     pub fn build(self) -> PlainConnection<S> {
-        PlainConnection::new(self.token, self.stream)
+        let mut connection = PlainConnection::new(self.token, self.stream);
+        connection.exchange.pipeline.requests_remaining = self.max_requests;
+        connection.exchange.parser_config = self.parser_config;
+        connection.exchange.body_read_timeout = self.body_read_timeout;
+        connection.exchange.max_queued_responses = self.max_queued_responses;
+        connection
     }
 }
 
@@ -111,6 +740,10 @@ where
 pub struct TlsConnectionBuilder<S> {
     stream: S,
     token: Token,
+    max_requests: Option<usize>,
+    parser_config: ParserConfig,
+    body_read_timeout: Option<Duration>,
+    max_queued_responses: Option<usize>,
     config: Arc<ServerConfig>,
 }
 
@@ -118,18 +751,62 @@ impl<S> TlsConnectionBuilder<S>
 where
     S: TcpStream + Read + Write + Source,
 {
-    fn new(stream: S, token: Token, config: Arc<ServerConfig>) -> Self {
+    fn new(
+        stream: S,
+        token: Token,
+        max_requests: Option<usize>,
+        parser_config: ParserConfig,
+        body_read_timeout: Option<Duration>,
+        max_queued_responses: Option<usize>,
+        config: Arc<ServerConfig>,
+    ) -> Self {
         TlsConnectionBuilder {
             stream,
             token,
+            max_requests,
+            parser_config,
+            body_read_timeout,
+            max_queued_responses,
             config,
         }
     }
 
+    /// Offers `protocols` during ALPN negotiation in place of those of the server configuration.
+    /// As the configuration is shared between connections, it is copied; prefer configuring it
+    /// once with [`with_alpn`] when every connection offers the same protocols.
+    pub fn with_alpn_protocols(mut self, protocols: &[&[u8]]) -> Self {
+        let mut config = (*self.config).clone();
+        config.alpn_protocols = protocols.iter().map(|p| p.to_vec()).collect();
+        self.config = Arc::new(config);
+        self
+    }
+
     /// TODO
     pub fn build(self) -> std::result::Result<TlsConnection<S>, rustls::Error> {
-        let tls = ServerConnection::new(self.config)?;
-        Ok(TlsConnection::new(self.token, self.stream, tls))
+        let tls = Box::new(ServerConnection::new(self.config.clone())?);
+        Ok(self.finish(tls))
+    }
+
+    /// Builds the connection, storing its TLS session in an allocation reclaimed from a closed
+    /// connection with [`TlsConnection::into_tls_allocation`]. As rustls sessions cannot be
+    /// reset, a new session is created and moved into the existing allocation, replacing the
+    /// previous session.
+    pub fn build_in(
+        self,
+        mut tls: Box<ServerConnection>,
+    ) -> std::result::Result<TlsConnection<S>, rustls::Error> {
+        *tls = ServerConnection::new(self.config.clone())?;
+        Ok(self.finish(tls))
+    }
+
+    #[inline]
+    fn finish(self, tls: Box<ServerConnection>) -> TlsConnection<S> {
+        let mut connection = TlsConnection::with_boxed_tls(self.token, self.stream, tls);
+        connection.exchange.pipeline.requests_remaining = self.max_requests;
+        connection.exchange.parser_config = self.parser_config;
+        connection.exchange.body_read_timeout = self.body_read_timeout;
+        connection.exchange.max_queued_responses = self.max_queued_responses;
+        connection
     }
 }
 
@@ -142,7 +819,8 @@ where
     stream: S,
     token: Token,
     closed: bool,
-    responses: Vec<Response>,
+    exchange: Exchange,
+    extensions: Extensions,
     /// TODO
     pub state: Option<ConnectionVersion>,
 }
@@ -157,10 +835,17 @@ where
             stream,
             token,
             closed: false,
-            responses: Vec::default(),
+            exchange: Exchange::default(),
+            extensions: Extensions::default(),
             state: None,
         }
     }
+
+    /// Returns the underlying stream, so tests outside this module can feed it data
+    #[cfg(test)]
+    pub(crate) fn stream_mut(&mut self) -> &mut S {
+        &mut self.stream
+    }
 }
 
 impl<S> Connection for PlainConnection<S>
@@ -168,7 +853,14 @@ where
     S: TcpStream + Read + Write + Source,
 {
     #[inline]
-    fn read(&mut self) -> Result<()> {
+    fn read(&mut self) -> std::result::Result<(), ConnectionError> {
+        if self.exchange.body_deadline.is_some() {
+            self.check_timeout(Instant::now())?;
+        }
+        if self.exchange.defer_read() {
+            return Ok(());
+        }
+
         let mut done = false;
 
         if self.state.is_none() {
@@ -177,9 +869,11 @@ where
 
             self.state = if self.stream.peek(&mut preface_buf).is_ok() && preface_buf == H2_PREFACE
             {
-                Some(ConnectionVersion::H2)
+                Some(ConnectionVersion::H2(Handshake::new()))
             } else {
-                Some(ConnectionVersion::Http11(Some(H1Request::default())))
+                Some(ConnectionVersion::Http11(Some(H1Request::with_config(
+                    self.exchange.parser_config,
+                ))))
             };
         }
 
@@ -189,18 +883,19 @@ where
                     request.fill(&mut self.stream)? == 0
                 }
                 ConnectionVersion::Http11(None) => {
-                    let mut request = H1Request::default();
+                    let mut request = H1Request::with_config(self.exchange.parser_config);
                     let done = request.fill(&mut self.stream)? == 0;
                     self.state = Some(ConnectionVersion::Http11(Some(request)));
                     done
                 }
-                ConnectionVersion::H2 => true,
+                ConnectionVersion::H2(ref mut handshake) => handshake.fill(&mut self.stream)? == 0,
                 ConnectionVersion::H3 => true,
             }
         }
 
         if done {
             self.closed = true;
+            check_final_request(&mut self.state)?;
         }
 
         Ok(())
@@ -209,26 +904,57 @@ where
     #[inline]
     fn write(&mut self) -> io::Result<usize> {
         let mut total = 0;
-        for response in &self.responses {
-            let write_buf = response.get_serialized();
-            total += write_buf.as_bytes().len();
-            self.stream.write_all(write_buf.as_bytes())?;
-            self.stream.flush()?;
+        while let Some(outgoing) = self.exchange.outgoing.front_mut() {
+            if outgoing.is_done() {
+                self.exchange.pop_outgoing();
+                continue;
+            }
+
+            let result = match outgoing {
+                Outgoing::Bytes { buf, written, .. } => self.stream.write(&buf[*written..]),
+                Outgoing::File { file, offset, len } => {
+                    let count = usize::try_from(*len - *offset).unwrap_or(usize::MAX);
+                    self.stream.send_file(file, *offset, count)
+                }
+            };
+
+            match result {
+                Ok(0) => return Err(ErrorKind::WriteZero.into()),
+                Ok(n) => {
+                    total += n;
+                    if outgoing.advance(n) {
+                        self.exchange.pop_outgoing();
+                    }
+                }
+                Err(e) if e.kind() == ErrorKind::WouldBlock => break,
+                Err(e) if e.kind() == ErrorKind::Interrupted => {}
+                Err(e) => return Err(e),
+            }
         }
 
-        self.responses.clear();
+        self.stream.flush()?;
+
+        if self.exchange.is_finished() {
+            self.closed = true;
+        }
 
         Ok(total)
     }
 
     fn parse(&mut self) -> ParseResult<usize> {
+        if self.exchange.is_parsing_paused() {
+            return Ok(Status::Partial);
+        }
+
         if let Some(ref mut state) = self.state {
             match state {
-                ConnectionVersion::Http11(Some(ref mut request)) => request.parse(),
+                ConnectionVersion::Http11(Some(ref mut request)) => {
+                    self.exchange.parse(request, self.closed)
+                }
                 ConnectionVersion::Http11(None) => {
                     panic!("Tried to parse on connection with no request")
                 }
-                ConnectionVersion::H2 => Ok(Status::Partial),
+                ConnectionVersion::H2(ref mut handshake) => handshake.parse(),
                 ConnectionVersion::H3 => Ok(Status::Partial),
             }
         } else {
@@ -238,19 +964,56 @@ where
 
     #[inline]
     fn prepare_response(&mut self, response: Response) {
-        self.responses.push(response);
-        self.state = match self.state {
-            Some(ConnectionVersion::Http11(_)) => Some(ConnectionVersion::Http11(None)),
-            Some(ConnectionVersion::H2) => Some(ConnectionVersion::H2),
-            Some(ConnectionVersion::H3) => Some(ConnectionVersion::H3),
-            None => None,
-        };
+        self.exchange.answer_next(Reply::Response(response));
+    }
+
+    #[inline]
+    fn sequence(&self) -> Option<u64> {
+        self.exchange.pipeline.parsed
+    }
+
+    #[inline]
+    fn prepare_response_in_order(&mut self, sequence: u64, response: Response) {
+        self.exchange
+            .answer(Some(sequence), Reply::Response(response));
+    }
+
+    #[inline]
+    fn write_raw(&mut self, buf: &[u8]) {
+        self.exchange.answer_next(Reply::Raw(buf.to_vec()));
     }
 
     fn is_closed(&self) -> bool {
         self.closed
     }
 
+    #[inline]
+    fn wants_read(&self) -> bool {
+        self.exchange.wants_read()
+    }
+
+    #[inline]
+    fn wants_write(&self) -> bool {
+        !self.exchange.outgoing.is_empty()
+    }
+
+    #[inline]
+    fn extensions(&mut self) -> &mut Extensions {
+        &mut self.extensions
+    }
+
+    fn check_timeout(&mut self, now: Instant) -> std::result::Result<(), ConnectionError> {
+        if !self.exchange.expire_body(now) {
+            return Ok(());
+        }
+
+        // the connection closes whether or not the peer receives the response
+        let _ = self.write();
+        self.closed = true;
+
+        Err(ConnectionError::Timeout)
+    }
+
     #[inline]
     fn register(&mut self, registry: &Registry) -> Result<()> {
         registry.register(
@@ -280,6 +1043,8 @@ where
     tls: Box<ServerConnection>,
     token: Token,
     closed: bool,
+    exchange: Exchange,
+    extensions: Extensions,
     /// TODO
     pub state: Option<ConnectionVersion>,
 }
@@ -290,30 +1055,59 @@ where
 {
     /// TODO
     pub fn new(token: Token, stream: S, tls: ServerConnection) -> Self {
+        Self::with_boxed_tls(token, stream, Box::new(tls))
+    }
+
+    #[inline]
+    fn with_boxed_tls(token: Token, stream: S, tls: Box<ServerConnection>) -> Self {
         Self {
             stream,
-            tls: Box::new(tls),
+            tls,
             token,
             closed: false,
+            exchange: Exchange::default(),
+            extensions: Extensions::default(),
             state: None,
         }
     }
 
+    /// Consumes the connection, closing its stream and returning the allocation holding its TLS
+    /// session. It can be passed to [`TlsConnectionBuilder::build_in`] to avoid allocating a new
+    /// session for the next connection accepted.
+    pub fn into_tls_allocation(self) -> Box<ServerConnection> {
+        self.tls
+    }
+
+    /// Returns the certificate chain presented by the client, leaf first, once the handshake has
+    /// completed. Clients only present certificates when requested by the server's
+    /// [`ServerConfig`], such as through a
+    /// [`WebPkiClientVerifier`](rustls::server::WebPkiClientVerifier).
+    pub fn peer_certificates(&self) -> Option<&[CertificateDer<'static>]> {
+        if self.tls.is_handshaking() {
+            return None;
+        }
+
+        self.tls.peer_certificates()
+    }
+
+    /// Returns the subject names of the client's certificate, for logging and authorization
+    /// decisions. See [`subject_names`].
+    pub fn peer_subject(&self) -> Option<SubjectNames<'_>> {
+        subject_names(self.peer_certificates()?.first()?)
+    }
+
+    /// Reads the TLS records the peer has sent, returning the number of bytes read, and whether
+    /// the peer has closed the connection. Records read before the peer closed it must still be
+    /// processed.
     #[inline]
-    fn read_tls(&mut self) -> Result<usize> {
+    fn read_tls(&mut self) -> Result<(usize, bool)> {
         let mut read = 0;
         loop {
             match self.tls.read_tls(&mut self.stream) {
-                Ok(0) => return Ok(0),
+                Ok(0) => return Ok((read, true)),
                 Ok(n) => read += n,
                 Err(e) => match e.kind() {
-                    ErrorKind::WouldBlock => {
-                        if read == 0 {
-                            return Err(e);
-                        } else {
-                            return Ok(read);
-                        }
-                    }
+                    ErrorKind::WouldBlock => return Ok((read, false)),
                     ErrorKind::Interrupted => {}
                     _ => return Err(e),
                 },
@@ -321,6 +1115,33 @@ where
         }
     }
 
+    /// Processes the TLS records read, reading the plaintext they carry into the request being
+    /// received. The TLS session closes the connection if a record is invalid.
+    #[inline]
+    fn process_tls(&mut self) -> std::result::Result<(), ConnectionError> {
+        let tls_state = match self.tls.process_new_packets() {
+            Ok(tls_state) => tls_state,
+            Err(err) => {
+                self.closed = true;
+                return Err(ConnectionError::Tls(err));
+            }
+        };
+
+        // the protocol is known once the handshake completes, before any application data
+        if self.state.is_none() && !self.tls.is_handshaking() {
+            self.state = match self.tls.alpn_protocol() {
+                Some(b"h2") => Some(ConnectionVersion::H2(Handshake::new())),
+                _ => Some(ConnectionVersion::Http11(Some(H1Request::with_config(
+                    self.exchange.parser_config,
+                )))),
+            };
+        }
+
+        self.read_plaintext(tls_state)?;
+
+        Ok(())
+    }
+
     #[inline]
     fn read_plaintext(&mut self, tls_state: IoState) -> Result<()> {
         if tls_state.plaintext_bytes_to_read() > 0 {
@@ -329,9 +1150,16 @@ where
                     ConnectionVersion::Http11(Some(ref mut request)) => request
                         .fill_exact(&mut self.tls.reader(), tls_state.plaintext_bytes_to_read()),
                     ConnectionVersion::Http11(None) => {
-                        panic!("Tried to read on connection with no request")
+                        let mut request = H1Request::with_config(self.exchange.parser_config);
+                        request.fill_exact(
+                            &mut self.tls.reader(),
+                            tls_state.plaintext_bytes_to_read(),
+                        )?;
+                        *state = ConnectionVersion::Http11(Some(request));
+                        Ok(())
                     }
-                    ConnectionVersion::H2 => Ok(()),
+                    ConnectionVersion::H2(ref mut handshake) => handshake
+                        .fill_exact(&mut self.tls.reader(), tls_state.plaintext_bytes_to_read()),
                     ConnectionVersion::H3 => Ok(()),
                 };
             }
@@ -346,30 +1174,22 @@ where
     S: TcpStream + Read + Write + Source,
 {
     #[inline]
-    fn read(&mut self) -> Result<()> {
-        if self.state.is_none() {
-            if let Some(protos) = self.tls.alpn_protocol() {
-                if protos.windows(2).any(|w| w == b"h2") {
-                    self.state = Some(ConnectionVersion::H2);
-                }
-            }
-
-            if self.state.is_none() {
-                self.state = Some(ConnectionVersion::Http11(Some(H1Request::default())));
-            }
+    fn read(&mut self) -> std::result::Result<(), ConnectionError> {
+        if self.exchange.body_deadline.is_some() {
+            self.check_timeout(Instant::now())?;
+        }
+        if self.exchange.defer_read() {
+            return Ok(());
         }
 
-        let mut done = self.read_tls()? == 0;
-
-        if !done {
-            match self.tls.process_new_packets() {
-                Ok(tls_state) => self.read_plaintext(tls_state)?,
-                Err(_) => done = true,
-            }
+        let (read, eof) = self.read_tls()?;
+        if read > 0 {
+            self.process_tls()?;
         }
 
-        if done {
+        if eof {
             self.closed = true;
+            return check_final_request(&mut self.state);
         }
 
         Ok(())
@@ -377,37 +1197,126 @@ where
 
     #[inline]
     fn write(&mut self) -> io::Result<usize> {
-        // TODO: this may be supressing errors
-        self.tls.write_tls(&mut self.stream)
+        let mut total = 0;
+        loop {
+            while self.tls.wants_write() {
+                match self.tls.write_tls(&mut self.stream) {
+                    Ok(n) => total += n,
+                    Err(e) if e.kind() == ErrorKind::WouldBlock => return Ok(total),
+                    Err(e) if e.kind() == ErrorKind::Interrupted => {}
+                    Err(e) => return Err(e),
+                }
+            }
+
+            let Some(outgoing) = self.exchange.outgoing.front_mut() else {
+                break;
+            };
+
+            if outgoing.is_done() {
+                self.exchange.pop_outgoing();
+                continue;
+            }
+
+            let accepted = match outgoing {
+                Outgoing::Bytes { buf, written, .. } => {
+                    self.tls.writer().write(&buf[*written..])?
+                }
+                Outgoing::File { file, offset, len } => {
+                    let count = usize::try_from(*len - *offset).unwrap_or(usize::MAX);
+                    copy_file_to(&mut self.tls.writer(), file, *offset, count)?
+                }
+            };
+
+            if accepted == 0 {
+                break;
+            }
+
+            if outgoing.advance(accepted) {
+                self.exchange.pop_outgoing();
+            }
+        }
+
+        if self.exchange.is_finished() {
+            self.closed = true;
+        }
+
+        Ok(total)
     }
 
     fn parse(&mut self) -> ParseResult<usize> {
+        if self.exchange.is_parsing_paused() {
+            return Ok(Status::Partial);
+        }
+
         if let Some(ref mut state) = self.state {
             match state {
-                ConnectionVersion::Http11(Some(ref mut request)) => request.parse(),
+                ConnectionVersion::Http11(Some(ref mut request)) => {
+                    self.exchange.parse(request, self.closed)
+                }
                 ConnectionVersion::Http11(None) => {
                     panic!("Tried to parse on connection with no request")
                 }
-                ConnectionVersion::H2 => Ok(Status::Partial),
+                ConnectionVersion::H2(ref mut handshake) => handshake.parse(),
                 ConnectionVersion::H3 => Ok(Status::Partial),
             }
         } else {
-            Err(ParseError::Method)
+            // the TLS handshake has not completed
+            Ok(Status::Partial)
         }
     }
 
     #[inline]
     fn prepare_response(&mut self, response: Response) {
-        self.tls
-            .writer()
-            .write_all(response.get_serialized().as_bytes())
-            .unwrap();
+        self.exchange.answer_next(Reply::Response(response));
+    }
+
+    #[inline]
+    fn sequence(&self) -> Option<u64> {
+        self.exchange.pipeline.parsed
+    }
+
+    #[inline]
+    fn prepare_response_in_order(&mut self, sequence: u64, response: Response) {
+        self.exchange
+            .answer(Some(sequence), Reply::Response(response));
+    }
+
+    #[inline]
+    fn write_raw(&mut self, buf: &[u8]) {
+        self.exchange.answer_next(Reply::Raw(buf.to_vec()));
     }
 
     fn is_closed(&self) -> bool {
         self.closed
     }
 
+    #[inline]
+    fn wants_read(&self) -> bool {
+        self.exchange.wants_read()
+    }
+
+    #[inline]
+    fn wants_write(&self) -> bool {
+        !self.exchange.outgoing.is_empty() || self.tls.wants_write()
+    }
+
+    #[inline]
+    fn extensions(&mut self) -> &mut Extensions {
+        &mut self.extensions
+    }
+
+    fn check_timeout(&mut self, now: Instant) -> std::result::Result<(), ConnectionError> {
+        if !self.exchange.expire_body(now) {
+            return Ok(());
+        }
+
+        // the connection closes whether or not the peer receives the response
+        let _ = self.write();
+        self.closed = true;
+
+        Err(ConnectionError::Timeout)
+    }
+
     #[inline]
     fn register(&mut self, registry: &Registry) -> Result<()> {
         registry.register(
@@ -426,3 +1335,1122 @@ where
         self.token
     }
 }
+
+#[cfg(test)]
+mod test {
+    use std::error::Error;
+    use std::fs;
+    use std::io::{self, ErrorKind, Read, Write};
+    use std::sync::Arc;
+    use std::time::{Duration, Instant};
+
+    use mio::Token;
+    use rustls::pki_types::{CertificateDer, PrivateKeyDer, PrivatePkcs8KeyDer, ServerName};
+    use rustls::server::{ResolvesServerCertUsingSni, WebPkiClientVerifier};
+    use rustls::{ClientConfig, ClientConnection, RootCertStore, ServerConfig, ServerConnection};
+
+    use super::{
+        with_alpn, Connection, ConnectionBuilder, ConnectionError, ConnectionVersion,
+        PlainConnection, TlsConnection, ALPN_PROTOCOLS, CONTINUE,
+    };
+    use crate::net::mock_stream::MockStream;
+    use crate::parser::h1::response::{Response, ResponseTemplate};
+    use crate::parser::h2::CONNECTION_PREFACE;
+    use crate::parser::{status::Status, ParseError, Status as ParseStatus, Version};
+
+    fn tls_config() -> Arc<ServerConfig> {
+        // the handshake never completes in these tests, so no certificate is required
+        Arc::new(
+            ServerConfig::builder()
+                .with_no_client_auth()
+                .with_cert_resolver(Arc::new(ResolvesServerCertUsingSni::new())),
+        )
+    }
+
+    #[test]
+    fn connection_error_converts_from_io_error() {
+        let err = ConnectionError::from(io::Error::from(ErrorKind::ConnectionReset));
+
+        assert!(
+            matches!(err, ConnectionError::Io(ref e) if e.kind() == ErrorKind::ConnectionReset)
+        );
+        assert!(err.source().is_some());
+    }
+
+    #[test]
+    fn connection_error_converts_from_tls_error() {
+        let err = ConnectionError::from(rustls::Error::DecryptError);
+
+        assert!(matches!(
+            err,
+            ConnectionError::Tls(rustls::Error::DecryptError)
+        ));
+        assert!(err.source().is_some());
+    }
+
+    #[test]
+    fn connection_error_converts_from_parse_error() {
+        let err = ConnectionError::from(ParseError::Method);
+
+        assert!(matches!(err, ConnectionError::Parse(ParseError::Method)));
+        assert_eq!("Parse error: Invalid token in method", err.to_string());
+    }
+
+    #[test]
+    fn connection_error_timeout_has_no_source() {
+        let err = ConnectionError::Timeout;
+
+        assert!(err.source().is_none());
+        assert_eq!("Connection timed out", err.to_string());
+    }
+
+    #[test]
+    fn plain_read_reports_io_errors() {
+        let stream = MockStream::new();
+        let mut connection = ConnectionBuilder::new(stream, Token(0))
+            .with_plaintext()
+            .build();
+
+        let err = connection.read().unwrap_err();
+
+        assert!(matches!(err, ConnectionError::Io(ref e) if e.kind() == ErrorKind::WouldBlock));
+    }
+
+    #[test]
+    fn tls_read_reports_tls_errors_and_closes() {
+        let mut stream = MockStream::new();
+        stream.push(b"GET / HTTP/1.1\r\n\r\n");
+        let mut connection = ConnectionBuilder::new(stream, Token(0))
+            .with_tls(tls_config())
+            .build()
+            .unwrap();
+
+        let err = connection.read().unwrap_err();
+
+        assert!(matches!(err, ConnectionError::Tls(_)));
+        assert!(connection.is_closed());
+    }
+
+    const CA_CERT: &[u8] = include_bytes!("../certs/ca.der");
+    const SERVER_CERT: &[u8] = include_bytes!("../certs/server.der");
+    const SERVER_KEY: &[u8] = include_bytes!("../certs/server.key.der");
+    const CLIENT_CERT: &[u8] = include_bytes!("../certs/client.der");
+    const CLIENT_KEY: &[u8] = include_bytes!("../certs/client.key.der");
+
+    fn roots() -> RootCertStore {
+        let mut roots = RootCertStore::empty();
+        roots.add(CertificateDer::from(CA_CERT)).unwrap();
+        roots
+    }
+
+    fn key(der: &'static [u8]) -> PrivateKeyDer<'static> {
+        PrivateKeyDer::Pkcs8(PrivatePkcs8KeyDer::from(der))
+    }
+
+    /// Exchanges records between `connection` and `client` until the TLS handshake completes
+    fn complete_handshake(
+        connection: &mut TlsConnection<MockStream>,
+        client: &mut ClientConnection,
+    ) {
+        while client.is_handshaking() || connection.tls.is_handshaking() {
+            send_to_server(connection, client);
+            connection.write().unwrap();
+            let records = std::mem::take(&mut connection.stream.written);
+            client.read_tls(&mut records.as_slice()).unwrap();
+            client.process_new_packets().unwrap();
+        }
+    }
+
+    /// Delivers the records `client` wants to write to `connection`, and reads them
+    fn send_to_server(connection: &mut TlsConnection<MockStream>, client: &mut ClientConnection) {
+        let mut records = Vec::new();
+        while client.wants_write() {
+            client.write_tls(&mut records).unwrap();
+        }
+        if !records.is_empty() {
+            connection.stream.push(&records);
+            connection.read().unwrap();
+        }
+    }
+
+    fn alpn_client(protocols: &[&[u8]]) -> ClientConnection {
+        let mut config = ClientConfig::builder()
+            .with_root_certificates(roots())
+            .with_no_client_auth();
+        config.alpn_protocols = protocols.iter().map(|p| p.to_vec()).collect();
+
+        ClientConnection::new(Arc::new(config), ServerName::try_from("localhost").unwrap()).unwrap()
+    }
+
+    fn alpn_server_config() -> Arc<ServerConfig> {
+        with_alpn(
+            ServerConfig::builder()
+                .with_no_client_auth()
+                .with_single_cert(vec![CertificateDer::from(SERVER_CERT)], key(SERVER_KEY))
+                .unwrap(),
+        )
+    }
+
+    #[test]
+    fn tls_alpn_h2_selects_h2_parser() {
+        let mut connection = ConnectionBuilder::new(MockStream::new(), Token(0))
+            .with_tls(alpn_server_config())
+            .build()
+            .unwrap();
+        let mut client = alpn_client(&ALPN_PROTOCOLS);
+
+        complete_handshake(&mut connection, &mut client);
+        assert_eq!(Some(&b"h2"[..]), client.alpn_protocol());
+
+        // preface, then an empty SETTINGS frame
+        client.writer().write_all(CONNECTION_PREFACE).unwrap();
+        client
+            .writer()
+            .write_all(&[0, 0, 0, 0x4, 0, 0, 0, 0, 0])
+            .unwrap();
+        send_to_server(&mut connection, &mut client);
+
+        assert!(matches!(connection.state, Some(ConnectionVersion::H2(_))));
+        assert_eq!(Ok(ParseStatus::Complete(33)), connection.parse());
+    }
+
+    #[test]
+    fn tls_alpn_http_1_1_selects_h1_parser() {
+        const REQ: &[u8] = b"GET / HTTP/1.1\r\nHost: localhost\r\n\r\n";
+        for protocols in [&[&b"http/1.1"[..]][..], &[]] {
+            let mut connection = ConnectionBuilder::new(MockStream::new(), Token(0))
+                .with_tls(alpn_server_config())
+                .build()
+                .unwrap();
+            let mut client = alpn_client(protocols);
+
+            complete_handshake(&mut connection, &mut client);
+            assert_eq!(Ok(ParseStatus::Partial), connection.parse());
+
+            client.writer().write_all(REQ).unwrap();
+            send_to_server(&mut connection, &mut client);
+
+            assert!(matches!(
+                connection.state,
+                Some(ConnectionVersion::Http11(Some(_)))
+            ));
+            assert_eq!(Ok(ParseStatus::Complete(REQ.len())), connection.parse());
+        }
+    }
+
+    #[test]
+    fn tls_request_read_into_connection_without_request() {
+        const REQ: &[u8] = b"GET / HTTP/1.1\r\nHost: localhost\r\n\r\n";
+        let mut connection = ConnectionBuilder::new(MockStream::new(), Token(0))
+            .with_tls(alpn_server_config())
+            .build()
+            .unwrap();
+        let mut client = alpn_client(&[b"http/1.1"]);
+        complete_handshake(&mut connection, &mut client);
+        connection.state = Some(ConnectionVersion::Http11(None));
+
+        client.writer().write_all(REQ).unwrap();
+        send_to_server(&mut connection, &mut client);
+
+        assert_eq!(Ok(ParseStatus::Complete(REQ.len())), connection.parse());
+    }
+
+    #[test]
+    fn tls_request_spanning_records_is_appended() {
+        const HEAD: &[u8] = b"POST / HTTP/1.1\r\nHost: localhost\r\nContent-Length: 5\r\n\r\n";
+        let mut connection = ConnectionBuilder::new(MockStream::new(), Token(0))
+            .with_tls(alpn_server_config())
+            .build()
+            .unwrap();
+        let mut client = alpn_client(&[b"http/1.1"]);
+        complete_handshake(&mut connection, &mut client);
+
+        client.writer().write_all(HEAD).unwrap();
+        send_to_server(&mut connection, &mut client);
+        assert_eq!(Ok(ParseStatus::Partial), connection.parse());
+
+        client.writer().write_all(b"hello").unwrap();
+        send_to_server(&mut connection, &mut client);
+        assert_eq!(
+            Ok(ParseStatus::Complete(HEAD.len() + 5)),
+            connection.parse()
+        );
+
+        let Some(ConnectionVersion::Http11(Some(ref request))) = connection.state else {
+            panic!("expected an HTTP/1.1 request");
+        };
+        assert_eq!(Some(&b"hello"[..]), request.body_bytes());
+    }
+
+    #[test]
+    fn tls_requests_parsed_in_turn_after_each_response() {
+        const REQ: &[u8] = b"GET / HTTP/1.1\r\nHost: localhost\r\n\r\n";
+        let mut connection = ConnectionBuilder::new(MockStream::new(), Token(0))
+            .with_tls(alpn_server_config())
+            .build()
+            .unwrap();
+        let mut client = alpn_client(&[b"http/1.1"]);
+        complete_handshake(&mut connection, &mut client);
+
+        for _ in 0..3 {
+            client.writer().write_all(REQ).unwrap();
+            send_to_server(&mut connection, &mut client);
+            assert_eq!(Ok(ParseStatus::Complete(REQ.len())), connection.parse());
+
+            connection.prepare_response(Response::new_with_status_line(
+                Version::H1_1,
+                Status::NoContent,
+            ));
+            connection.write().unwrap();
+            assert_eq!(Ok(ParseStatus::Partial), connection.parse());
+        }
+
+        let received = receive_from_server(&mut connection, &mut client);
+        let received = String::from_utf8(received).unwrap();
+        assert_eq!(3, received.matches("HTTP/1.1 204 No Content\r\n").count());
+    }
+
+    #[test]
+    fn tls_wants_write_reflects_queued_response() {
+        let mut connection = ConnectionBuilder::new(MockStream::new(), Token(0))
+            .with_tls(alpn_server_config())
+            .build()
+            .unwrap();
+        let mut client = alpn_client(&[b"http/1.1"]);
+
+        complete_handshake(&mut connection, &mut client);
+        assert!(!connection.wants_write());
+
+        connection.prepare_response(Response::new_with_status_line(
+            Version::H1_1,
+            Status::NoContent,
+        ));
+        assert!(connection.wants_write());
+
+        connection.write().unwrap();
+        assert!(!connection.wants_write());
+    }
+
+    #[test]
+    fn tls_read_reports_request_truncated_at_eof() {
+        let mut connection = ConnectionBuilder::new(MockStream::new(), Token(0))
+            .with_tls(alpn_server_config())
+            .build()
+            .unwrap();
+        let mut client = alpn_client(&[b"http/1.1"]);
+
+        complete_handshake(&mut connection, &mut client);
+        client
+            .writer()
+            .write_all(b"GET / HTTP/1.1\r\nHost: local")
+            .unwrap();
+        send_to_server(&mut connection, &mut client);
+        connection.stream.eof = true;
+
+        let err = connection.read().unwrap_err();
+        assert!(matches!(
+            err,
+            ConnectionError::Parse(ParseError::UnterminatedHeaders)
+        ));
+        assert!(connection.is_closed());
+    }
+
+    #[test]
+    fn tls_request_received_with_close_is_answered() {
+        const REQ: &[u8] = b"GET / HTTP/1.1\r\nHost: localhost\r\n\r\n";
+        let mut connection = ConnectionBuilder::new(MockStream::new(), Token(0))
+            .with_tls(alpn_server_config())
+            .build()
+            .unwrap();
+        let mut client = alpn_client(&[b"http/1.1"]);
+        complete_handshake(&mut connection, &mut client);
+
+        // the request's records and the peer closing arrive in a single read
+        client.writer().write_all(REQ).unwrap();
+        client.send_close_notify();
+        let mut records = Vec::new();
+        while client.wants_write() {
+            client.write_tls(&mut records).unwrap();
+        }
+        connection.stream.push(&records);
+        connection.stream.eof = true;
+
+        connection.read().unwrap();
+        assert!(connection.is_closed());
+        assert_eq!(Ok(ParseStatus::Complete(REQ.len())), connection.parse());
+
+        connection.prepare_response(Response::new_with_status_line(
+            Version::H1_1,
+            Status::NoContent,
+        ));
+        connection.finalize().unwrap();
+        let received = receive_from_server(&mut connection, &mut client);
+        assert!(received.starts_with(b"HTTP/1.1 204 No Content\r\n"));
+    }
+
+    #[test]
+    fn plain_eof_between_requests_is_not_an_error() {
+        let mut connection = ConnectionBuilder::new(MockStream::new(), Token(0))
+            .with_plaintext()
+            .build();
+        connection.stream.eof = true;
+
+        connection.read().unwrap();
+        assert!(connection.is_closed());
+        assert_eq!(Ok(ParseStatus::Partial), connection.parse());
+    }
+
+    /// Delivers the records `connection` has written to `client`, returning the plaintext received
+    fn receive_from_server(
+        connection: &mut TlsConnection<MockStream>,
+        client: &mut ClientConnection,
+    ) -> Vec<u8> {
+        let records = std::mem::take(&mut connection.stream.written);
+        let mut records = records.as_slice();
+        let mut plaintext = Vec::new();
+        while !records.is_empty() {
+            client.read_tls(&mut records).unwrap();
+            client.process_new_packets().unwrap();
+            let _ = client.reader().read_to_end(&mut plaintext);
+        }
+
+        plaintext
+    }
+
+    #[test]
+    fn tls_response_larger_than_session_buffer_is_written() {
+        let body: Vec<u8> = (0..200 * 1024).map(|i| (i % 251) as u8).collect();
+        let mut connection = ConnectionBuilder::new(MockStream::new(), Token(0))
+            .with_tls(alpn_server_config())
+            .build()
+            .unwrap();
+        let mut client = alpn_client(&[b"http/1.1"]);
+        complete_handshake(&mut connection, &mut client);
+
+        connection.prepare_response(
+            Response::builder(Version::H1_1, Status::Ok)
+                .body(body.clone())
+                .build(),
+        );
+        connection.write().unwrap();
+        assert!(!connection.wants_write());
+
+        let received = receive_from_server(&mut connection, &mut client);
+        assert!(received.starts_with(b"HTTP/1.1 200 OK\r\n"));
+        assert!(received.ends_with(&body));
+    }
+
+    #[test]
+    fn tls_builder_overrides_alpn_protocols() {
+        let mut connection = ConnectionBuilder::new(MockStream::new(), Token(0))
+            .with_tls(alpn_server_config())
+            .with_alpn_protocols(&[b"http/1.1"])
+            .build()
+            .unwrap();
+        let mut client = alpn_client(&ALPN_PROTOCOLS);
+
+        complete_handshake(&mut connection, &mut client);
+        assert_eq!(Some(&b"http/1.1"[..]), client.alpn_protocol());
+    }
+
+    #[test]
+    fn tls_exposes_client_certificate_after_handshake() {
+        let verifier = WebPkiClientVerifier::builder(Arc::new(roots()))
+            .build()
+            .unwrap();
+        let server_config = ServerConfig::builder()
+            .with_client_cert_verifier(verifier)
+            .with_single_cert(vec![CertificateDer::from(SERVER_CERT)], key(SERVER_KEY))
+            .unwrap();
+        let client_config = ClientConfig::builder()
+            .with_root_certificates(roots())
+            .with_client_auth_cert(vec![CertificateDer::from(CLIENT_CERT)], key(CLIENT_KEY))
+            .unwrap();
+
+        let mut connection = ConnectionBuilder::new(MockStream::new(), Token(0))
+            .with_tls(Arc::new(server_config))
+            .build()
+            .unwrap();
+        let mut client = ClientConnection::new(
+            Arc::new(client_config),
+            ServerName::try_from("localhost").unwrap(),
+        )
+        .unwrap();
+
+        while client.is_handshaking() || connection.tls.is_handshaking() {
+            assert_eq!(None, connection.peer_certificates());
+
+            let mut records = Vec::new();
+            while client.wants_write() {
+                client.write_tls(&mut records).unwrap();
+            }
+            if !records.is_empty() {
+                connection.stream.push(&records);
+                connection.read().unwrap();
+            }
+
+            connection.write().unwrap();
+            let records = std::mem::take(&mut connection.stream.written);
+            client.read_tls(&mut records.as_slice()).unwrap();
+            client.process_new_packets().unwrap();
+        }
+
+        let certificates = connection.peer_certificates().unwrap();
+        assert_eq!(CLIENT_CERT, certificates[0].as_ref());
+
+        let subject = connection.peer_subject().unwrap();
+        assert_eq!(Some("rask test client"), subject.common_name);
+        assert_eq!(vec!["client.localhost"], subject.dns_names);
+    }
+
+    #[test]
+    fn tls_build_in_reuses_session_allocation() {
+        let mut stream = MockStream::new();
+        stream.push(b"GET / HTTP/1.1\r\n\r\n");
+        let mut connection = ConnectionBuilder::new(stream, Token(0))
+            .with_tls(tls_config())
+            .build()
+            .unwrap();
+        assert!(connection.read().is_err());
+
+        let tls = connection.into_tls_allocation();
+        let allocation: *const ServerConnection = &*tls;
+
+        let connection = ConnectionBuilder::new(MockStream::new(), Token(1))
+            .with_tls(tls_config())
+            .build_in(tls)
+            .unwrap();
+
+        assert!(!connection.is_closed());
+        assert!(connection.tls.is_handshaking());
+        assert_eq!(allocation, &*connection.tls as *const ServerConnection);
+    }
+
+    #[test]
+    fn plain_read_reports_headers_truncated_at_eof() {
+        let mut connection = ConnectionBuilder::new(MockStream::new(), Token(0))
+            .with_plaintext()
+            .build();
+        connection.stream.push(b"GET / HTTP/1.1\r\nHost: www.exa");
+        connection.stream.eof = true;
+
+        let err = connection.read().unwrap_err();
+        assert!(matches!(
+            err,
+            ConnectionError::Parse(ParseError::UnterminatedHeaders)
+        ));
+        assert!(connection.is_closed());
+        assert_eq!(Err(ParseError::UnterminatedHeaders), connection.parse());
+    }
+
+    #[test]
+    fn plain_final_request_closes_connection() {
+        const REQ: &[u8] = b"GET / HTTP/1.1\r\nHost: www.example.org\r\n\r\n";
+        let mut connection = ConnectionBuilder::new(MockStream::new(), Token(0))
+            .with_max_requests(2)
+            .with_plaintext()
+            .build();
+
+        for expected in ["Connection: keep-alive\r\n", "Connection: close\r\n"] {
+            connection.stream.push(REQ);
+            connection.read().unwrap();
+            assert!(matches!(connection.parse(), Ok(ParseStatus::Complete(_))));
+
+            connection.prepare_response(Response::new_with_status_line(
+                Version::H1_1,
+                Status::NoContent,
+            ));
+            assert!(!connection.is_closed());
+
+            connection.stream.written.clear();
+            connection.write().unwrap();
+            let written = String::from_utf8(connection.stream.written.clone()).unwrap();
+            assert!(written.contains(expected));
+        }
+
+        assert!(connection.is_closed());
+    }
+
+    /// Returns the body of every response written to `stream`, with whether it closes the
+    /// connection
+    fn written_responses(stream: &MockStream) -> Vec<(String, bool)> {
+        String::from_utf8(stream.written.clone())
+            .unwrap()
+            .split("HTTP/1.1 200 OK\r\n")
+            .skip(1)
+            .map(|response| {
+                let (head, body) = response.split_once("\r\n\r\n").unwrap();
+                (body.to_owned(), head.contains("Connection: close"))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn plain_pipelined_responses_written_in_request_order() {
+        const REQS: [&[u8]; 3] = [
+            b"GET /first HTTP/1.1\r\nHost: www.example.org\r\n\r\n",
+            b"GET /second HTTP/1.1\r\nHost: www.example.org\r\n\r\n",
+            b"GET /third HTTP/1.1\r\nHost: www.example.org\r\nConnection: close\r\n\r\n",
+        ];
+        let mut connection = ConnectionBuilder::new(MockStream::new(), Token(0))
+            .with_plaintext()
+            .build();
+        connection.stream.push(&REQS.concat());
+        connection.read().unwrap();
+
+        let mut sequences = Vec::new();
+        for req in REQS {
+            assert_eq!(Ok(ParseStatus::Complete(req.len())), connection.parse());
+            sequences.push(connection.sequence().unwrap());
+        }
+
+        // workers finish the third and second requests before the first
+        let mut expected = Vec::new();
+        for (i, released) in [(2, 0..0), (1, 0..0), (0, 0..3)] {
+            let body = ["first", "second", "third"][i];
+            connection.prepare_response_in_order(
+                sequences[i],
+                Response::builder(Version::H1_1, Status::Ok)
+                    .body(body.as_bytes().to_vec())
+                    .build(),
+            );
+            connection.write().unwrap();
+
+            // only the final request closes the connection
+            expected.extend(released.map(|i| (["first", "second", "third"][i].to_owned(), i == 2)));
+            assert_eq!(expected, written_responses(&connection.stream));
+        }
+        assert!(connection.is_closed());
+    }
+
+    #[test]
+    fn plain_pipelined_request_parsed_after_earlier_responses() {
+        const REQ: &[u8] = b"GET / HTTP/1.1\r\nHost: www.example.org\r\n\r\n";
+        let mut connection = ConnectionBuilder::new(MockStream::new(), Token(0))
+            .with_plaintext()
+            .build();
+        connection.stream.push(&REQ.repeat(3));
+        connection.read().unwrap();
+
+        let mut sequences = Vec::new();
+        for _ in 0..2 {
+            assert_eq!(Ok(ParseStatus::Complete(REQ.len())), connection.parse());
+            sequences.push(connection.sequence().unwrap());
+        }
+        for (sequence, body) in [(sequences[1], "second"), (sequences[0], "first")] {
+            connection.prepare_response_in_order(
+                sequence,
+                Response::builder(Version::H1_1, Status::Ok)
+                    .body(body.as_bytes().to_vec())
+                    .build(),
+            );
+        }
+
+        // releasing both responses together leaves the third request in place
+        assert_eq!(Ok(ParseStatus::Complete(REQ.len())), connection.parse());
+        connection.prepare_response(
+            Response::builder(Version::H1_1, Status::Ok)
+                .body(b"third".to_vec())
+                .build(),
+        );
+        connection.write().unwrap();
+
+        let bodies: Vec<String> = written_responses(&connection.stream)
+            .into_iter()
+            .map(|(body, close)| {
+                assert!(!close);
+                body
+            })
+            .collect();
+        assert_eq!(vec!["first", "second", "third"], bodies);
+        assert_eq!(Ok(ParseStatus::Partial), connection.parse());
+        assert_eq!(None, connection.sequence());
+    }
+
+    #[test]
+    fn plain_pipelined_requests_keep_connection_alive_by_default() {
+        const REQ: &[u8] = b"GET / HTTP/1.1\r\nHost: www.example.org\r\n\r\n";
+        let mut connection = ConnectionBuilder::new(MockStream::new(), Token(0))
+            .with_plaintext()
+            .build();
+
+        connection.stream.push(&[REQ, REQ].concat());
+        connection.read().unwrap();
+
+        for _ in 0..2 {
+            assert_eq!(Ok(ParseStatus::Complete(REQ.len())), connection.parse());
+            connection.prepare_response(Response::new_with_status_line(
+                Version::H1_1,
+                Status::NoContent,
+            ));
+            connection.write().unwrap();
+            assert!(!connection.is_closed());
+        }
+
+        let written = String::from_utf8(connection.stream.written.clone()).unwrap();
+        assert_eq!(2, written.matches("HTTP/1.1 204 No Content\r\n").count());
+        assert!(!written.contains("Connection: close"));
+    }
+
+    #[test]
+    fn plain_response_queue_cap_holds_back_requests() {
+        const REQ: &[u8] = b"GET / HTTP/1.1\r\nHost: www.example.org\r\n\r\n";
+        let mut connection = ConnectionBuilder::new(MockStream::new(), Token(0))
+            .with_max_queued_responses(4)
+            .with_plaintext()
+            .build();
+        connection.stream.block_writes = true;
+
+        connection.stream.push(&REQ.repeat(100));
+        connection.read().unwrap();
+
+        let mut answered = 0;
+        for _ in 0..10 {
+            while let Ok(ParseStatus::Complete(1..)) = connection.parse() {
+                connection.prepare_response(Response::new_with_status_line(
+                    Version::H1_1,
+                    Status::NoContent,
+                ));
+                answered += 1;
+            }
+            connection.write().unwrap();
+        }
+        assert_eq!(4, answered);
+        assert_eq!(4, connection.exchange.outgoing.len());
+
+        // requests sent while the queue is full are left unread
+        let buffered = |connection: &PlainConnection<MockStream>| match connection.state {
+            Some(ConnectionVersion::Http11(Some(ref request))) => request.raw().len(),
+            _ => panic!("expected an HTTP/1.1 request"),
+        };
+        let before = buffered(&connection);
+        connection.stream.push(&REQ.repeat(100));
+        connection.read().unwrap();
+        assert_eq!(before, buffered(&connection));
+        assert!(!connection.wants_read());
+
+        // writing a single response makes room for a single request
+        let response = Response::new_with_status_line(Version::H1_1, Status::NoContent);
+        connection.stream.block_writes = false;
+        connection.stream.write_budget = Some(response.serialize().len());
+        connection.write().unwrap();
+        assert_eq!(3, connection.exchange.outgoing.len());
+        assert!(connection.wants_read());
+        assert_eq!(Ok(ParseStatus::Complete(REQ.len())), connection.parse());
+        connection.prepare_response(response);
+        assert_eq!(Ok(ParseStatus::Partial), connection.parse());
+
+        // draining the queue resumes reading and parsing the requests held back
+        connection.stream.write_budget = None;
+        connection.write().unwrap();
+        assert!(!connection.wants_write());
+        connection.read().unwrap();
+        assert!(connection.stream.incoming.is_empty());
+        assert_eq!(Ok(ParseStatus::Complete(REQ.len())), connection.parse());
+    }
+
+    #[test]
+    fn plain_finalize_writes_queued_response_before_close() {
+        const REQ: &[u8] = b"GET / HTTP/1.1\r\nHost: www.example.org\r\n\r\n";
+        let mut connection = ConnectionBuilder::new(MockStream::new(), Token(0))
+            .with_plaintext()
+            .build();
+
+        connection.stream.push(REQ);
+        connection.stream.eof = true;
+        connection.read().unwrap();
+        assert_eq!(Ok(ParseStatus::Complete(REQ.len())), connection.parse());
+        connection.prepare_response(Response::new_with_status_line(
+            Version::H1_1,
+            Status::NoContent,
+        ));
+        assert!(connection.is_closed());
+
+        connection.stream.block_writes = true;
+        connection.finalize().unwrap();
+        assert!(connection.wants_write());
+        assert!(connection.stream.written.is_empty());
+
+        connection.stream.block_writes = false;
+        connection.finalize().unwrap();
+        assert!(!connection.wants_write());
+
+        let written = String::from_utf8(connection.stream.written.clone()).unwrap();
+        assert!(written.starts_with("HTTP/1.1 204 No Content\r\n"));
+    }
+
+    #[test]
+    fn plain_wants_write_reflects_queued_response() {
+        let mut connection = ConnectionBuilder::new(MockStream::new(), Token(0))
+            .with_plaintext()
+            .build();
+        assert!(!connection.wants_write());
+
+        connection.prepare_response(Response::new_with_status_line(
+            Version::H1_1,
+            Status::NoContent,
+        ));
+        assert!(connection.wants_write());
+
+        connection.write().unwrap();
+        assert!(!connection.wants_write());
+    }
+
+    #[test]
+    fn plain_expect_continue_sends_interim_response() {
+        const HEAD: &[u8] =
+            b"POST / HTTP/1.1\r\nHost: localhost\r\nExpect: 100-continue\r\nContent-Length: 5\r\n\r\n";
+        let mut connection = ConnectionBuilder::new(MockStream::new(), Token(0))
+            .with_plaintext()
+            .build();
+
+        connection.stream.push(HEAD);
+        connection.read().unwrap();
+        assert_eq!(Ok(ParseStatus::Partial), connection.parse());
+        assert_eq!(Ok(ParseStatus::Partial), connection.parse());
+        connection.write().unwrap();
+        assert_eq!(CONTINUE, connection.stream.written.as_slice());
+
+        connection.stream.push(b"hello");
+        connection.read().unwrap();
+        assert_eq!(
+            Ok(ParseStatus::Complete(HEAD.len() + 5)),
+            connection.parse()
+        );
+        connection.prepare_response(Response::new_with_status_line(
+            Version::H1_1,
+            Status::NoContent,
+        ));
+        connection.write().unwrap();
+
+        let written = String::from_utf8(connection.stream.written.clone()).unwrap();
+        assert_eq!(1, written.matches("100 Continue").count());
+        assert!(written.contains("HTTP/1.1 204 No Content\r\n"));
+        assert!(!connection.is_closed());
+    }
+
+    #[test]
+    fn plain_request_without_expect_gets_no_interim_response() {
+        const HEAD: &[u8] = b"POST / HTTP/1.1\r\nHost: localhost\r\nContent-Length: 5\r\n\r\n";
+        let mut connection = ConnectionBuilder::new(MockStream::new(), Token(0))
+            .with_plaintext()
+            .build();
+
+        connection.stream.push(HEAD);
+        connection.read().unwrap();
+        assert_eq!(Ok(ParseStatus::Partial), connection.parse());
+        connection.write().unwrap();
+        assert!(connection.stream.written.is_empty());
+
+        connection.stream.push(b"hello");
+        connection.read().unwrap();
+        assert_eq!(
+            Ok(ParseStatus::Complete(HEAD.len() + 5)),
+            connection.parse()
+        );
+    }
+
+    #[test]
+    fn plain_unsupported_expectation_fails_and_closes() {
+        const REQ: &[u8] =
+            b"POST / HTTP/1.1\r\nHost: localhost\r\nExpect: 100-continue, x-custom\r\nContent-Length: 5\r\n\r\nhello";
+        let mut connection = ConnectionBuilder::new(MockStream::new(), Token(0))
+            .with_plaintext()
+            .build();
+
+        connection.stream.push(REQ);
+        connection.read().unwrap();
+        assert_eq!(Ok(ParseStatus::Partial), connection.parse());
+        assert_eq!(Ok(ParseStatus::Partial), connection.parse());
+        connection.write().unwrap();
+
+        let written = String::from_utf8(connection.stream.written.clone()).unwrap();
+        assert!(written.starts_with("HTTP/1.1 417 Expectation Failed\r\n"));
+        assert!(written.contains("Connection: close\r\n"));
+        assert!(connection.is_closed());
+    }
+
+    #[test]
+    fn plain_expect_continue_follows_earlier_responses() {
+        const FIRST: &[u8] = b"GET / HTTP/1.1\r\nHost: localhost\r\n\r\n";
+        const HEAD: &[u8] =
+            b"POST / HTTP/1.1\r\nHost: localhost\r\nExpect: 100-continue\r\nContent-Length: 5\r\n\r\n";
+        let mut connection = ConnectionBuilder::new(MockStream::new(), Token(0))
+            .with_plaintext()
+            .build();
+
+        connection.stream.push(&[FIRST, HEAD].concat());
+        connection.read().unwrap();
+        assert_eq!(Ok(ParseStatus::Complete(FIRST.len())), connection.parse());
+        let first = connection.sequence().unwrap();
+        assert_eq!(Ok(ParseStatus::Partial), connection.parse());
+        connection.write().unwrap();
+        assert!(connection.stream.written.is_empty());
+
+        connection.prepare_response_in_order(
+            first,
+            Response::new_with_status_line(Version::H1_1, Status::NoContent),
+        );
+        connection.write().unwrap();
+
+        let written = connection.stream.written.as_slice();
+        assert!(written.starts_with(b"HTTP/1.1 204 No Content\r\n"));
+        assert!(written.ends_with(CONTINUE));
+        assert_eq!(
+            1,
+            String::from_utf8_lossy(written)
+                .matches("100 Continue")
+                .count()
+        );
+    }
+
+    #[test]
+    fn plain_expectation_failed_follows_earlier_responses() {
+        const FIRST: &[u8] = b"GET / HTTP/1.1\r\nHost: localhost\r\n\r\n";
+        const HEAD: &[u8] =
+            b"POST / HTTP/1.1\r\nHost: localhost\r\nExpect: x-custom\r\nContent-Length: 5\r\n\r\n";
+        let mut connection = ConnectionBuilder::new(MockStream::new(), Token(0))
+            .with_plaintext()
+            .build();
+
+        connection.stream.push(&[FIRST, HEAD].concat());
+        connection.read().unwrap();
+        assert_eq!(Ok(ParseStatus::Complete(FIRST.len())), connection.parse());
+        let first = connection.sequence().unwrap();
+        assert_eq!(Ok(ParseStatus::Partial), connection.parse());
+        connection.write().unwrap();
+        assert!(connection.stream.written.is_empty());
+
+        connection.prepare_response_in_order(
+            first,
+            Response::new_with_status_line(Version::H1_1, Status::NoContent),
+        );
+        connection.write().unwrap();
+
+        let written = String::from_utf8(connection.stream.written.clone()).unwrap();
+        let failed = written.find("HTTP/1.1 417 Expectation Failed\r\n").unwrap();
+        assert!(written[..failed].starts_with("HTTP/1.1 204 No Content\r\n"));
+        assert!(connection.is_closed());
+    }
+
+    #[test]
+    fn plain_h2c_upgrade_is_served_as_http_1_1() {
+        const REQ: &[u8] = b"GET / HTTP/1.1\r\nHost: localhost\r\n\
+Connection: Upgrade, HTTP2-Settings\r\nUpgrade: h2c\r\nHTTP2-Settings: AAMAAABk\r\n\r\n";
+        let mut connection = ConnectionBuilder::new(MockStream::new(), Token(0))
+            .with_plaintext()
+            .build();
+
+        connection.stream.push(REQ);
+        connection.read().unwrap();
+        assert_eq!(Ok(ParseStatus::Complete(REQ.len())), connection.parse());
+        assert!(matches!(
+            connection.state,
+            Some(ConnectionVersion::Http11(Some(_)))
+        ));
+
+        connection.prepare_response(Response::new_with_status_line(
+            Version::H1_1,
+            Status::NoContent,
+        ));
+        connection.write().unwrap();
+        assert!(connection
+            .stream
+            .written
+            .starts_with(b"HTTP/1.1 204 No Content\r\n"));
+    }
+
+    #[test]
+    fn plain_connection_close_request_closes_connection() {
+        const REQ: &[u8] = b"GET / HTTP/1.1\r\nConnection: close\r\n\r\n";
+        let mut connection = ConnectionBuilder::new(MockStream::new(), Token(0))
+            .with_plaintext()
+            .build();
+
+        connection.stream.push(REQ);
+        connection.read().unwrap();
+        assert!(matches!(connection.parse(), Ok(ParseStatus::Complete(_))));
+        connection.prepare_response(Response::new_with_status_line(
+            Version::H1_1,
+            Status::NoContent,
+        ));
+        connection.write().unwrap();
+
+        let written = String::from_utf8(connection.stream.written.clone()).unwrap();
+        assert!(written.contains("Connection: close\r\n"));
+        assert!(connection.is_closed());
+    }
+
+    #[test]
+    fn plain_requests_after_closing_request_are_not_processed() {
+        const REQ: &[u8] = b"GET / HTTP/1.1\r\nHost: www.example.org\r\n\r\n";
+        const CLOSE: &[u8] =
+            b"GET / HTTP/1.1\r\nHost: www.example.org\r\nConnection: close\r\n\r\n";
+        let closing = ConnectionBuilder::new(MockStream::new(), Token(0))
+            .with_plaintext()
+            .build();
+        let budgeted = ConnectionBuilder::new(MockStream::new(), Token(0))
+            .with_max_requests(1)
+            .with_plaintext()
+            .build();
+
+        for (mut connection, input) in [(closing, [CLOSE, REQ].concat()), (budgeted, REQ.repeat(2))]
+        {
+            connection.stream.push(&input);
+            connection.read().unwrap();
+
+            let mut answered = 0;
+            while let Ok(ParseStatus::Complete(1..)) = connection.parse() {
+                connection.prepare_response(Response::new_with_status_line(
+                    Version::H1_1,
+                    Status::NoContent,
+                ));
+                answered += 1;
+            }
+            connection.write().unwrap();
+
+            assert_eq!(1, answered);
+            assert!(connection.is_closed());
+            assert_eq!(Ok(ParseStatus::Partial), connection.parse());
+        }
+    }
+
+    #[test]
+    fn plain_slow_body_times_out() {
+        let timeout = Duration::from_secs(5);
+        let mut connection = ConnectionBuilder::new(MockStream::new(), Token(0))
+            .with_body_read_timeout(timeout)
+            .with_plaintext()
+            .build();
+        connection
+            .stream
+            .push(b"POST / HTTP/1.1\r\nContent-Length: 10\r\n\r\nab");
+
+        let start = Instant::now();
+        connection.read().unwrap();
+        assert_eq!(Ok(ParseStatus::Partial), connection.parse());
+        assert!(connection.check_timeout(start).is_ok());
+
+        // a byte arriving before the deadline does not extend it
+        connection.stream.push(b"c");
+        connection.read().unwrap();
+        assert_eq!(Ok(ParseStatus::Partial), connection.parse());
+        assert!(connection.check_timeout(start + timeout / 2).is_ok());
+
+        let err = connection.check_timeout(start + timeout * 2).unwrap_err();
+        assert!(matches!(err, ConnectionError::Timeout));
+        assert!(connection.is_closed());
+        let written = String::from_utf8(connection.stream.written.clone()).unwrap();
+        assert!(written.starts_with("HTTP/1.1 408 Request Timeout\r\n"));
+        assert!(written.contains("Connection: close\r\n"));
+    }
+
+    #[test]
+    fn plain_body_timeout_ignores_complete_requests() {
+        let timeout = Duration::from_secs(5);
+        let mut connection = ConnectionBuilder::new(MockStream::new(), Token(0))
+            .with_body_read_timeout(timeout)
+            .with_plaintext()
+            .build();
+        connection
+            .stream
+            .push(b"POST / HTTP/1.1\r\nContent-Length: 2\r\n\r\na");
+
+        let start = Instant::now();
+        connection.read().unwrap();
+        assert_eq!(Ok(ParseStatus::Partial), connection.parse());
+        connection.stream.push(b"b");
+        connection.read().unwrap();
+        assert!(matches!(connection.parse(), Ok(ParseStatus::Complete(_))));
+
+        assert!(connection.check_timeout(start + timeout * 2).is_ok());
+        assert!(!connection.is_closed());
+    }
+
+    #[test]
+    fn plain_response_writes_body() {
+        let mut connection = ConnectionBuilder::new(MockStream::new(), Token(0))
+            .with_plaintext()
+            .build();
+
+        connection.prepare_response(
+            Response::builder(Version::H1_1, Status::Ok)
+                .header("Content-Type", "text/plain")
+                .body(b"hello".to_vec())
+                .build(),
+        );
+        connection.write().unwrap();
+
+        let written = String::from_utf8(connection.stream.written.clone()).unwrap();
+        assert!(written.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(written.contains("Content-Type: text/plain\r\n"));
+        assert!(written.ends_with("Content-Length: 5\r\n\r\nhello"));
+    }
+
+    #[test]
+    fn plain_file_response_streams_file() {
+        let contents: Vec<u8> = (0..40 * 1024).map(|i| (i % 251) as u8).collect();
+        let path = std::env::temp_dir().join(format!("rask-connection-{}", std::process::id()));
+        fs::write(&path, &contents).unwrap();
+        let response = Response::from_file(&path, Version::H1_1, Status::Ok).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        let head = response.serialize();
+        let mut connection = ConnectionBuilder::new(MockStream::new(), Token(0))
+            .with_plaintext()
+            .build();
+        connection.prepare_response(response);
+
+        connection.stream.block_writes = true;
+        assert_eq!(0, connection.write().unwrap());
+        assert!(connection.stream.written.is_empty());
+
+        connection.stream.block_writes = false;
+        assert_eq!(head.len() + contents.len(), connection.write().unwrap());
+        assert_eq!(
+            [head.as_slice(), &contents].concat(),
+            connection.stream.written
+        );
+    }
+
+    #[test]
+    fn extensions_persist_across_requests() {
+        #[derive(Debug, PartialEq)]
+        struct User(&'static str);
+
+        let mut connection = ConnectionBuilder::new(MockStream::new(), Token(0))
+            .with_plaintext()
+            .build();
+        connection.extensions().insert(User("ferris"));
+
+        connection.stream.push(b"GET / HTTP/1.1\r\n\r\n");
+        connection.read().unwrap();
+        connection.parse().unwrap();
+        connection.prepare_response(Response::new_with_status_line(
+            Version::H1_1,
+            Status::NoContent,
+        ));
+
+        assert_eq!(Some(&User("ferris")), connection.extensions().get::<User>());
+    }
+
+    #[test]
+    fn plain_write_raw_writes_template_bytes() {
+        let template = ResponseTemplate::new(&Response::new_with_status_line(
+            Version::H1_1,
+            Status::NoContent,
+        ));
+        let mut connection = ConnectionBuilder::new(MockStream::new(), Token(0))
+            .with_plaintext()
+            .build();
+
+        connection.write_raw(template.as_bytes());
+        connection.write_raw(template.as_bytes());
+
+        assert_eq!(template.as_bytes().len() * 2, connection.write().unwrap());
+        assert_eq!(
+            [template.as_bytes(), template.as_bytes()].concat(),
+            connection.stream.written
+        );
+    }
+}
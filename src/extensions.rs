@@ -0,0 +1,132 @@
+// Copyright 2022 Ryan Seipp
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Typed storage for state scoped to a connection
+
+use std::any::{Any, TypeId};
+use std::collections::HashMap;
+use std::fmt::Debug;
+
+/// A map holding at most one value of each type, such as the authenticated user or rate limit
+/// bucket of a connection. Values are retrieved by their type rather than a key.
+///
+/// ```rust
+/// # use rask::extensions::Extensions;
+/// #[derive(Debug, PartialEq)]
+/// struct User(&'static str);
+///
+/// let mut extensions = Extensions::new();
+/// extensions.insert(User("ferris"));
+///
+/// assert_eq!(Some(&User("ferris")), extensions.get::<User>());
+/// ```
+#[derive(Default)]
+pub struct Extensions {
+    map: HashMap<TypeId, Box<dyn Any + Send>>,
+}
+
+impl Extensions {
+    /// Creates an empty map
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Inserts `value`, returning the previous value of the same type if present
+    pub fn insert<T: Any + Send>(&mut self, value: T) -> Option<T> {
+        self.map
+            .insert(TypeId::of::<T>(), Box::new(value))
+            .and_then(|previous| previous.downcast().ok())
+            .map(|previous| *previous)
+    }
+
+    /// Returns a reference to the value of type `T`, if present
+    pub fn get<T: Any + Send>(&self) -> Option<&T> {
+        self.map
+            .get(&TypeId::of::<T>())
+            .and_then(|value| value.downcast_ref())
+    }
+
+    /// Returns a mutable reference to the value of type `T`, if present
+    pub fn get_mut<T: Any + Send>(&mut self) -> Option<&mut T> {
+        self.map
+            .get_mut(&TypeId::of::<T>())
+            .and_then(|value| value.downcast_mut())
+    }
+
+    /// Removes and returns the value of type `T`, if present
+    pub fn remove<T: Any + Send>(&mut self) -> Option<T> {
+        self.map
+            .remove(&TypeId::of::<T>())
+            .and_then(|value| value.downcast().ok())
+            .map(|value| *value)
+    }
+
+    /// Returns true if a value of type `T` is present
+    pub fn contains<T: Any + Send>(&self) -> bool {
+        self.map.contains_key(&TypeId::of::<T>())
+    }
+
+    /// Removes every value
+    pub fn clear(&mut self) {
+        self.map.clear();
+    }
+}
+
+impl Debug for Extensions {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Extensions")
+            .field("len", &self.map.len())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Extensions;
+
+    #[derive(Debug, PartialEq)]
+    struct RateLimit {
+        remaining: u32,
+    }
+
+    #[test]
+    fn stores_one_value_per_type() {
+        let mut extensions = Extensions::new();
+
+        assert_eq!(None, extensions.insert(RateLimit { remaining: 10 }));
+        assert_eq!(None, extensions.insert(42u32));
+        assert_eq!(
+            Some(RateLimit { remaining: 10 }),
+            extensions.insert(RateLimit { remaining: 5 })
+        );
+
+        assert_eq!(Some(&RateLimit { remaining: 5 }), extensions.get());
+        assert_eq!(Some(&42u32), extensions.get());
+        assert_eq!(None, extensions.get::<u64>());
+    }
+
+    #[test]
+    fn get_mut_and_remove() {
+        let mut extensions = Extensions::new();
+        extensions.insert(RateLimit { remaining: 2 });
+
+        extensions.get_mut::<RateLimit>().unwrap().remaining -= 1;
+
+        assert_eq!(
+            Some(RateLimit { remaining: 1 }),
+            extensions.remove::<RateLimit>()
+        );
+        assert!(!extensions.contains::<RateLimit>());
+    }
+}
@@ -32,10 +32,14 @@
 
 use std::sync::{Arc, Mutex};
 
+pub mod cert;
 pub mod connection;
+pub mod extensions;
 pub mod multilistener;
-mod net;
+pub mod net;
 pub mod parser;
+pub(crate) mod rng;
+pub mod sequencer;
 
 /// TODO
 #[derive(Debug)]
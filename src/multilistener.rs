@@ -4,15 +4,20 @@ use std::{
     io::{ErrorKind, Read, Result, Write},
     marker::PhantomData,
     sync::Arc,
+    time::{Duration, Instant},
 };
 
 use mio::{event::Source, Events, Interest, Poll, Token};
-use rustls::ServerConfig;
+use rustls::{ServerConfig, ServerConnection};
 use slab::Slab;
 
 use crate::{
-    connection::{Connection, PlainConnection},
-    parser::{h1::response::Response, status::Status, Version},
+    connection::{Connection, ConnectionError, PlainConnection},
+    parser::{
+        h1::{config::ParserConfig, response::Response},
+        status::Status,
+        ParseError, Status as ParseStatus, Version,
+    },
 };
 use crate::{
     connection::{ConnectionBuilder, TlsConnection},
@@ -22,6 +27,9 @@ use crate::{
 const LISTEN_TOKEN: Token = Token(usize::MAX);
 const WAKE_TOKEN: Token = Token(usize::MAX - 1);
 
+/// Maximum number of TLS session allocations kept from closed connections for reuse
+const TLS_POOL_SIZE: usize = 128;
+
 /// Configuration for the listener
 #[derive(Debug)]
 pub struct ListenerConfig {
@@ -31,6 +39,69 @@ pub struct ListenerConfig {
     pub http_port: u16,
     /// TODO
     pub https_port: u16,
+    /// Maximum number of requests served over a single keep-alive connection, or `None` for no
+    /// limit.
+    pub max_requests: Option<usize>,
+    /// Accept absolute-form request targets, acting as a forward proxy. When disabled, the
+    /// listener acts as an origin server and rejects absolute-form targets for methods other
+    /// than CONNECT.
+    /// [RFC 9112 Section 3.2.2](https://www.rfc-editor.org/rfc/rfc9112#section-3.2.2)
+    pub proxy_mode: bool,
+    /// Maximum time allowed to receive a request body once its headers have been received, or
+    /// `None` for no limit. Clients that exceed it, such as those sending the body a byte at a
+    /// time, are sent `408 Request Timeout` and disconnected. Deadlines are checked at least once
+    /// per timeout period, so a stalled client is disconnected within twice the timeout.
+    pub body_read_timeout: Option<Duration>,
+    /// Maximum number of responses queued for a connection whose peer is not reading them, or
+    /// `None` for no limit. Once reached, no further requests are read from the connection or
+    /// answered until a queued response has been written.
+    pub max_queued_responses: Option<usize>,
+}
+
+impl ListenerConfig {
+    /// Returns the configuration requests received by the listener are parsed with
+    #[inline]
+    fn parser_config(&self) -> ParserConfig {
+        ParserConfig {
+            allow_absolute_form: self.proxy_mode,
+            ..ParserConfig::default()
+        }
+    }
+}
+
+/// Answers every complete request received over `connection`, returning true if any was
+/// answered. A single read may carry several pipelined requests. Stops at a request that has only
+/// partially been received, once parsing consumes nothing, such as after the HTTP/2 handshake has
+/// completed, or while the connection's response queue is full. A request that fails to parse is
+/// answered with the status for its error, and no further requests are parsed.
+#[inline]
+fn answer_requests<C: Connection>(connection: &mut C) -> bool {
+    let mut answered = false;
+    loop {
+        match connection.parse() {
+            Ok(ParseStatus::Complete(1..)) => {
+                // TODO: handle routing for request handlers here
+
+                let response = Response::new_with_status_line(Version::H1_1, Status::NoContent);
+                connection.prepare_response(response);
+                answered = true;
+            }
+            Err(err) => {
+                answer_error(connection, &err);
+                return true;
+            }
+            Ok(_) => return answered,
+        }
+    }
+}
+
+/// Answers a request that failed to parse with the status for `err`, closing the connection once
+/// the response has been written
+#[inline]
+fn answer_error<C: Connection>(connection: &mut C, err: &ParseError) {
+    let mut response = Response::new_with_status_line(Version::H1_1, err.response_status());
+    response.set_close(true);
+    connection.prepare_response(response);
 }
 
 /// Socket listener for the server.
@@ -45,6 +116,9 @@ where
     num_events: usize,
     poll: Poll,
     connections: Slab<C>,
+    // the boxes are the allocations being reused, so they must not be moved out of
+    #[allow(clippy::vec_box)]
+    tls_pool: Vec<Box<ServerConnection>>,
     configuration: ListenerConfig,
     _marker: PhantomData<S>,
 }
@@ -62,9 +136,19 @@ where
                     let entry = self.connections.vacant_entry();
                     let token = Token(entry.key());
 
-                    let mut connection = ConnectionBuilder::new(stream, token)
-                        .with_plaintext()
-                        .build();
+                    let mut builder = ConnectionBuilder::new(stream, token)
+                        .with_parser_config(self.configuration.parser_config());
+                    if let Some(max_requests) = self.configuration.max_requests {
+                        builder = builder.with_max_requests(max_requests);
+                    }
+                    if let Some(timeout) = self.configuration.body_read_timeout {
+                        builder = builder.with_body_read_timeout(timeout);
+                    }
+                    if let Some(max) = self.configuration.max_queued_responses {
+                        builder = builder.with_max_queued_responses(max);
+                    }
+
+                    let mut connection = builder.with_plaintext().build();
                     connection.register(self.poll.registry())?;
                     self.connections.insert(connection);
                 }
@@ -82,7 +166,10 @@ where
         let mut events = Events::with_capacity(self.num_events);
 
         loop {
-            match self.poll.poll(&mut events, None) {
+            match self
+                .poll
+                .poll(&mut events, self.configuration.body_read_timeout)
+            {
                 Ok(_) => {
                     for event in events.iter() {
                         match event.token() {
@@ -96,6 +183,8 @@ where
                             }
                         }
                     }
+
+                    self.expire_connections();
                 }
                 Err(err) => {
                     println!("Failed to poll for events: {}", err);
@@ -119,17 +208,32 @@ where
                     let entry = self.connections.vacant_entry();
                     let token = Token(entry.key());
 
-                    let connection = ConnectionBuilder::new(stream, token)
-                        .with_tls(
-                            self.configuration
-                                .tls
-                                .as_ref()
-                                .expect("Tls configuration is required")
-                                .clone(),
-                        )
-                        .build()
-                        .expect("Invalid TLS Configuration");
+                    let mut builder = ConnectionBuilder::new(stream, token)
+                        .with_parser_config(self.configuration.parser_config());
+                    if let Some(max_requests) = self.configuration.max_requests {
+                        builder = builder.with_max_requests(max_requests);
+                    }
+                    if let Some(timeout) = self.configuration.body_read_timeout {
+                        builder = builder.with_body_read_timeout(timeout);
+                    }
+                    if let Some(max) = self.configuration.max_queued_responses {
+                        builder = builder.with_max_queued_responses(max);
+                    }
+
+                    let builder = builder.with_tls(
+                        self.configuration
+                            .tls
+                            .as_ref()
+                            .expect("Tls configuration is required")
+                            .clone(),
+                    );
+                    let mut connection = match self.tls_pool.pop() {
+                        Some(tls) => builder.build_in(tls),
+                        None => builder.build(),
+                    }
+                    .expect("Invalid TLS Configuration");
 
+                    connection.register(self.poll.registry())?;
                     self.connections.insert(connection);
                 }
                 Err(ref err) if err.kind() == ErrorKind::WouldBlock => return Ok(()),
@@ -146,7 +250,10 @@ where
         let mut events = Events::with_capacity(self.num_events);
 
         loop {
-            match self.poll.poll(&mut events, None) {
+            match self
+                .poll
+                .poll(&mut events, self.configuration.body_read_timeout)
+            {
                 Ok(_) => {
                     for event in events.iter() {
                         match event.token() {
@@ -156,10 +263,16 @@ where
                             }
 
                             _ => {
-                                self.event(event);
+                                if let Some(connection) = self.event(event) {
+                                    self.recycle(connection);
+                                }
                             }
                         }
                     }
+
+                    for connection in self.expire_connections() {
+                        self.recycle(connection);
+                    }
                 }
                 Err(err) => {
                     println!("Failed to poll for events: {}", err);
@@ -168,6 +281,14 @@ where
             }
         }
     }
+
+    /// Keeps the TLS session allocation of a closed connection for the next accepted connection
+    #[inline]
+    fn recycle(&mut self, connection: TlsConnection<S>) {
+        if self.tls_pool.len() < TLS_POOL_SIZE {
+            self.tls_pool.push(connection.into_tls_allocation());
+        }
+    }
 }
 
 impl<T, S, C> MultiListener<T, S, C>
@@ -188,41 +309,81 @@ where
             num_events: 1024,
             poll,
             connections: Slab::default(),
+            tls_pool: Vec::new(),
             configuration: config,
             _marker: PhantomData,
         }
     }
 
+    /// Services an event for a connection, returning the connection if it was closed.
     #[inline]
-    fn event(&mut self, event: &mio::event::Event) {
+    fn event(&mut self, event: &mio::event::Event) -> Option<C> {
         let token = event.token();
 
-        let Some(ref mut connection) = self.connections.get_mut(token.0) else {
-            return;
-        };
+        let connection = self.connections.get_mut(token.0)?;
 
-        if event.is_readable() {
-            let read_result = connection.read();
+        let mut readable = event.is_readable();
+        loop {
+            if readable {
+                let read_result = connection.read();
+                if let Err(ConnectionError::Parse(ref err)) = read_result {
+                    answer_error(connection, err);
+                }
 
-            if read_result.is_err() || connection.is_closed() {
-                return self.close_connection(token);
+                if read_result.is_err() || connection.is_closed() {
+                    return self.close_connection(token);
+                }
             }
 
-            if let Ok(_request) = connection.parse() {
-                // TODO: handle routing for request handlers here
+            let answered = answer_requests(connection);
 
-                let response = Response::new_with_status_line(Version::H1_1, Status::NoContent);
-                connection.prepare_response(response);
+            if (event.is_writable() && connection.write().is_err()) || connection.is_closed() {
+                return self.close_connection(token);
+            }
+
+            // writing the responses may have made room to answer requests held back while the
+            // queue was full, and to read those the peer has sent since
+            readable = connection.wants_read();
+            if !event.is_writable() || !(answered || readable) {
+                return None;
             }
         }
+    }
 
-        if (event.is_writable() && connection.write().is_err()) || connection.is_closed() {
-            self.close_connection(event.token())
+    /// Closes every connection that has exceeded a deadline, returning the closed connections.
+    fn expire_connections(&mut self) -> Vec<C> {
+        if self.configuration.body_read_timeout.is_none() {
+            return Vec::new();
         }
+
+        let now = Instant::now();
+        let expired: Vec<Token> = self
+            .connections
+            .iter_mut()
+            .filter_map(|(key, connection)| connection.check_timeout(now).err().map(|_| Token(key)))
+            .collect();
+
+        expired
+            .into_iter()
+            // the peer is not waited on to receive the timeout response
+            .filter_map(|token| self.remove_connection(token))
+            .collect()
+    }
+
+    /// Closes the connection once its queued responses have been written. It stays registered
+    /// until a writable event lets them be written, unless the peer can no longer receive them.
+    #[inline]
+    fn close_connection(&mut self, token: Token) -> Option<C> {
+        let connection = self.connections.get_mut(token.0)?;
+        if connection.is_closed() && connection.finalize().is_ok() && connection.wants_write() {
+            return None;
+        }
+
+        self.remove_connection(token)
     }
 
     #[inline]
-    fn close_connection(&mut self, token: Token) {
+    fn remove_connection(&mut self, token: Token) -> Option<C> {
         let mut closed = false;
         if let Some(ref mut connection) = self.connections.get_mut(token.0) {
             if connection.is_closed() {
@@ -232,7 +393,95 @@ where
         }
 
         if closed {
-            self.connections.try_remove(token.0);
+            self.connections.try_remove(token.0)
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use mio::Token;
+
+    use super::answer_requests;
+    use crate::connection::{Connection, ConnectionBuilder};
+    use crate::net::mock_stream::MockStream;
+    use crate::parser::Status as ParseStatus;
+
+    #[test]
+    fn partial_request_is_not_answered() {
+        let mut connection = ConnectionBuilder::new(MockStream::new(), Token(0))
+            .with_plaintext()
+            .build();
+
+        connection
+            .stream_mut()
+            .push(b"GET / HTTP/1.1\r\nHost: www.example.org\r\n");
+        connection.read().unwrap();
+
+        assert!(!answer_requests(&mut connection));
+        assert!(!connection.wants_write());
+    }
+
+    #[test]
+    fn request_received_in_two_reads_is_answered_once() {
+        const REQ: &[u8] = b"GET / HTTP/1.1\r\nHost: www.example.org\r\n\r\n";
+        let mut connection = ConnectionBuilder::new(MockStream::new(), Token(0))
+            .with_plaintext()
+            .build();
+
+        connection.stream_mut().push(&REQ[..20]);
+        connection.read().unwrap();
+        assert!(!answer_requests(&mut connection));
+
+        connection.stream_mut().push(&REQ[20..]);
+        connection.read().unwrap();
+        assert!(answer_requests(&mut connection));
+        assert!(!answer_requests(&mut connection));
+        connection.write().unwrap();
+
+        let written = String::from_utf8(connection.stream_mut().written.clone()).unwrap();
+        assert_eq!(1, written.matches("HTTP/1.1 204 No Content\r\n").count());
+        // the partial data was kept, so nothing remains to be answered
+        assert_eq!(Ok(ParseStatus::Partial), connection.parse());
+    }
+
+    #[test]
+    fn malformed_requests_are_answered_with_their_status() {
+        let cases: [(&[u8], &str); 3] = [
+            (
+                b"GET / HTTP/1.2\r\nHost: www.example.org\r\n\r\n",
+                "HTTP/1.1 505 HTTP Version Not Supported\r\n",
+            ),
+            (
+                b"POST / HTTP/1.1\r\nHost: www.example.org\r\nTransfer-Encoding: gzip\r\n\r\nGET / HTTP/1.1\r\n\r\n",
+                "HTTP/1.1 400 Bad Request\r\n",
+            ),
+            (
+                b"GET / HTTP/1.1\r\nHost: www.example.org\r\n\r\nGET /\x7f HTTP/1.1\r\n\r\n",
+                "HTTP/1.1 400 Bad Request\r\n",
+            ),
+        ];
+
+        for (input, status_line) in cases {
+            let mut connection = ConnectionBuilder::new(MockStream::new(), Token(0))
+                .with_plaintext()
+                .build();
+            connection.stream_mut().push(input);
+            connection.read().unwrap();
+
+            assert!(answer_requests(&mut connection));
+            assert!(!answer_requests(&mut connection));
+            connection.write().unwrap();
+
+            // the error is answered last, closing the connection
+            let written = String::from_utf8(connection.stream_mut().written.clone()).unwrap();
+            let (_, last) = written.rsplit_once("HTTP/1.1 ").unwrap();
+            assert!(format!("HTTP/1.1 {}", last).starts_with(status_line));
+            assert!(last.contains("Connection: close\r\n"));
+            assert_eq!(1, written.matches(status_line).count());
+            assert!(connection.is_closed());
         }
     }
 }
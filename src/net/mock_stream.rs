@@ -0,0 +1,134 @@
+use std::collections::VecDeque;
+use std::io::{Error, ErrorKind, Read, Result, Write};
+use std::net::{Shutdown, SocketAddr};
+
+use mio::{event::Source, Interest, Registry, Token};
+
+use super::tcp_stream::TcpStream;
+
+/// In-memory stream for driving connections in tests. Reads drain queued chunks one at a time,
+/// returning `WouldBlock` once empty, or EOF if `eof` is set. Writes return `WouldBlock` while
+/// `block_writes` is set, or once the bytes allowed by `write_budget` have been written.
+#[derive(Debug, Default)]
+pub struct MockStream {
+    pub incoming: VecDeque<Vec<u8>>,
+    pub written: Vec<u8>,
+    pub eof: bool,
+    pub block_writes: bool,
+    pub write_budget: Option<usize>,
+}
+
+impl MockStream {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&mut self, data: &[u8]) {
+        self.incoming.push_back(data.to_vec());
+    }
+}
+
+impl Read for MockStream {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        let Some(chunk) = self.incoming.front_mut() else {
+            return if self.eof {
+                Ok(0)
+            } else {
+                Err(ErrorKind::WouldBlock.into())
+            };
+        };
+
+        let n = buf.len().min(chunk.len());
+        buf[..n].copy_from_slice(&chunk[..n]);
+        chunk.drain(..n);
+        if chunk.is_empty() {
+            self.incoming.pop_front();
+        }
+
+        Ok(n)
+    }
+}
+
+impl Write for MockStream {
+    fn write(&mut self, buf: &[u8]) -> Result<usize> {
+        if self.block_writes || self.write_budget == Some(0) {
+            return Err(ErrorKind::WouldBlock.into());
+        }
+
+        let n = self
+            .write_budget
+            .map_or(buf.len(), |budget| budget.min(buf.len()));
+        if let Some(budget) = &mut self.write_budget {
+            *budget -= n;
+        }
+
+        self.written.extend_from_slice(&buf[..n]);
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        Ok(())
+    }
+}
+
+impl Source for MockStream {
+    fn register(&mut self, _: &Registry, _: Token, _: Interest) -> Result<()> {
+        Ok(())
+    }
+
+    fn reregister(&mut self, _: &Registry, _: Token, _: Interest) -> Result<()> {
+        Ok(())
+    }
+
+    fn deregister(&mut self, _: &Registry) -> Result<()> {
+        Ok(())
+    }
+}
+
+impl TcpStream for MockStream {
+    fn connect(_: SocketAddr) -> Result<Self> {
+        Ok(Self::default())
+    }
+
+    fn peer_addr(&self) -> Result<SocketAddr> {
+        Ok(SocketAddr::from(([127, 0, 0, 1], 0)))
+    }
+
+    fn local_addr(&self) -> Result<SocketAddr> {
+        Ok(SocketAddr::from(([127, 0, 0, 1], 0)))
+    }
+
+    fn shutdown(&self, _: Shutdown) -> Result<()> {
+        Ok(())
+    }
+
+    fn set_nodelay(&self, _: bool) -> Result<()> {
+        Ok(())
+    }
+
+    fn nodelay(&self) -> Result<bool> {
+        Ok(true)
+    }
+
+    fn set_ttl(&self, _: u32) -> Result<()> {
+        Ok(())
+    }
+
+    fn ttl(&self) -> Result<u32> {
+        Ok(64)
+    }
+
+    fn take_error(&self) -> Result<Option<Error>> {
+        Ok(None)
+    }
+
+    fn peek(&self, buf: &mut [u8]) -> Result<usize> {
+        let mut n = 0;
+        for (dst, src) in buf.iter_mut().zip(self.incoming.iter().flatten()) {
+            *dst = *src;
+            n += 1;
+        }
+
+        Ok(n)
+    }
+}
@@ -1,2 +1,13 @@
+//! Transport abstractions that connections are generic over.
+//!
+//! [`ConnectionBuilder`](crate::connection::ConnectionBuilder) and
+//! [`MultiListener`](crate::multilistener::MultiListener) accept any transport implementing
+//! [`TcpStream`](tcp_stream::TcpStream) and [`TcpListener`](tcp_listener::TcpListener). The traits
+//! are public so that other transports can be supplied, such as the in-memory transport of the
+//! TLS benchmarks.
+
 pub mod tcp_listener;
 pub mod tcp_stream;
+
+#[cfg(test)]
+pub(crate) mod mock_stream;
@@ -1,21 +1,30 @@
+//! The [`TcpListener`] abstraction and its implementations for mio and std.
+
 use std::io::{Error, Result};
 use std::net::SocketAddr;
 
 use super::tcp_stream::TcpStream;
 
+/// A TCP socket server, listening for connections of type `S`.
 pub trait TcpListener<S: TcpStream> {
+    /// Creates a listener bound to `addr`.
     fn bind(addr: SocketAddr) -> Result<Self>
     where
         Self: Sized;
 
+    /// Accepts a new incoming connection, returning the stream and the address of the peer.
     fn accept(&self) -> Result<(S, SocketAddr)>;
 
+    /// Returns the local address this listener is bound to.
     fn local_addr(&self) -> Result<SocketAddr>;
 
+    /// Sets the value of the `IP_TTL` option.
     fn set_ttl(&self, ttl: u32) -> Result<()>;
 
+    /// Returns the value of the `IP_TTL` option.
     fn ttl(&self) -> Result<u32>;
 
+    /// Returns and clears the value of the `SO_ERROR` option.
     fn take_error(&self) -> Result<Option<Error>>;
 }
 
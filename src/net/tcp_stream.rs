@@ -1,28 +1,75 @@
-use std::io::{Error, Result};
+//! The [`TcpStream`] abstraction and its implementation for mio.
+
+use std::fs::File;
+use std::io::{Error, ErrorKind, Read, Result, Seek, SeekFrom, Write};
 use std::net::{Shutdown, SocketAddr};
 
+/// A connected TCP stream. Abstracts over blocking and non-blocking implementations so that
+/// connections can be driven by any transport.
 pub trait TcpStream {
+    /// Opens a connection to `addr`.
     fn connect(addr: SocketAddr) -> Result<Self>
     where
         Self: Sized;
 
+    /// Returns the address of the remote peer.
     fn peer_addr(&self) -> Result<SocketAddr>;
 
+    /// Returns the local address this stream is bound to.
     fn local_addr(&self) -> Result<SocketAddr>;
 
+    /// Shuts down the read, write, or both halves of the connection.
     fn shutdown(&self, how: Shutdown) -> Result<()>;
 
+    /// Sets the value of the `TCP_NODELAY` option.
     fn set_nodelay(&self, nodelay: bool) -> Result<()>;
 
+    /// Returns the value of the `TCP_NODELAY` option.
     fn nodelay(&self) -> Result<bool>;
 
+    /// Sets the value of the `IP_TTL` option.
     fn set_ttl(&self, ttl: u32) -> Result<()>;
 
+    /// Returns the value of the `IP_TTL` option.
     fn ttl(&self) -> Result<u32>;
 
+    /// Returns and clears the value of the `SO_ERROR` option.
     fn take_error(&self) -> Result<Option<Error>>;
 
+    /// Reads from the stream into `buf` without removing the data from the queue.
     fn peek(&self, buf: &mut [u8]) -> Result<usize>;
+
+    /// Writes up to `count` bytes of `file`, starting at `offset`, to the stream. Returns the
+    /// number of bytes written, which may be fewer than `count`.
+    ///
+    /// The default implementation copies through a userspace buffer. Implementations should
+    /// override it with a zero copy syscall where the platform provides one.
+    fn send_file(&mut self, file: &File, offset: u64, count: usize) -> Result<usize>
+    where
+        Self: Write,
+    {
+        copy_file_to(self, file, offset, count)
+    }
+}
+
+/// Copies a single chunk of up to `count` bytes of `file`, starting at `offset`, to `writer`.
+/// Returns the number of bytes accepted by `writer`.
+pub fn copy_file_to<W: Write + ?Sized>(
+    writer: &mut W,
+    mut file: &File,
+    offset: u64,
+    count: usize,
+) -> Result<usize> {
+    let mut buf = [0u8; 16 * 1024];
+    let len = count.min(buf.len());
+
+    file.seek(SeekFrom::Start(offset))?;
+    let read = file.read(&mut buf[..len])?;
+    if read == 0 {
+        return Err(ErrorKind::UnexpectedEof.into());
+    }
+
+    writer.write(&buf[..read])
 }
 
 impl TcpStream for mio::net::TcpStream {
@@ -78,6 +125,22 @@ impl TcpStream for mio::net::TcpStream {
     fn peek(&self, buf: &mut [u8]) -> Result<usize> {
         Self::peek(self, buf)
     }
+
+    #[cfg(target_os = "linux")]
+    #[inline]
+    fn send_file(&mut self, file: &File, offset: u64, count: usize) -> Result<usize> {
+        use std::os::fd::AsRawFd;
+
+        let mut offset =
+            libc::off_t::try_from(offset).map_err(|_| Error::from(ErrorKind::InvalidInput))?;
+        let sent =
+            unsafe { libc::sendfile(self.as_raw_fd(), file.as_raw_fd(), &mut offset, count) };
+        if sent < 0 {
+            return Err(Error::last_os_error());
+        }
+
+        Ok(sent as usize)
+    }
 }
 
 impl TcpStream for std::net::TcpStream {
@@ -134,3 +197,37 @@ impl TcpStream for std::net::TcpStream {
         Self::peek(self, buf)
     }
 }
+
+#[cfg(test)]
+mod test {
+    use std::fs;
+    use std::io::Read;
+
+    use super::TcpStream;
+
+    #[test]
+    fn send_file_writes_file_contents() {
+        let contents: Vec<u8> = (0..64 * 1024).map(|i| (i % 251) as u8).collect();
+        let path = std::env::temp_dir().join(format!("rask-send-file-{}", std::process::id()));
+        fs::write(&path, &contents).unwrap();
+        let file = fs::File::open(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let client = std::net::TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        let (mut server, _) = listener.accept().unwrap();
+        let mut client = mio::net::TcpStream::from_std(client);
+
+        let mut offset = 0;
+        while offset < contents.len() {
+            offset += client
+                .send_file(&file, offset as u64, contents.len() - offset)
+                .unwrap();
+        }
+        drop(client);
+
+        let mut received = Vec::new();
+        server.read_to_end(&mut received).unwrap();
+        assert_eq!(contents, received);
+    }
+}
@@ -0,0 +1,265 @@
+// Copyright 2022 Ryan Seipp
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Content negotiation header values
+//! [RFC 9110 Section 12.5](https://www.rfc-editor.org/rfc/rfc9110#section-12.5)
+
+use super::tokens::is_header_name_token;
+
+/// Parses a comma separated list of tokens with optional quality values, such as the value of an
+/// `Accept-Encoding` header. Elements are returned in the order they were received, with a
+/// default weight of `1.0`. Empty list elements are skipped, as are elements whose token or
+/// weight is malformed.
+///
+/// Defined by RFC 9110 Section 12.4.2 by ABNF
+/// ```abnf
+/// weight = OWS ";" OWS "q=" qvalue
+/// qvalue = ( "0" [ "." 0*3DIGIT ] )
+///        / ( "1" [ "." 0*3("0") ] )
+/// ```
+///
+/// ```rust
+/// # use rask::parser::h1::accept::parse_quality_list;
+/// let list = parse_quality_list(b"gzip, deflate;q=0.5, br;q=0");
+/// assert_eq!(vec![(b"gzip" as &[u8], 1.0), (b"deflate", 0.5), (b"br", 0.0)], list);
+/// ```
+pub fn parse_quality_list(value: &[u8]) -> Vec<(&[u8], f32)> {
+    let mut list = Vec::default();
+
+    for element in value.split(|&b| b == b',') {
+        let mut parts = element.split(|&b| b == b';');
+        let token = trim_whitespace(parts.next().unwrap_or_default());
+        if token.is_empty() || !token.iter().all(|&b| is_header_name_token(b)) {
+            continue;
+        }
+
+        let mut weight = Some(1.0);
+        for parameter in parts {
+            let parameter = trim_whitespace(parameter);
+            if parameter.len() >= 2
+                && parameter[0].eq_ignore_ascii_case(&b'q')
+                && parameter[1] == b'='
+            {
+                weight = parse_qvalue(&parameter[2..]);
+            }
+        }
+
+        if let Some(weight) = weight {
+            list.push((token, weight));
+        }
+    }
+
+    list
+}
+
+/// Parses the value of an `Accept-Charset` header into charset tokens and their weights.
+///
+/// `Accept-Charset` is deprecated by RFC 9110 Section 12.5.2, but is still sent by legacy
+/// clients.
+///
+/// ```rust
+/// # use rask::parser::h1::accept::parse_accept_charset;
+/// let charsets = parse_accept_charset(b"Shift_JIS,utf-8;q=0.7");
+/// assert_eq!(vec![(b"Shift_JIS" as &[u8], 1.0), (b"utf-8", 0.7)], charsets);
+/// ```
+#[inline]
+pub fn parse_accept_charset(value: &[u8]) -> Vec<(&[u8], f32)> {
+    parse_quality_list(value)
+}
+
+/// Language ranges of an `Accept-Language` header, with their weights
+/// [RFC 9110 Section 12.5.4](https://www.rfc-editor.org/rfc/rfc9110#section-12.5.4)
+///
+/// ```rust
+/// # use rask::parser::h1::accept::AcceptLanguage;
+/// let accept = AcceptLanguage::parse(b"ja,en-us;q=0.7,en;q=0.3");
+/// assert_eq!(Some("en-GB"), accept.best_match(&["fr", "en-GB"]));
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct AcceptLanguage<'a> {
+    ranges: Vec<(&'a [u8], f32)>,
+}
+
+impl<'a> AcceptLanguage<'a> {
+    /// Parses the value of an `Accept-Language` header. See [`parse_quality_list`].
+    pub fn parse(value: &'a [u8]) -> Self {
+        Self {
+            ranges: parse_quality_list(value),
+        }
+    }
+
+    /// Returns the language ranges and their weights, in the order they were received
+    pub fn ranges(&self) -> &[(&'a [u8], f32)] {
+        &self.ranges
+    }
+
+    /// Returns the available language tag with the highest weight, or `None` if none are
+    /// acceptable. Each tag is weighted by the most specific range matching it, using the basic
+    /// filtering of RFC 4647 Section 3.3.1: `en` matches `en` and `en-US`, but `en-US` does not
+    /// match `en`, and `*` matches every tag. Tags matched only by ranges with a weight of 0, or
+    /// by none, are not acceptable. Ties are broken by the order of `available`.
+    pub fn best_match<'b>(&self, available: &[&'b str]) -> Option<&'b str> {
+        let mut best: Option<(&str, f32)> = None;
+        for &tag in available {
+            let weight = self
+                .ranges
+                .iter()
+                .filter(|(range, _)| language_range_matches(range, tag.as_bytes()))
+                .max_by_key(|(range, _)| language_range_specificity(range))
+                .map(|&(_, weight)| weight);
+
+            match (weight, best) {
+                (Some(weight), Some((_, best_weight))) if weight <= best_weight => {}
+                (Some(weight), _) if weight > 0.0 => best = Some((tag, weight)),
+                _ => {}
+            }
+        }
+
+        best.map(|(tag, _)| tag)
+    }
+}
+
+/// Returns true if the language range `range` matches `tag`, which is when they are equal or
+/// `range` is a prefix of `tag` ending at a subtag boundary, ignoring case.
+/// [RFC 4647 Section 3.3.1](https://www.rfc-editor.org/rfc/rfc4647#section-3.3.1)
+#[inline]
+fn language_range_matches(range: &[u8], tag: &[u8]) -> bool {
+    range == b"*"
+        || (tag.len() >= range.len()
+            && tag[..range.len()].eq_ignore_ascii_case(range)
+            && matches!(tag.get(range.len()), None | Some(b'-')))
+}
+
+/// Orders matching language ranges from least to most specific, with `*` the least specific
+#[inline]
+fn language_range_specificity(range: &[u8]) -> usize {
+    if range == b"*" {
+        0
+    } else {
+        range.len()
+    }
+}
+
+#[inline]
+fn parse_qvalue(value: &[u8]) -> Option<f32> {
+    let (&whole, rest) = value.split_first()?;
+    let fraction = match rest {
+        [] => &[],
+        [b'.', fraction @ ..] if fraction.len() <= 3 => fraction,
+        _ => return None,
+    };
+
+    match whole {
+        b'0' if fraction.iter().all(u8::is_ascii_digit) => {
+            let thousandths = fraction
+                .iter()
+                .chain([b'0'; 3].iter())
+                .take(3)
+                .fold(0u16, |acc, &b| acc * 10 + u16::from(b - b'0'));
+            Some(f32::from(thousandths) / 1000.0)
+        }
+        b'1' if fraction.iter().all(|&b| b == b'0') => Some(1.0),
+        _ => None,
+    }
+}
+
+/// Strips leading and trailing optional whitespace (SP / HTAB) from `value`.
+#[inline]
+pub(crate) fn trim_whitespace(value: &[u8]) -> &[u8] {
+    let start = value
+        .iter()
+        .position(|&b| b != b' ' && b != b'\t')
+        .unwrap_or(value.len());
+    let end = value
+        .iter()
+        .rposition(|&b| b != b' ' && b != b'\t')
+        .map_or(start, |n| n + 1);
+
+    &value[start..end]
+}
+
+#[cfg(test)]
+mod test {
+    use super::{parse_accept_charset, parse_quality_list, AcceptLanguage};
+
+    #[test]
+    fn accept_charset_parses_fixture_value() {
+        let charsets = parse_accept_charset(b"Shift_JIS,utf-8;q=0.7,*;q=0.7");
+
+        assert_eq!(
+            vec![(b"Shift_JIS" as &[u8], 1.0), (b"utf-8", 0.7), (b"*", 0.7)],
+            charsets
+        );
+    }
+
+    #[test]
+    fn accept_language_matches_fixture_value() {
+        let accept = AcceptLanguage::parse(b"ja,en-us;q=0.7,en;q=0.3");
+
+        assert_eq!(Some("ja"), accept.best_match(&["en", "ja"]));
+        assert_eq!(Some("en-US"), accept.best_match(&["en-GB", "en-US"]));
+        assert_eq!(Some("en-GB"), accept.best_match(&["fr", "en-GB"]));
+        // a more specific range does not match a less specific tag
+        assert_eq!(Some("en"), accept.best_match(&["en"]));
+        assert_eq!(Some("ja-JP"), accept.best_match(&["en", "ja-JP"]));
+        assert_eq!(None, accept.best_match(&["fr", "english"]));
+        assert_eq!(None, accept.best_match(&[]));
+    }
+
+    #[test]
+    fn accept_language_honors_wildcard_and_zero_weight() {
+        let accept = AcceptLanguage::parse(b"de;q=0.5, *;q=0.1, fr;q=0");
+
+        assert_eq!(Some("de-AT"), accept.best_match(&["it", "de-AT"]));
+        assert_eq!(Some("it"), accept.best_match(&["fr", "it", "es"]));
+        // the specific range rejecting fr takes precedence over the wildcard
+        assert_eq!(None, accept.best_match(&["fr-CA"]));
+    }
+
+    #[test]
+    fn quality_list_trims_whitespace_and_skips_empty_elements() {
+        let list = parse_quality_list(b" , gzip ;  q=0.25 ,, br\t");
+
+        assert_eq!(vec![(b"gzip" as &[u8], 0.25), (b"br", 1.0)], list);
+    }
+
+    #[test]
+    fn quality_list_accepts_boundary_qvalues() {
+        let list = parse_quality_list(b"a;q=0, b;q=0.001, c;Q=1.000, d;q=1");
+
+        assert_eq!(
+            vec![
+                (b"a" as &[u8], 0.0),
+                (b"b", 0.001),
+                (b"c", 1.0),
+                (b"d", 1.0)
+            ],
+            list
+        );
+    }
+
+    #[test]
+    fn quality_list_skips_malformed_elements() {
+        let list = parse_quality_list(b"a;q=1.5, b;q=0.1234, c;q=, d e, f;q=0.5");
+
+        assert_eq!(vec![(b"f" as &[u8], 0.5)], list);
+    }
+
+    #[test]
+    fn quality_list_ignores_other_parameters() {
+        let list = parse_quality_list(b"utf-8;foo=bar;q=0.4");
+
+        assert_eq!(vec![(b"utf-8" as &[u8], 0.4)], list);
+    }
+}
@@ -0,0 +1,334 @@
+// Copyright 2022 Ryan Seipp
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Streaming request bodies
+use std::io::{self, ErrorKind, Read};
+
+use super::accept::trim_whitespace;
+use super::config::ParserConfig;
+use super::request::parse_chunk_size;
+
+/// Reads the body of a request as it is received, so large bodies need not be buffered in full.
+/// Bytes already received after the request head are read first, followed by those read from the
+/// stream the request arrived on. Chunked bodies are decoded, and bodies sized by
+/// `Content-Length` end after that many bytes. A stream that ends early fails with
+/// [`ErrorKind::UnexpectedEof`].
+///
+/// Nothing is read from the stream past the end of the body, so a request pipelined after it can
+/// still be read from the stream. Chunk size lines longer than
+/// [`ParserConfig::max_request_line`], and trailer sections larger than
+/// [`ParserConfig::max_header_bytes`], fail with [`ErrorKind::InvalidData`].
+///
+/// Created by [`H1Request::body_reader`](super::request::H1Request::body_reader).
+///
+/// ```rust
+/// # use std::io::Read;
+/// # use rask::parser::h1::request::H1Request;
+/// let mut req = H1Request::new();
+/// req.fill(&mut &b"POST / HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n5\r\nhel"[..])
+///     .unwrap();
+/// req.parse().unwrap();
+///
+/// let mut body = Vec::new();
+/// let mut stream: &[u8] = b"lo\r\n0\r\n\r\n";
+/// req.body_reader(&mut stream)
+///     .unwrap()
+///     .read_to_end(&mut body)
+///     .unwrap();
+/// assert_eq!(b"hello", body.as_slice());
+/// ```
+#[derive(Debug)]
+pub struct RequestBody<'a, R> {
+    received: &'a [u8],
+    stream: R,
+    framing: Framing,
+    line: Vec<u8>,
+    /// Maximum length of a chunk size line, including its line ending
+    max_line: usize,
+    /// Bytes of trailer fields that may still be read before the trailer section is too large
+    trailer_budget: usize,
+}
+
+/// Position within the framing of the body
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Framing {
+    /// `remaining` bytes of a body sized by `Content-Length`
+    Length { remaining: u64 },
+    /// Size line of the next chunk
+    ChunkSize,
+    /// `remaining` bytes of the current chunk's data
+    ChunkData { remaining: u64 },
+    /// CRLF ending the current chunk's data
+    ChunkEnd,
+    /// Trailer section after the last chunk, which is skipped
+    Trailers,
+    /// The whole body has been read
+    Done,
+}
+
+impl<'a, R: Read> RequestBody<'a, R> {
+    /// Creates a reader for a body sized by `Content-Length`
+    pub(crate) fn with_length(received: &'a [u8], stream: R, len: u64) -> Self {
+        let framing = if len == 0 {
+            Framing::Done
+        } else {
+            Framing::Length { remaining: len }
+        };
+        Self::new(received, stream, framing, &ParserConfig::default())
+    }
+
+    /// Creates a reader decoding a chunked body, limiting its framing as set by `config`
+    pub(crate) fn chunked(received: &'a [u8], stream: R, config: &ParserConfig) -> Self {
+        Self::new(received, stream, Framing::ChunkSize, config)
+    }
+
+    #[inline]
+    fn new(received: &'a [u8], stream: R, framing: Framing, config: &ParserConfig) -> Self {
+        Self {
+            received,
+            stream,
+            framing,
+            line: Vec::new(),
+            max_line: config.max_request_line,
+            trailer_budget: config.max_header_bytes,
+        }
+    }
+
+    /// Reads a complete line of at most `max` bytes into `self.line`, returning it without its
+    /// CRLF. A line interrupted by an error, such as [`ErrorKind::WouldBlock`], is resumed on the
+    /// next call. Bytes are read from the stream one at a time, so nothing past the line is read.
+    #[inline]
+    fn read_line(&mut self, max: usize) -> io::Result<&[u8]> {
+        while !self.line.ends_with(b"\n") {
+            let room = max.saturating_sub(self.line.len());
+            if room == 0 {
+                return Err(too_large());
+            }
+
+            if self.received.is_empty() {
+                let mut byte = [0];
+                if self.stream.read(&mut byte)? == 0 {
+                    return Err(ErrorKind::UnexpectedEof.into());
+                }
+                self.line.push(byte[0]);
+            } else {
+                let len = room.min(self.received.len());
+                let len = self.received[..len]
+                    .iter()
+                    .position(|&b| b == b'\n')
+                    .map_or(len, |n| n + 1);
+                let (line, received) = self.received.split_at(len);
+                self.line.extend_from_slice(line);
+                self.received = received;
+            }
+        }
+
+        match self.line.strip_suffix(b"\r\n") {
+            Some(line) => Ok(line),
+            None => Err(invalid_chunk()),
+        }
+    }
+
+    /// Reads up to `remaining` bytes of the body into `buf`
+    #[inline]
+    fn read_data(&mut self, buf: &mut [u8], remaining: u64) -> io::Result<usize> {
+        let len =
+            usize::try_from(remaining).map_or(buf.len(), |remaining| remaining.min(buf.len()));
+        let buf = &mut buf[..len];
+        let read = if self.received.is_empty() {
+            self.stream.read(buf)?
+        } else {
+            self.received.read(buf)?
+        };
+
+        match read {
+            0 if len > 0 => Err(ErrorKind::UnexpectedEof.into()),
+            n => Ok(n),
+        }
+    }
+}
+
+impl<'a, R: Read> Read for RequestBody<'a, R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        loop {
+            match self.framing {
+                Framing::Length { remaining } => {
+                    let n = self.read_data(buf, remaining)?;
+                    let remaining = remaining - n as u64;
+                    self.framing = if remaining == 0 {
+                        Framing::Done
+                    } else {
+                        Framing::Length { remaining }
+                    };
+                    return Ok(n);
+                }
+                Framing::ChunkSize => {
+                    let line = self.read_line(self.max_line)?;
+                    let size = line.split(|&b| b == b';').next().unwrap_or_default();
+                    let size = parse_chunk_size(trim_whitespace(size)).ok_or_else(invalid_chunk)?;
+                    self.line.clear();
+                    self.framing = match size {
+                        0 => Framing::Trailers,
+                        size => Framing::ChunkData {
+                            remaining: size as u64,
+                        },
+                    };
+                }
+                Framing::ChunkData { remaining } => {
+                    let n = self.read_data(buf, remaining)?;
+                    let remaining = remaining - n as u64;
+                    if remaining == 0 {
+                        self.framing = Framing::ChunkEnd;
+                    } else {
+                        self.framing = Framing::ChunkData { remaining };
+                    }
+                    return Ok(n);
+                }
+                Framing::ChunkEnd => {
+                    if !self.read_line(self.max_line)?.is_empty() {
+                        return Err(invalid_chunk());
+                    }
+                    self.line.clear();
+                    self.framing = Framing::ChunkSize;
+                }
+                Framing::Trailers => {
+                    // the empty line ending the section doesn't count against the limit
+                    let end = self
+                        .read_line(self.trailer_budget.saturating_add(2))?
+                        .is_empty();
+                    let len = self.line.len();
+                    self.line.clear();
+                    if end {
+                        self.framing = Framing::Done;
+                    } else {
+                        self.trailer_budget =
+                            self.trailer_budget.checked_sub(len).ok_or_else(too_large)?;
+                    }
+                }
+                Framing::Done => return Ok(0),
+            }
+        }
+    }
+}
+
+#[inline]
+fn invalid_chunk() -> io::Error {
+    io::Error::new(ErrorKind::InvalidData, "invalid chunked transfer coding")
+}
+
+#[inline]
+fn too_large() -> io::Error {
+    io::Error::new(
+        ErrorKind::InvalidData,
+        "chunked transfer coding exceeds the configured limits",
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::{ErrorKind, Read};
+
+    use super::RequestBody;
+    use crate::net::mock_stream::MockStream;
+    use crate::parser::h1::config::ParserConfig;
+
+    #[test]
+    fn chunked_body_decoded_across_reads() {
+        const RECEIVED: &[u8] = b"5;ext=1\r\nhello\r\n6\r\n wo";
+        let mut stream = MockStream::new();
+        stream.push(b"rld\r\n0\r");
+        stream.push(b"\nExpires: Wed, 21 Oct 2015 07:28:00 GMT\r\n\r\nGET / HTTP/1.1\r\n");
+
+        let mut body = RequestBody::chunked(RECEIVED, &mut stream, &ParserConfig::default());
+        let mut decoded = Vec::new();
+        body.read_to_end(&mut decoded).unwrap();
+        assert_eq!(b"hello world", decoded.as_slice());
+
+        // the request pipelined after the body is left in the stream
+        let mut rest = Vec::new();
+        stream.eof = true;
+        stream.read_to_end(&mut rest).unwrap();
+        assert_eq!(b"GET / HTTP/1.1\r\n", rest.as_slice());
+    }
+
+    #[test]
+    fn chunked_body_resumes_after_would_block() {
+        let mut stream = MockStream::new();
+        stream.push(b"5\r");
+
+        let mut body = RequestBody::chunked(b"", &mut stream, &ParserConfig::default());
+        let mut buf = [0; 16];
+        let err = body.read(&mut buf).unwrap_err();
+        assert_eq!(ErrorKind::WouldBlock, err.kind());
+
+        body.stream.push(b"\nhello\r\n0\r\n\r\n");
+        let mut decoded = Vec::new();
+        body.read_to_end(&mut decoded).unwrap();
+        assert_eq!(b"hello", decoded.as_slice());
+    }
+
+    #[test]
+    fn chunked_body_rejects_malformed_framing() {
+        for input in [
+            &b"z\r\nhello\r\n0\r\n\r\n"[..],
+            b"5\r\nhelloX\r\n0\r\n\r\n",
+            b"5\nhello",
+        ] {
+            let mut body = RequestBody::chunked(input, &b""[..], &ParserConfig::default());
+            let err = body.read_to_end(&mut Vec::new()).unwrap_err();
+            assert_eq!(ErrorKind::InvalidData, err.kind());
+        }
+
+        let mut body = RequestBody::chunked(b"5\r\nhel", &b""[..], &ParserConfig::default());
+        let err = body.read_to_end(&mut Vec::new()).unwrap_err();
+        assert_eq!(ErrorKind::UnexpectedEof, err.kind());
+    }
+
+    #[test]
+    fn chunked_body_framing_is_limited() {
+        let config = ParserConfig {
+            max_request_line: 8,
+            max_header_bytes: 16,
+            ..ParserConfig::default()
+        };
+
+        let mut stream = &b"5;ext=1\r\nhello\r\n0\r\n\r\n"[..];
+        let mut body = RequestBody::chunked(b"", &mut stream, &config);
+        let err = body.read_to_end(&mut Vec::new()).unwrap_err();
+        assert_eq!(ErrorKind::InvalidData, err.kind());
+        // reading stopped at the limit
+        assert_eq!(b"\nhello\r\n0\r\n\r\n", stream);
+
+        let mut body = RequestBody::chunked(b"0\r\nA: 123456789012\r\n\r\n", &b""[..], &config);
+        let err = body.read_to_end(&mut Vec::new()).unwrap_err();
+        assert_eq!(ErrorKind::InvalidData, err.kind());
+
+        let mut body = RequestBody::chunked(b"0\r\nA: 12345678901\r\n\r\n", &b""[..], &config);
+        body.read_to_end(&mut Vec::new()).unwrap();
+    }
+
+    #[test]
+    fn length_body_enforces_content_length() {
+        let mut stream = &b"lo world"[..];
+        let mut body = RequestBody::with_length(b"hel", &mut stream, 5);
+        let mut read = Vec::new();
+        body.read_to_end(&mut read).unwrap();
+        assert_eq!(b"hello", read.as_slice());
+        assert_eq!(b" world", stream);
+
+        let mut body = RequestBody::with_length(b"hel", &b"l"[..], 5);
+        let err = body.read_to_end(&mut Vec::new()).unwrap_err();
+        assert_eq!(ErrorKind::UnexpectedEof, err.kind());
+    }
+}
@@ -0,0 +1,144 @@
+// Copyright 2022 Ryan Seipp
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Parser configuration
+
+/// Default limit on the length of the request line, in bytes
+pub const DEFAULT_MAX_REQUEST_LINE: usize = 8 * 1024;
+
+/// Default limit on the size of the header section, in bytes
+pub const DEFAULT_MAX_HEADER_BYTES: usize = 16 * 1024;
+
+/// Default limit on the number of continuation lines folded into a single header value
+pub const DEFAULT_MAX_OBS_FOLD_LINES: usize = 8;
+
+/// Default limit on the length of a single folded header value, in bytes
+pub const DEFAULT_MAX_FOLDED_VALUE_BYTES: usize = 4 * 1024;
+
+/// Controls how strictly requests are parsed. Each flag relaxes the parser to accept input the
+/// RFCs permit recipients to tolerate, at the cost of a larger surface for request smuggling.
+///
+/// The default is [`ParserConfig::strict`], which rejects all of it.
+///
+/// ```rust
+/// # use rask::parser::{Status, h1::{config::ParserConfig, request::H1Request}};
+/// let mut req = H1Request::with_config(ParserConfig {
+///     allow_leading_crlf: true,
+///     ..ParserConfig::strict()
+/// });
+/// let mut buf: &[u8] = b"\r\nGET / HTTP/1.1\r\n\r\n";
+///
+/// req.fill(&mut buf).unwrap();
+/// assert_eq!(Ok(Status::Complete(20)), req.parse());
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParserConfig {
+    /// Accept a bare LF as a line terminator in place of CRLF.
+    /// [RFC 9112 Section 2.2](https://www.rfc-editor.org/rfc/rfc9112#section-2.2)
+    pub allow_bare_lf: bool,
+    /// Accept runs of SP and HTAB between the components of the request line, rather than
+    /// exactly one SP.
+    /// [RFC 9112 Section 3](https://www.rfc-editor.org/rfc/rfc9112#section-3)
+    pub allow_multiple_spaces: bool,
+    /// Ignore empty lines received before the request line.
+    /// [RFC 9112 Section 2.2](https://www.rfc-editor.org/rfc/rfc9112#section-2.2)
+    pub allow_leading_crlf: bool,
+    /// Accept repeated `Content-Length` headers, provided every value is identical.
+    /// [RFC 9110 Section 8.6](https://www.rfc-editor.org/rfc/rfc9110#section-8.6)
+    pub allow_duplicate_content_length: bool,
+    /// Accept absolute-form request targets, as sent to a forward proxy, for methods other than
+    /// CONNECT. When disabled, only origin-form and asterisk-form targets are accepted, as
+    /// expected by an origin server.
+    /// [RFC 9112 Section 3.2.2](https://www.rfc-editor.org/rfc/rfc9112#section-3.2.2)
+    pub allow_absolute_form: bool,
+    /// Accept header values continued onto following lines that begin with SP or HTAB
+    /// (obs-fold). The line breaks of each fold are replaced with SP in the request buffer, so
+    /// the value is read as a single line without being copied. When disabled, folded values are
+    /// rejected with [`ParseError::ObsFold`](crate::parser::ParseError::ObsFold).
+    /// [RFC 9112 Section 5.2](https://www.rfc-editor.org/rfc/rfc9112#section-5.2)
+    pub allow_obs_fold: bool,
+    /// Maximum length in bytes of the request line, including any leading empty lines and its
+    /// line ending. Exceeding it fails with
+    /// [`ParseError::RequestLineTooLong`](crate::parser::ParseError::RequestLineTooLong) as soon
+    /// as the limit is passed, so an endless target cannot grow the request buffer without bound.
+    /// The chunk size lines of a body read through
+    /// [`RequestBody`](super::body::RequestBody) are held to the same limit. Defaults to
+    /// [`DEFAULT_MAX_REQUEST_LINE`].
+    /// [RFC 9112 Section 3](https://www.rfc-editor.org/rfc/rfc9112#section-3)
+    pub max_request_line: usize,
+    /// Maximum size in bytes of the header section, excluding the empty line that ends it. The
+    /// same limit applies separately to the trailer section of a chunked body. Exceeding it fails
+    /// with [`ParseError::HeadersTooLarge`](crate::parser::ParseError::HeadersTooLarge) as soon as
+    /// the limit is passed, so a client cannot stream headers without bound. Defaults to
+    /// [`DEFAULT_MAX_HEADER_BYTES`].
+    /// [RFC 9112 Section 5](https://www.rfc-editor.org/rfc/rfc9112#section-5)
+    pub max_header_bytes: usize,
+    /// Maximum number of continuation lines folded into a single header value when
+    /// [`allow_obs_fold`](Self::allow_obs_fold) is enabled. Exceeding it fails with
+    /// [`ParseError::ObsFold`](crate::parser::ParseError::ObsFold). Defaults to
+    /// [`DEFAULT_MAX_OBS_FOLD_LINES`].
+    pub max_obs_fold_lines: usize,
+    /// Maximum length in bytes of a folded header value, from its first byte to the end of its
+    /// last continuation line. Exceeding it fails with
+    /// [`ParseError::ObsFold`](crate::parser::ParseError::ObsFold). Values that are not folded
+    /// are only bound by [`max_header_bytes`](Self::max_header_bytes). Defaults to
+    /// [`DEFAULT_MAX_FOLDED_VALUE_BYTES`].
+    pub max_folded_value_bytes: usize,
+    /// Maximum number of bytes scanned, across calls to parse, before the request head must be
+    /// complete. See [`H1Request::with_parse_budget`](super::request::H1Request::with_parse_budget).
+    pub parse_budget: Option<usize>,
+}
+
+impl Default for ParserConfig {
+    fn default() -> Self {
+        Self {
+            allow_bare_lf: false,
+            allow_multiple_spaces: false,
+            allow_leading_crlf: false,
+            allow_duplicate_content_length: false,
+            allow_absolute_form: false,
+            allow_obs_fold: false,
+            max_request_line: DEFAULT_MAX_REQUEST_LINE,
+            max_header_bytes: DEFAULT_MAX_HEADER_BYTES,
+            max_obs_fold_lines: DEFAULT_MAX_OBS_FOLD_LINES,
+            max_folded_value_bytes: DEFAULT_MAX_FOLDED_VALUE_BYTES,
+            parse_budget: None,
+        }
+    }
+}
+
+impl ParserConfig {
+    /// Rejects anything the RFCs do not require a recipient to accept. Suited to servers that
+    /// sit behind, or in front of, other HTTP implementations.
+    pub fn strict() -> Self {
+        Self::default()
+    }
+
+    /// Tolerates the deviations the RFCs allow, for interoperability with legacy clients.
+    pub fn compatibility() -> Self {
+        Self {
+            allow_bare_lf: true,
+            allow_multiple_spaces: true,
+            allow_leading_crlf: true,
+            allow_duplicate_content_length: true,
+            allow_absolute_form: true,
+            allow_obs_fold: true,
+            max_request_line: DEFAULT_MAX_REQUEST_LINE,
+            max_header_bytes: DEFAULT_MAX_HEADER_BYTES,
+            max_obs_fold_lines: DEFAULT_MAX_OBS_FOLD_LINES,
+            max_folded_value_bytes: DEFAULT_MAX_FOLDED_VALUE_BYTES,
+            parse_budget: None,
+        }
+    }
+}
@@ -16,6 +16,9 @@
 
 use super::{ParseError, ParseResult, Status};
 
+pub mod accept;
+pub mod body;
+pub mod config;
 pub mod request;
 pub mod response;
 pub mod tokens;
@@ -27,16 +30,10 @@ pub mod tokens;
 /// ```
 #[inline]
 pub fn discard_whitespace(buf: &[u8], pos: usize) -> Option<usize> {
-    let mut pos = pos;
-    for &byte in &buf[pos..] {
-        if byte != b' ' && byte != b'\t' {
-            return Some(pos);
-        }
-
-        pos += 1;
-    }
-
-    None
+    buf[pos..]
+        .iter()
+        .position(|&byte| byte != b' ' && byte != b'\t')
+        .map(|n| pos + n)
 }
 
 /// Consumes whitespace characters from `buf`. Requires that at least one whitespace character is
@@ -52,8 +49,10 @@ pub fn discard_required_whitespace(
     err_type: ParseError,
 ) -> ParseResult<usize> {
     let mut pos = pos;
-    if buf[pos] != b' ' && buf[pos] != b'\t' {
-        return Err(err_type);
+    match buf.get(pos) {
+        None => return Ok(Status::Partial),
+        Some(&byte) if byte != b' ' && byte != b'\t' => return Err(err_type),
+        _ => {}
     }
 
     pos += 1;
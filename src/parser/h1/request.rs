@@ -16,16 +16,22 @@
 
 use std::fmt::Display;
 use std::io::{self, ErrorKind, Read};
-use std::mem::MaybeUninit;
 use std::ops::Range;
 use std::str::from_utf8;
 
+use super::accept::trim_whitespace;
+use super::body::RequestBody;
+use super::config::ParserConfig;
 use super::tokens::{is_header_name_token, is_header_value_token};
 use super::{
     discard_required_newline, discard_required_whitespace, discard_whitespace, ParseError,
     ParseResult,
 };
-use crate::parser::{Method, Status, Version};
+use crate::parser::{percent_decode, uri::Uri, Method, Status, Version};
+
+/// Maximum number of headers parsed in a single request. Requests with more headers fail with
+/// [`ParseError::TooManyHeaders`].
+pub const MAX_HEADERS: usize = 96;
 
 /// TODO
 #[derive(Debug, PartialEq, Eq, Default, Clone)]
@@ -53,10 +59,23 @@ pub struct H1Request {
     pub method: Option<Method>,
     /// TODO
     pub target: Option<Range<usize>>,
+    /// Form of the request target, classified from the method and the target
+    pub target_form: Option<TargetForm>,
     /// TODO
     pub version: Option<Version>,
-    /// TODO
-    pub headers: Option<&'static [Header]>,
+    /// Headers, in the order they were received
+    pub headers: Vec<Header>,
+    /// Message body, sized by the `Content-Length` header, or delimited by the last chunk of a
+    /// chunked body. Chunked bodies are not decoded, so the range includes the chunk framing and
+    /// trailer section.
+    pub body: Option<Range<usize>>,
+    /// Trailer fields received after the last chunk of a chunked body, in the order they were
+    /// received
+    pub trailers: Option<Vec<Header>>,
+    head_complete: bool,
+    body_start: usize,
+    config: ParserConfig,
+    scanned: usize,
 }
 
 impl Display for H1Request {
@@ -68,7 +87,7 @@ impl Display for H1Request {
         ))?;
         f.write_fmt(format_args!("{}\r\n", &self.version.as_ref().unwrap()))?;
 
-        for header in *self.headers.as_ref().unwrap() {
+        for header in &self.headers {
             f.write_fmt(format_args!(
                 "{}: {}\r\n",
                 from_utf8(&self.data[header.name.clone()]).unwrap(),
@@ -86,38 +105,464 @@ impl H1Request {
         Self::default()
     }
 
+    /// Creates a new HTTP/1.1 request which fails to parse with [`ParseError::BudgetExceeded`] once
+    /// more than `budget` bytes have been scanned without completing the request head.
+    ///
+    /// As each call to [`H1Request::parse`] scans the request from the start, the count
+    /// accumulates across calls. This bounds the CPU spent on a request delivered slowly in many
+    /// small reads, independent of the size of the buffer.
+    pub fn with_parse_budget(budget: usize) -> Self {
+        Self::with_config(ParserConfig {
+            parse_budget: Some(budget),
+            ..ParserConfig::default()
+        })
+    }
+
+    /// Creates a new HTTP/1.1 request which fails to parse once the request line exceeds
+    /// `max_line` bytes, or the header section exceeds `max_header_bytes` bytes. See
+    /// [`ParserConfig::max_request_line`] and [`ParserConfig::max_header_bytes`].
+    pub fn with_limits(max_line: usize, max_header_bytes: usize) -> Self {
+        Self::with_config(ParserConfig {
+            max_request_line: max_line,
+            max_header_bytes,
+            ..ParserConfig::default()
+        })
+    }
+
+    /// Creates a new HTTP/1.1 request, parsed according to `config`
+    pub fn with_config(config: ParserConfig) -> Self {
+        Self {
+            config,
+            ..Self::default()
+        }
+    }
+
+    /// Creates a new HTTP/1.1 request from bytes already received, taking ownership of `data`
+    /// rather than copying it as [`H1Request::fill`] does. More bytes may still be appended with
+    /// `fill` if the request is partial.
+    ///
+    /// ```rust
+    /// # use rask::parser::{Status, h1::request::H1Request};
+    /// let data = b"GET / HTTP/1.1\r\nHost: www.example.org\r\n\r\n".to_vec();
+    /// let mut req = H1Request::from_vec(data);
+    ///
+    /// assert_eq!(Ok(Status::Complete(41)), req.parse());
+    /// ```
+    pub fn from_vec(data: Vec<u8>) -> Self {
+        Self {
+            data,
+            ..Self::default()
+        }
+    }
+
+    /// Clears the parsed request so the request can be reused for the next request on a
+    /// persistent connection, retaining the allocated buffers. The parser configuration is kept.
+    ///
+    /// If the request was complete, any bytes received after it, such as a pipelined request,
+    /// are shifted to the front of the buffer to be parsed next. Otherwise all received bytes are
+    /// discarded.
+    pub fn reset(&mut self) {
+        let consumed = match self.body {
+            Some(ref body) if self.complete => body.end,
+            _ => self.data.len(),
+        };
+        self.data.drain(..consumed);
+
+        self.complete = false;
+        self.method = None;
+        self.target = None;
+        self.target_form = None;
+        self.version = None;
+        self.headers.clear();
+        self.body = None;
+        self.trailers = None;
+        self.head_complete = false;
+        self.body_start = 0;
+        self.scanned = 0;
+    }
+
+    /// Returns the raw bytes received for this request. Ranges stored on the request, such as
+    /// `target` and each [`Header`], index into this slice.
+    #[inline]
+    pub fn raw(&self) -> &[u8] {
+        &self.data
+    }
+
+    /// Returns the name/value ranges of every parsed header, in the order they were received.
+    ///
+    /// This is lower-level than resolving each header individually, and is intended for bulk
+    /// scans over the header section. The ranges index into [`H1Request::raw`]. An empty slice is
+    /// returned if no headers have been parsed.
+    #[inline]
+    pub fn header_ranges(&self) -> &[Header] {
+        &self.headers
+    }
+
+    /// Returns each parsed header as a name and raw value, in the order they were received,
+    /// borrowing from the request without allocating.
+    ///
+    /// ```rust
+    /// # use rask::parser::h1::request::H1Request;
+    /// let mut req = H1Request::new();
+    /// let mut req_buffer: &[u8] =
+    ///     b"GET / HTTP/1.1\r\nHost: www.example.org\r\nAccept: */*\r\n\r\n";
+    ///
+    /// req.fill(&mut req_buffer).unwrap();
+    /// req.parse().unwrap();
+    ///
+    /// let mut headers = req.iter_headers();
+    /// assert_eq!(Some(("Host", &b"www.example.org"[..])), headers.next());
+    /// assert_eq!(Some(("Accept", &b"*/*"[..])), headers.next());
+    /// assert_eq!(None, headers.next());
+    /// ```
+    pub fn iter_headers(&self) -> impl Iterator<Item = (&str, &[u8])> {
+        self.headers.iter().map(|header| {
+            (
+                // header names are validated as tokens, which are always ASCII
+                from_utf8(&self.data[header.name.clone()]).unwrap(),
+                &self.data[header.value.clone()],
+            )
+        })
+    }
+
+    /// Returns the value of the first header named `name`, compared case-insensitively.
+    pub fn header(&self, name: &[u8]) -> Option<&[u8]> {
+        find_header(&self.data, self.header_ranges(), name)
+    }
+
+    /// Returns the value of the first header named `name` as a string, for headers an
+    /// application requires to hold UTF-8. Fails with [`ParseError::HeaderValue`] if the value is
+    /// not valid UTF-8.
+    ///
+    /// ```rust
+    /// # use rask::parser::h1::request::H1Request;
+    /// let mut req = H1Request::new();
+    /// req.fill(&mut &b"GET / HTTP/1.1\r\nX-Data: {\"a\":1}\r\n\r\n"[..]).unwrap();
+    /// req.parse().unwrap();
+    ///
+    /// assert_eq!(Some(Ok("{\"a\":1}")), req.header_str("x-data"));
+    /// ```
+    pub fn header_str(&self, name: &str) -> Option<Result<&str, ParseError>> {
+        self.header(name.as_bytes())
+            .map(|value| from_utf8(value).map_err(|_| ParseError::HeaderValue))
+    }
+
+    /// Returns the value of the `X-Request-ID` header, used to correlate the request across the
+    /// services that handle it. See
+    /// [`Response::echo_request_id`](super::response::Response::echo_request_id).
+    pub fn request_id(&self) -> Option<&[u8]> {
+        self.header(b"X-Request-ID")
+    }
+
+    /// Returns the port of the `Host` header, or the default port of the connection's scheme if it
+    /// is omitted: 443 for TLS connections, and 80 otherwise. Returns `None` if there is no `Host`
+    /// header, or its port is not a valid port number. IPv6 addresses are enclosed in brackets.
+    ///
+    /// Defined by RFC 9110 Section 7.2 and RFC 3986 Section 3.2 by ABNF
+    /// ```abnf
+    /// Host = uri-host [ ":" port ]
+    /// port = *DIGIT
+    /// ```
+    ///
+    /// ```rust
+    /// # use rask::parser::h1::request::H1Request;
+    /// let mut req = H1Request::new();
+    /// req.fill(&mut &b"GET / HTTP/1.1\r\nHost: [::1]:9000\r\n\r\n"[..]).unwrap();
+    /// req.parse().unwrap();
+    ///
+    /// assert_eq!(Some(9000), req.host_port(false));
+    /// ```
+    pub fn host_port(&self, is_tls: bool) -> Option<u16> {
+        let host = self.header(b"Host")?;
+        let port = match host.strip_prefix(b"[") {
+            Some(literal) => {
+                let end = literal.iter().position(|&b| b == b']')?;
+                match &literal[end + 1..] {
+                    [] => None,
+                    [b':', port @ ..] => Some(port),
+                    _ => return None,
+                }
+            }
+            None => match host.iter().position(|&b| b == b':') {
+                // an IPv6 address must be enclosed in brackets
+                Some(n) if host[n + 1..].contains(&b':') => return None,
+                Some(n) => Some(&host[n + 1..]),
+                None => None,
+            },
+        };
+
+        match port {
+            Some(port) if !port.is_empty() => {
+                if !port.iter().all(u8::is_ascii_digit) {
+                    return None;
+                }
+                from_utf8(port).ok()?.parse().ok()
+            }
+            _ if is_tls => Some(443),
+            _ => Some(80),
+        }
+    }
+
+    /// Returns the value of the `HTTP2-Settings` header if the request asks to upgrade the
+    /// connection to cleartext HTTP/2 (h2c): an HTTP/1.1 request whose `Upgrade` header lists
+    /// `h2c` and whose `Connection` header lists both `Upgrade` and `HTTP2-Settings`, carrying
+    /// exactly one `HTTP2-Settings` header. The value is not decoded.
+    /// [RFC 7540 Section 3.2](https://www.rfc-editor.org/rfc/rfc7540#section-3.2)
+    pub fn h2c_upgrade_settings(&self) -> Option<&[u8]> {
+        if self.version != Some(Version::H1_1)
+            || !self
+                .list_header(b"Upgrade")
+                .any(|protocol| protocol.eq_ignore_ascii_case(b"h2c"))
+        {
+            return None;
+        }
+
+        let mut upgrade = false;
+        let mut settings = false;
+        for option in self.list_header(b"Connection") {
+            upgrade |= option.eq_ignore_ascii_case(b"Upgrade");
+            settings |= option.eq_ignore_ascii_case(b"HTTP2-Settings");
+        }
+
+        let mut values = self.headers.iter().filter(|header| {
+            self.data[header.name.clone()].eq_ignore_ascii_case(b"HTTP2-Settings")
+        });
+        match (upgrade && settings, values.next(), values.next()) {
+            (true, Some(header), None) => Some(&self.data[header.value.clone()]),
+            _ => None,
+        }
+    }
+
+    /// Returns true if an `Expect` header lists any expectation other than `100-continue`,
+    /// compared case-insensitively. Such requests must be rejected with
+    /// [`ExpectationFailed`](crate::parser::status::Status::ExpectationFailed), even when
+    /// `100-continue` is listed alongside them.
+    ///
+    /// Defined by RFC 9110 Section 10.1.1 by ABNF
+    /// ```abnf
+    /// Expect      = #expectation
+    /// expectation = token [ "=" ( token / quoted-string ) parameters ]
+    /// ```
+    pub fn unsupported_expectation(&self) -> bool {
+        self.list_header(b"Expect")
+            .any(|token| !token.eq_ignore_ascii_case(b"100-continue"))
+    }
+
+    /// Returns true if the client is waiting for a `100 Continue` interim response before sending
+    /// the request body. This requires an HTTP/1.1 request whose `Expect` header lists
+    /// `100-continue` and that declares a non-empty body. Requests with `Content-Length: 0` have
+    /// no body to wait for, so the final response should be sent directly.
+    /// [RFC 9110 Section 10.1.1](https://www.rfc-editor.org/rfc/rfc9110#section-10.1.1)
+    pub fn expects_continue(&self) -> bool {
+        self.version == Some(Version::H1_1)
+            && self
+                .list_header(b"Expect")
+                .any(|token| token.eq_ignore_ascii_case(b"100-continue"))
+            && (is_chunked(&self.data, &self.headers)
+                || parse_content_length(&self.data, &self.headers, &self.config)
+                    .is_ok_and(|len| len > 0))
+    }
+
+    /// Returns true if the connection should persist after this request, per RFC 9112 Section
+    /// 9.3. HTTP/1.1 connections persist unless the `Connection` header lists `close`, while
+    /// HTTP/1.0 connections close unless it lists `keep-alive`.
+    pub fn should_keep_alive(&self) -> bool {
+        let mut close = false;
+        let mut keep_alive = false;
+        for option in self.list_header(b"Connection") {
+            close |= option.eq_ignore_ascii_case(b"close");
+            keep_alive |= option.eq_ignore_ascii_case(b"keep-alive");
+        }
+
+        !close
+            && match self.version {
+                Some(Version::H1_1) => true,
+                Some(Version::H1_0) => keep_alive,
+                _ => false,
+            }
+    }
+
+    /// Returns true if the connection can be reused after responding to this request. In addition
+    /// to the version defaults and `Connection` options of [`H1Request::should_keep_alive`], the
+    /// connection is closed when the body framing is ambiguous: a `Transfer-Encoding` sent
+    /// alongside `Content-Length` may be framed differently by an intermediary.
+    /// [RFC 9112 Section 6.3](https://www.rfc-editor.org/rfc/rfc9112#section-6.3)
+    ///
+    /// ```rust
+    /// # use rask::parser::h1::request::H1Request;
+    /// let mut req = H1Request::new();
+    /// req.fill(&mut &b"GET / HTTP/1.0\r\nConnection: keep-alive\r\n\r\n"[..]).unwrap();
+    /// req.parse().unwrap();
+    ///
+    /// assert!(req.is_keep_alive());
+    /// ```
+    pub fn is_keep_alive(&self) -> bool {
+        self.should_keep_alive() && !self.has_ambiguous_framing()
+    }
+
+    /// Validates the parsed request against the rules that apply once every header is known.
+    /// Requests with more than one `Host` header fail with [`ParseError::DuplicateHost`], and
+    /// HTTP/1.1 requests without one fail with [`ParseError::MissingHost`], unless the target is
+    /// in absolute-form, which carries the host itself. HTTP/1.0 requests may omit it.
+    /// [RFC 9112 Section 3.2](https://www.rfc-editor.org/rfc/rfc9112#section-3.2)
+    ///
+    /// ```rust
+    /// # use rask::parser::{h1::request::H1Request, ParseError};
+    /// let mut req = H1Request::new();
+    /// req.fill(&mut &b"GET / HTTP/1.1\r\nAccept: */*\r\n\r\n"[..]).unwrap();
+    /// req.parse().unwrap();
+    ///
+    /// assert_eq!(Err(ParseError::MissingHost), req.validate());
+    /// ```
+    pub fn validate(&self) -> Result<(), ParseError> {
+        let mut hosts = self
+            .headers
+            .iter()
+            .filter(|header| self.data[header.name.clone()].eq_ignore_ascii_case(b"Host"));
+        match (hosts.next(), hosts.next()) {
+            (Some(_), Some(_)) => Err(ParseError::DuplicateHost),
+            (None, _)
+                if self.version == Some(Version::H1_1)
+                    && self.target_form != Some(TargetForm::Absolute) =>
+            {
+                Err(ParseError::MissingHost)
+            }
+            _ => Ok(()),
+        }
+    }
+
+    #[inline]
+    fn has_ambiguous_framing(&self) -> bool {
+        self.header(b"Transfer-Encoding").is_some() && self.header(b"Content-Length").is_some()
+    }
+
+    /// Returns the non-empty elements of every comma separated list header named `name`.
+    #[inline]
+    fn list_header<'a>(&'a self, name: &'a [u8]) -> impl Iterator<Item = &'a [u8]> {
+        self.headers
+            .iter()
+            .filter(move |header| self.data[header.name.clone()].eq_ignore_ascii_case(name))
+            .flat_map(|header| self.data[header.value.clone()].split(|&b| b == b','))
+            .map(trim_whitespace)
+            .filter(|element| !element.is_empty())
+    }
+
+    /// Returns true once the request line and headers have been parsed, even if the body has not
+    /// been completely received.
+    #[inline]
+    pub fn is_head_complete(&self) -> bool {
+        self.head_complete
+    }
+
+    /// Returns the message body, once the request has been completely received. Requests without
+    /// a `Content-Length` header have an empty body.
+    #[inline]
+    pub fn body_bytes(&self) -> Option<&[u8]> {
+        self.body.clone().map(|body| &self.data[body])
+    }
+
+    /// Returns a reader streaming the message body once the request head has been parsed, reading
+    /// the body bytes already received before reading more from `stream`. Chunked bodies are
+    /// decoded. Returns `None` until the head is complete.
+    ///
+    /// The request does not see bytes read through the reader, so it should not be parsed further
+    /// once the body has been read from the stream.
+    pub fn body_reader<R: Read>(&self, stream: R) -> Option<RequestBody<'_, R>> {
+        if !self.head_complete {
+            return None;
+        }
+
+        let received = &self.data[self.body_start..];
+        if is_chunked(&self.data, &self.headers) {
+            return Some(RequestBody::chunked(received, stream, &self.config));
+        }
+
+        let len = parse_content_length(&self.data, &self.headers, &self.config).ok()?;
+        Some(RequestBody::with_length(received, stream, len as u64))
+    }
+
+    /// Parses the request target into a structured [`Uri`], borrowing from the request buffer.
+    /// Fails with [`ParseError::Target`] if the target has not been parsed yet, or is malformed.
+    pub fn uri(&self) -> Result<Uri<'_>, ParseError> {
+        let target = self.target.clone().ok_or(ParseError::Target)?;
+        Uri::parse(&self.data[target])
+    }
+
+    /// Returns the request target up to its first `?`, or the whole target if it has no query.
+    /// Returns `None` if the target has not been parsed yet.
+    ///
+    /// Unlike [`H1Request::uri`], the target is split without being validated or re-scanned.
+    pub fn path(&self) -> Option<&[u8]> {
+        let target = &self.data[self.target.clone()?];
+        let end = target
+            .iter()
+            .position(|&b| b == b'?')
+            .unwrap_or(target.len());
+
+        Some(&target[..end])
+    }
+
+    /// Returns the path of the request target with every percent-encoded octet decoded, for
+    /// routing. Fails with [`ParseError::Target`] if the target has not been parsed yet, or
+    /// [`ParseError::PercentEncoding`] if the path holds a malformed percent-encoded octet.
+    ///
+    /// An encoded `/` is decoded like any other octet, so the result can no longer be split into
+    /// the segments the client intended. Use [`Uri::segments`] when that distinction matters.
+    pub fn decoded_path(&self) -> Result<Vec<u8>, ParseError> {
+        percent_decode(self.path().ok_or(ParseError::Target)?)
+    }
+
+    /// Returns the request target after its first `?`, or `None` if the target has no query or
+    /// has not been parsed yet.
+    pub fn query(&self) -> Option<&[u8]> {
+        let target = &self.data[self.target.clone()?];
+        let start = target.iter().position(|&b| b == b'?')?;
+
+        Some(&target[start + 1..])
+    }
+
     /// Fills the request buffer with data received for the connection
     pub fn fill<R: Read>(&mut self, reader: &mut R) -> io::Result<usize> {
-        let mut total_read = 0;
-        let mut bytes = [0u8; 4096];
-        loop {
-            match reader.read(&mut bytes) {
-                Ok(0) => return Ok(0),
-                Ok(n) => {
-                    total_read += n;
-                    self.data.extend_from_slice(&bytes[..n]);
-                }
+        const READ_SIZE: usize = 4096;
+
+        // reads directly into the buffer rather than copying from a scratch buffer. The space read
+        // into is zeroed rather than left uninitialized, as `Read` implementations may read from
+        // it. Within a call, a window is only zeroed again once reads have filled it, but any
+        // unfilled part is truncated on return and zeroed again by the next call
+        let start = self.data.len();
+        let mut filled = start;
+        let result = loop {
+            if self.data.len() == filled {
+                self.data.resize(filled + READ_SIZE, 0);
+            }
+
+            match reader.read(&mut self.data[filled..]) {
+                Ok(0) => break Ok(0),
+                Ok(n) => filled += n,
                 Err(e) => match e.kind() {
                     ErrorKind::WouldBlock => {
-                        if total_read == 0 {
-                            return Err(e);
+                        if filled == start {
+                            break Err(e);
                         } else {
-                            return Ok(total_read);
+                            break Ok(filled - start);
                         }
                     }
                     ErrorKind::Interrupted => {}
-                    _ => return Err(e),
+                    _ => break Err(e),
                 },
             }
-        }
+        };
+
+        self.data.truncate(filled);
+        result
     }
 
     /// Fills the request buffer with exactly N bytes
     pub fn fill_exact<R: Read>(&mut self, reader: &mut R, n: usize) -> io::Result<()> {
-        // buffer may have write capacity left. To avoid blocking, resize correctly
-        let len = self.data.len().saturating_sub(1);
+        // append after any bytes already received, as a request may span several TLS records
+        let len = self.data.len();
         self.data.resize(len + n, 0);
-        reader.read_exact(&mut self.data)
+        reader.read_exact(&mut self.data[len..])
     }
 
     /// Parses a request
@@ -136,333 +581,912 @@ impl H1Request {
     /// assert_eq!(Some(Method::Get), req.method);
     /// assert_eq!(Some(4..5), req.target);
     /// assert_eq!(Some(Version::H1_1), req.version);
-    /// assert!(req.headers.is_some());
-    /// assert_eq!(Header {name: 16..20, value: 21..36}, req.headers.unwrap()[0]);
+    /// assert_eq!(Header {name: 16..20, value: 21..36}, req.headers[0]);
     /// assert_eq!(true, req.complete);
     /// # Ok(())
     /// # }
     /// ```
     pub fn parse(&mut self) -> ParseResult<usize> {
-        let mut pos: usize;
+        self.scanned = self.scanned.saturating_add(self.data.len());
+
+        match self.parse_head()? {
+            Status::Partial
+                if !self.head_complete
+                    && self
+                        .config
+                        .parse_budget
+                        .is_some_and(|budget| self.scanned > budget) =>
+            {
+                Err(ParseError::BudgetExceeded)
+            }
+            status => Ok(status),
+        }
+    }
 
-        match parse_method(&self.data) {
-            Ok(Status::Complete((read, method))) => {
-                pos = read;
-                self.method = Some(method)
+    /// Parses a request once the peer has closed the connection, so no more bytes will be
+    /// received. A request that would otherwise be [`Status::Partial`] fails with
+    /// [`ParseError::Incomplete`] instead, as it can never complete, or with
+    /// [`ParseError::UnterminatedHeaders`] if it was truncated after the request line, within the
+    /// header section. If no bytes were received, the peer closed between requests and the
+    /// request remains partial.
+    pub fn parse_final(&mut self) -> ParseResult<usize> {
+        match self.parse()? {
+            Status::Partial if !self.head_complete && self.method.is_some() => {
+                Err(ParseError::UnterminatedHeaders)
+            }
+            Status::Partial if !self.data.is_empty() => Err(ParseError::Incomplete),
+            status => Ok(status),
+        }
+    }
+
+    #[inline]
+    fn parse_head(&mut self) -> ParseResult<usize> {
+        let mut pos = match parse_request_line(&self.data, &self.config) {
+            Ok(Status::Complete((read, line))) => {
+                self.method = Some(line.method);
+                self.target = Some(line.target);
+                self.target_form = Some(line.target_form);
+                self.version = Some(line.version);
+                read
             }
             Ok(Status::Partial) => return Ok(Status::Partial),
             Err(err) => return Err(err),
         };
 
-        match discard_required_whitespace(&self.data, pos, ParseError::Method) {
+        self.headers.clear();
+        match parse_headers(&self.data, pos, &mut self.headers, &self.config) {
             Ok(Status::Complete(n)) => pos = n,
             Ok(Status::Partial) => return Ok(Status::Partial),
             Err(err) => return Err(err),
         };
 
-        match parse_target(&self.data, pos) {
-            Ok(Status::Complete((read, target))) => {
-                pos = read;
-                self.target = Some(target);
-            }
-            Ok(Status::Partial) => return Ok(Status::Partial),
-            Err(err) => return Err(err),
+        if self.config.allow_obs_fold {
+            unfold_header_values(&mut self.data, &self.headers);
         }
 
-        match discard_required_whitespace(&self.data, pos, ParseError::Target) {
-            Ok(Status::Complete(n)) => pos = n,
-            Ok(Status::Partial) => return Ok(Status::Partial),
-            Err(err) => return Err(err),
-        };
+        if let Some(version) = &self.version {
+            validate_framing(&self.data, &self.headers, version)?;
+        }
 
-        match parse_version(&self.data, pos) {
-            Ok(Status::Complete((read, version))) => {
-                pos = read;
-                self.version = Some(version);
-            }
-            Ok(Status::Partial) => return Ok(Status::Partial),
-            Err(err) => return Err(err),
-        };
+        let content_length = parse_content_length(&self.data, &self.headers, &self.config)?;
 
-        match discard_required_newline(&self.data, pos, ParseError::NewLine) {
+        match discard_newline(&self.data, pos, ParseError::NewLine, &self.config) {
             Ok(Status::Complete(n)) => pos = n,
             Ok(Status::Partial) => return Ok(Status::Partial),
             Err(err) => return Err(err),
         };
 
-        unsafe {
-            let mut headers: [MaybeUninit<Header>; 96] = MaybeUninit::uninit().assume_init();
-            let headers = &mut headers as *mut [MaybeUninit<Header>];
-            match parse_headers(&self.data, pos, &mut *headers) {
-                Ok(status) => {
-                    let headers = &*(headers as *mut [Header]);
-                    match status {
-                        HeaderStatus::Complete((read, num_headers)) => {
-                            self.headers = Some(&headers[0..num_headers]);
-                            pos = read;
-                        }
-                        HeaderStatus::Partial(num_headers) => {
-                            self.headers = Some(&headers[0..num_headers]);
-                            return Ok(Status::Partial);
-                        }
-                    }
-                }
-                Err(err) => {
-                    std::mem::take(&mut &mut *headers);
-                    return Err(err);
+        self.head_complete = true;
+        self.body_start = pos;
+
+        // a chunked transfer coding takes precedence over Content-Length
+        let end = if is_chunked(&self.data, &self.headers) {
+            let mut trailers = Vec::new();
+            match parse_chunked_body(&self.data, pos, &mut trailers, &self.config) {
+                Ok(Status::Complete(end)) => {
+                    validate_trailers(&self.data, &trailers)?;
+                    self.trailers = Some(trailers);
+                    end
                 }
+                Ok(Status::Partial) => return Ok(Status::Partial),
+                Err(err) => return Err(err),
             }
-        }
-
-        match discard_required_newline(&self.data, pos, ParseError::NewLine) {
-            Ok(Status::Complete(n)) => pos = n,
-            Ok(Status::Partial) => return Ok(Status::Partial),
-            Err(err) => return Err(err),
+        } else {
+            let end = pos
+                .checked_add(content_length)
+                .ok_or(ParseError::ContentLength)?;
+            if self.data.len() < end {
+                return Ok(Status::Partial);
+            }
+            end
         };
 
+        self.body = Some(pos..end);
         self.complete = true;
 
-        Ok(Status::Complete(pos))
+        Ok(Status::Complete(end))
     }
 }
 
 #[inline]
-fn parse_method(buf: &[u8]) -> ParseResult<(usize, Method)> {
-    if buf.len() < 8 {
-        return Ok(Status::Partial);
-    }
-
-    let eight: [u8; 8] = buf[..8].try_into().map_err(|_| ParseError::Method)?;
-    let eight = u64::from_ne_bytes(eight);
+fn find_header<'a>(buf: &'a [u8], headers: &[Header], name: &[u8]) -> Option<&'a [u8]> {
+    headers
+        .iter()
+        .find(|header| buf[header.name.clone()].eq_ignore_ascii_case(name))
+        .map(|header| &buf[header.value.clone()])
+}
 
-    if eight & 0x0000_0000_00ff_ffff == u64::from_le_bytes([b'G', b'E', b'T', 0, 0, 0, 0, 0]) {
-        Ok(Status::Complete((3, Method::Get)))
-    } else if eight & 0x0000_0000_00ff_ffff == u64::from_le_bytes([b'P', b'U', b'T', 0, 0, 0, 0, 0])
-    {
-        Ok(Status::Complete((3, Method::Put)))
-    } else if eight & 0x0000_0000_ffff_ffff
-        == u64::from_le_bytes([b'P', b'O', b'S', b'T', 0, 0, 0, 0])
-    {
-        Ok(Status::Complete((4, Method::Post)))
-    } else if eight & 0x0000_0000_ffff_ffff
-        == u64::from_le_bytes([b'H', b'E', b'A', b'D', 0, 0, 0, 0])
-    {
-        Ok(Status::Complete((4, Method::Head)))
-    } else if eight & 0x0000_00ff_ffff_ffff
-        == u64::from_le_bytes([b'T', b'R', b'A', b'C', b'E', 0, 0, 0])
-    {
-        Ok(Status::Complete((5, Method::Trace)))
-    } else if eight & 0x0000_ffff_ffff_ffff
-        == u64::from_le_bytes([b'D', b'E', b'L', b'E', b'T', b'E', 0, 0])
-    {
-        Ok(Status::Complete((6, Method::Delete)))
-    } else if eight & 0x00ff_ffff_ffff_ffff
-        == u64::from_le_bytes([b'O', b'P', b'T', b'I', b'O', b'N', b'S', 0])
-    {
-        Ok(Status::Complete((7, Method::Options)))
-    } else if eight & 0x00ff_ffff_ffff_ffff
-        == u64::from_le_bytes([b'C', b'O', b'N', b'N', b'E', b'C', b'T', 0])
+/// Rejects message framing the request's version does not support, or that leaves the body
+/// without a length. `Transfer-Encoding` was introduced by HTTP/1.1, so an HTTP/1.0 request
+/// carrying it is malformed. A request whose final transfer coding is not `chunked` can only be
+/// delimited by the connection closing, which a request cannot rely on.
+/// [RFC 9112 Section 6.1](https://www.rfc-editor.org/rfc/rfc9112#section-6.1)
+/// [RFC 9112 Section 6.3](https://www.rfc-editor.org/rfc/rfc9112#section-6.3)
+#[inline]
+fn validate_framing(buf: &[u8], headers: &[Header], version: &Version) -> Result<(), ParseError> {
+    if find_header(buf, headers, b"Transfer-Encoding").is_some()
+        && (*version == Version::H1_0 || !is_chunked(buf, headers))
     {
-        Ok(Status::Complete((7, Method::Connect)))
-    } else {
-        Err(ParseError::Method)
+        return Err(ParseError::ConflictingFraming);
     }
+
+    Ok(())
 }
 
-#[cfg(all(
-    target_feature = "avx2",
-    any(target_arch = "x86", target_arch = "x86_64")
-))]
+/// Parses the length of the message body from the `Content-Length` header, defaulting to an empty
+/// body when it is absent. Repeated headers are rejected unless permitted by `config` and every
+/// value is identical.
+/// Defined by RFC 9110 Section 8.6 by ABNF
+/// ```abnf
+/// Content-Length = 1*DIGIT
+/// ```
 #[inline]
-#[allow(overflowing_literals)]
-fn parse_target_vectorized_avx2(buf: &[u8], mut pos: usize) -> Result<usize, usize> {
-    #[cfg(target_arch = "x86")]
-    use core::arch::x86::*;
-    #[cfg(target_arch = "x86_64")]
-    use core::arch::x86_64::*;
+fn parse_content_length(
+    buf: &[u8],
+    headers: &[Header],
+    config: &ParserConfig,
+) -> Result<usize, ParseError> {
+    let mut values = headers
+        .iter()
+        .filter(|header| buf[header.name.clone()].eq_ignore_ascii_case(b"Content-Length"))
+        .map(|header| &buf[header.value.clone()]);
+
+    let Some(value) = values.next() else {
+        return Ok(0);
+    };
 
-    unsafe {
-        let row_map = _mm256_setr_epi8(
-            0x01, 0x02, 0x04, 0x08, 0x10, 0x20, 0x40, 0x80, // prevent fmt
-            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // prevent fmt
-            0x01, 0x02, 0x04, 0x08, 0x10, 0x20, 0x40, 0x80, // prevent fmt
-            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
-        );
-        let col_map = _mm256_setr_epi8(
-            0xf8, 0xfc, 0xfc, 0xfc, 0xfc, 0xfc, 0xfc, 0xfc, // prevent fmt
-            0xfc, 0xfc, 0xfc, 0xfc, 0xf4, 0xfc, 0xf4, 0x7c, // prevent fmt
-            0xf8, 0xfc, 0xfc, 0xfc, 0xfc, 0xfc, 0xfc, 0xfc, // prevent fmt
-            0xfc, 0xfc, 0xfc, 0xfc, 0xf4, 0xfc, 0xf4, 0x7c,
-        );
-        let lower_mask = _mm256_set1_epi8(0x0f);
+    for duplicate in values {
+        if !config.allow_duplicate_content_length || duplicate != value {
+            return Err(ParseError::ContentLength);
+        }
+    }
 
-        while buf[pos..].len() >= 32 {
-            let data = _mm256_lddqu_si256(buf[pos..].as_ptr() as *const _);
+    // Content-Length = 1*DIGIT
+    // [RFC 9110 Section 8.6](https://www.rfc-editor.org/rfc/rfc9110#section-8.6)
+    if value.is_empty() {
+        return Err(ParseError::ContentLength);
+    }
 
-            // divide by 2^4 to get row and take lower half as shuffle control mask
-            let lower_div16 = _mm256_and_si256(lower_mask, _mm256_srli_epi16(data, 4));
-            let row_mask = _mm256_shuffle_epi8(row_map, lower_div16);
-            let col_mask = _mm256_shuffle_epi8(col_map, data);
+    value.iter().try_fold(0usize, |acc, &b| {
+        if !b.is_ascii_digit() {
+            return Err(ParseError::ContentLength);
+        }
 
-            let row_col = _mm256_and_si256(row_mask, col_mask);
-            let valid = _mm256_cmpeq_epi8(row_col, _mm256_setzero_si256());
-            let num_valid = (_mm256_movemask_epi8(valid) as u32).trailing_zeros();
+        acc.checked_mul(10)
+            .and_then(|acc| acc.checked_add(usize::from(b - b'0')))
+            .ok_or(ParseError::ContentLength)
+    })
+}
 
-            pos += num_valid as usize;
+/// HTTP/1.1 request parsed without copying, borrowing from the buffer it was parsed from.
+///
+/// Unlike [`H1Request`], the caller owns the buffer and must hold every byte of the request
+/// before parsing. See [`parse_borrowed`].
+#[derive(Debug, PartialEq, Eq)]
+pub struct BorrowedRequest<'a> {
+    /// Request method
+    pub method: Method,
+    /// Request target
+    pub target: &'a [u8],
+    /// HTTP version
+    pub version: Version,
+    /// Header names and values, in the order they were received
+    pub headers: Vec<(&'a [u8], &'a [u8])>,
+}
 
-            if num_valid != 32 {
-                return Ok(pos);
-            }
-        }
-    }
+/// Parses a request directly from `buf`, without first copying it into an [`H1Request`].
+/// Returns the number of bytes consumed along with the request, or [`Status::Partial`] if `buf`
+/// does not yet hold a complete request head.
+///
+/// # Example
+/// ```
+/// # use rask::parser::{Method, Status, Version};
+/// # use rask::parser::h1::request::parse_borrowed;
+/// let buf: &[u8] = b"GET / HTTP/1.1\r\nHost:www.example.org\r\n\r\n";
+///
+/// let Ok(Status::Complete((read, req))) = parse_borrowed(buf) else {
+///     panic!("request is complete");
+/// };
+///
+/// assert_eq!(buf.len(), read);
+/// assert_eq!(Method::Get, req.method);
+/// assert_eq!(b"/", req.target);
+/// assert_eq!(Version::H1_1, req.version);
+/// assert_eq!(vec![(b"Host" as &[u8], b"www.example.org" as &[u8])], req.headers);
+/// ```
+pub fn parse_borrowed(buf: &[u8]) -> ParseResult<(usize, BorrowedRequest<'_>)> {
+    let config = ParserConfig::default();
+    let (mut pos, line) = match parse_request_line(buf, &config) {
+        Ok(Status::Complete(complete)) => complete,
+        Ok(Status::Partial) => return Ok(Status::Partial),
+        Err(err) => return Err(err),
+    };
 
-    Err(pos)
+    let mut headers = Vec::new();
+    match parse_headers(buf, pos, &mut headers, &config) {
+        Ok(Status::Complete(n)) => pos = n,
+        Ok(Status::Partial) => return Ok(Status::Partial),
+        Err(err) => return Err(err),
+    };
+
+    match discard_newline(buf, pos, ParseError::NewLine, &config) {
+        Ok(Status::Complete(n)) => pos = n,
+        Ok(Status::Partial) => return Ok(Status::Partial),
+        Err(err) => return Err(err),
+    };
+
+    Ok(Status::Complete((
+        pos,
+        BorrowedRequest {
+            method: line.method,
+            target: &buf[line.target],
+            version: line.version,
+            headers: headers
+                .into_iter()
+                .map(|header| (&buf[header.name], &buf[header.value]))
+                .collect(),
+        },
+    )))
 }
 
-#[cfg(all(
-    target_feature = "ssse3",
-    any(target_arch = "x86", target_arch = "x86_64")
-))]
+/// Classifies the request method from the start of `buf` without parsing the rest of the request,
+/// allowing a connection to be routed before it is fully parsed. Returns `None` if `buf` does
+/// not yet hold the method and its trailing space, or the method is not recognized.
+///
+/// ```rust
+/// # use rask::parser::Method;
+/// # use rask::parser::h1::request::peek_method;
+/// assert_eq!(Some(Method::Connect), peek_method(b"CONNECT "));
+/// assert_eq!(Some(Method::Get), peek_method(b"GET /"));
+/// assert_eq!(None, peek_method(b"GE"));
+/// ```
 #[inline]
-#[allow(overflowing_literals)]
-fn parse_target_vectorized_ssse3(buf: &[u8], mut pos: usize) -> Result<usize, usize> {
-    #[cfg(target_arch = "x86")]
-    use core::arch::x86::*;
-    #[cfg(target_arch = "x86_64")]
-    use core::arch::x86_64::*;
+pub fn peek_method(buf: &[u8]) -> Option<Method> {
+    let end = buf.iter().take(8).position(|&b| b == b' ')?;
 
-    unsafe {
-        let row_map: __m128i = _mm_setr_epi8(
-            0x01, 0x02, 0x04, 0x08, 0x10, 0x20, 0x40, 0x80, // prevent fmt
-            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
-        );
-        let col_map: __m128i = _mm_setr_epi8(
-            0xf8, 0xfc, 0xfc, 0xfc, 0xfc, 0xfc, 0xfc, 0xfc, // prevent fmt
-            0xfc, 0xfc, 0xfc, 0xfc, 0xf4, 0xfc, 0xf4, 0x7c,
-        );
-        let lower_mask: __m128i = _mm_set1_epi8(0x0f);
+    Method::try_from(&buf[..end]).ok()
+}
 
-        while buf[pos..].len() >= 16 {
-            let data = _mm_lddqu_si128(buf[pos..].as_ptr() as *const _);
+/// Cheaply checks whether `buf` holds a complete request head, without parsing it, by scanning for
+/// the empty line that terminates the header section. Returns the offset just past the
+/// terminator, or `None` if it has not been received yet.
+///
+/// As the whole buffer is scanned, a terminator split across reads is found once its remaining
+/// bytes have been appended. Only `CRLF CRLF` is recognized, so heads terminated by bare line
+/// feeds must be parsed to be detected.
+///
+/// ```rust
+/// # use rask::parser::h1::request::has_complete_headers;
+/// let buf = b"GET / HTTP/1.1\r\nHost: www.example.org\r\n\r\nbody";
+/// assert_eq!(Some(41), has_complete_headers(buf));
+/// assert_eq!(None, has_complete_headers(&buf[..40]));
+/// ```
+pub fn has_complete_headers(buf: &[u8]) -> Option<usize> {
+    find_header_terminator(buf, 0).ok()
+}
 
-            // divide by 2^4 and only take lower half
-            let lower_div16 = _mm_and_si128(lower_mask, _mm_srli_epi16(data, 4));
-            let row_mask = _mm_shuffle_epi8(row_map, lower_div16);
-            let col_mask = _mm_shuffle_epi8(col_map, data);
+/// Returns true if the running CPU supports AVX2. The result is cached by the standard library,
+/// and is known at compile time when the build already targets AVX2.
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+#[inline]
+pub(crate) fn has_avx2() -> bool {
+    std::is_x86_feature_detected!("avx2")
+}
 
-            let row_col = _mm_and_si128(row_mask, col_mask);
-            let valid = _mm_cmpeq_epi8(row_col, _mm_setzero_si128());
-            let num_valid = (0xffff_0000 | _mm_movemask_epi8(valid) as u32).trailing_zeros();
+/// Returns true if the running CPU supports SSSE3. See [`has_avx2`].
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+#[inline]
+pub(crate) fn has_ssse3() -> bool {
+    std::is_x86_feature_detected!("ssse3")
+}
 
-            pos += num_valid as usize;
+/// Returns the offset just past the first `CRLF CRLF` at or after `pos`, or `Err` if there is
+/// none. Dispatches to the widest vectorized implementation the CPU supports, finishing any
+/// remainder with [`find_header_terminator_scalar`].
+#[inline]
+#[cfg_attr(
+    not(any(target_arch = "x86", target_arch = "x86_64")),
+    allow(unused_mut)
+)]
+fn find_header_terminator(buf: &[u8], mut pos: usize) -> Result<usize, usize> {
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    {
+        if has_avx2() {
+            // SAFETY: AVX2 support was detected at runtime
+            match unsafe { find_header_terminator_avx2(buf, pos) } {
+                Ok(n) => return Ok(n),
+                Err(n) => pos = n,
+            };
+        }
 
-            if num_valid != 16 {
-                return Ok(pos);
+        if has_ssse3() {
+            // SAFETY: SSSE3 support was detected at runtime
+            match unsafe { find_header_terminator_ssse3(buf, pos) } {
+                Ok(n) => return Ok(n),
+                Err(n) => pos = n,
+            };
+        }
+    }
+
+    find_header_terminator_scalar(buf, pos)
+}
+
+#[inline]
+fn find_header_terminator_scalar(buf: &[u8], pos: usize) -> Result<usize, usize> {
+    buf[pos..]
+        .windows(4)
+        .position(|window| window == b"\r\n\r\n")
+        .map(|n| pos + n + 4)
+        .ok_or(buf.len())
+}
+
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+#[target_feature(enable = "avx2")]
+#[inline]
+unsafe fn find_header_terminator_avx2(buf: &[u8], mut pos: usize) -> Result<usize, usize> {
+    #[cfg(target_arch = "x86")]
+    use core::arch::x86::*;
+    #[cfg(target_arch = "x86_64")]
+    use core::arch::x86_64::*;
+
+    unsafe {
+        let cr = _mm256_set1_epi8(b'\r' as i8);
+        let lf = _mm256_set1_epi8(b'\n' as i8);
+
+        // each lane checks the four bytes starting at its offset, so three bytes past the block
+        // must be readable
+        while buf[pos..].len() >= 32 + 3 {
+            let ptr = buf[pos..].as_ptr();
+            let first = _mm256_cmpeq_epi8(_mm256_lddqu_si256(ptr as *const _), cr);
+            let second = _mm256_cmpeq_epi8(_mm256_lddqu_si256(ptr.add(1) as *const _), lf);
+            let third = _mm256_cmpeq_epi8(_mm256_lddqu_si256(ptr.add(2) as *const _), cr);
+            let fourth = _mm256_cmpeq_epi8(_mm256_lddqu_si256(ptr.add(3) as *const _), lf);
+
+            let found = _mm256_and_si256(
+                _mm256_and_si256(first, second),
+                _mm256_and_si256(third, fourth),
+            );
+            let mask = _mm256_movemask_epi8(found) as u32;
+
+            if mask != 0 {
+                return Ok(pos + mask.trailing_zeros() as usize + 4);
             }
+
+            pos += 32;
         }
     }
 
     Err(pos)
 }
 
-// #[inline]
-// fn parse_target(buf: &[u8], mut pos: usize) -> ParseResult<(usize, Range<usize>)> {
-//     let start = pos;
-//
-//     #[cfg(all(
-//         target_feature = "avx2",
-//         any(target_arch = "x86", target_arch = "x86_64")
-//     ))]
-//     match parse_target_vectorized_avx2(buf, pos) {
-//         Ok(n) => return Ok(Status::Complete((n, start..n))),
-//         Err(n) => pos = n,
-//     };
-//
-//     #[cfg(all(
-//         target_feature = "ssse3",
-//         any(target_arch = "x86", target_arch = "x86_64")
-//     ))]
-//     match parse_target_vectorized_ssse3(buf, pos) {
-//         Ok(n) => return Ok(Status::Complete((n, start..n))),
-//         Err(n) => pos = n,
-//     };
-//
-//     for &b in &buf[pos..] {
-//         if !is_request_target_token(b) {
-//             if pos == start {
-//                 return Err(ParseError::Target);
-//             }
-//
-//             return Ok(Status::Complete((pos, start..pos)));
-//         }
-//
-//         pos += 1;
-//     }
-//
-//     Ok(Status::Partial)
-// }
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+#[target_feature(enable = "ssse3")]
+#[inline]
+unsafe fn find_header_terminator_ssse3(buf: &[u8], mut pos: usize) -> Result<usize, usize> {
+    #[cfg(target_arch = "x86")]
+    use core::arch::x86::*;
+    #[cfg(target_arch = "x86_64")]
+    use core::arch::x86_64::*;
+
+    unsafe {
+        let cr = _mm_set1_epi8(b'\r' as i8);
+        let lf = _mm_set1_epi8(b'\n' as i8);
+
+        while buf[pos..].len() >= 16 + 3 {
+            let ptr = buf[pos..].as_ptr();
+            let first = _mm_cmpeq_epi8(_mm_lddqu_si128(ptr as *const _), cr);
+            let second = _mm_cmpeq_epi8(_mm_lddqu_si128(ptr.add(1) as *const _), lf);
+            let third = _mm_cmpeq_epi8(_mm_lddqu_si128(ptr.add(2) as *const _), cr);
+            let fourth = _mm_cmpeq_epi8(_mm_lddqu_si128(ptr.add(3) as *const _), lf);
+
+            let found = _mm_and_si128(_mm_and_si128(first, second), _mm_and_si128(third, fourth));
+            let mask = _mm_movemask_epi8(found) as u32;
+
+            if mask != 0 {
+                return Ok(pos + mask.trailing_zeros() as usize + 4);
+            }
+
+            pos += 16;
+        }
+    }
+
+    Err(pos)
+}
+
+/// Splits a buffer of concatenated requests, such as captured traffic, into the byte range of
+/// each complete request. Message bodies are framed by `Transfer-Encoding: chunked` or
+/// `Content-Length`, as described by RFC 9112 Section 6.3.
+///
+/// Splitting stops at the first request that is incomplete or malformed, as the start of the
+/// next request can no longer be located. Those trailing bytes are excluded from the result.
+///
+/// ```rust
+/// # use rask::parser::h1::request::split_requests;
+/// let buf = b"GET / HTTP/1.1\r\n\r\nPOST / HTTP/1.1\r\nContent-Length: 2\r\n\r\nhiGET";
+/// assert_eq!(vec![0..18, 18..58], split_requests(buf));
+/// ```
+pub fn split_requests(buf: &[u8]) -> Vec<Range<usize>> {
+    let mut requests = Vec::new();
+    let mut start = 0;
+    while let Some(len) = request_len(&buf[start..]) {
+        requests.push(start..start + len);
+        start += len;
+    }
 
+    requests
+}
+
+/// Returns the length of the complete request at the start of `buf`, including its body.
 #[inline]
-fn parse_target(buf: &[u8], mut pos: usize) -> ParseResult<(usize, Range<usize>)> {
-    let start = pos;
+fn request_len(buf: &[u8]) -> Option<usize> {
+    let config = ParserConfig::default();
+    let Ok(Status::Complete((pos, line))) = parse_request_line(buf, &config) else {
+        return None;
+    };
 
-    for window in buf[start..].chunks(64) {
-        let res = window.iter().enumerate().fold(0, |acc, (i, b)| {
-            (((*b == b'=' || (b'!'..=b';').contains(b) || (b'?'..=b'~').contains(b)) as u64) << i)
-                | acc
-        });
+    let mut headers = Vec::new();
+    let Ok(Status::Complete(pos)) = parse_headers(buf, pos, &mut headers, &config) else {
+        return None;
+    };
+    validate_framing(buf, &headers, &line.version).ok()?;
 
-        let num_valid = res.trailing_ones();
-        pos += num_valid as usize;
+    let Ok(Status::Complete(pos)) = discard_newline(buf, pos, ParseError::NewLine, &config) else {
+        return None;
+    };
 
-        if num_valid != 64 {
-            if pos == start {
-                return Err(ParseError::Target);
+    // a chunked transfer coding takes precedence over Content-Length
+    if is_chunked(buf, &headers) {
+        return chunked_body_len(&buf[pos..], &config).map(|len| pos + len);
+    }
+
+    let end = pos.checked_add(parse_content_length(buf, &headers, &config).ok()?)?;
+    (end <= buf.len()).then_some(end)
+}
+
+/// Returns true if `chunked` is the final transfer coding applied to the message body. Codings
+/// listed across several `Transfer-Encoding` headers are applied in the order received.
+#[inline]
+fn is_chunked(buf: &[u8], headers: &[Header]) -> bool {
+    headers
+        .iter()
+        .rev()
+        .filter(|header| buf[header.name.clone()].eq_ignore_ascii_case(b"Transfer-Encoding"))
+        .flat_map(|header| buf[header.value.clone()].rsplit(|&b| b == b','))
+        .map(trim_whitespace)
+        .find(|coding| !coding.is_empty())
+        .is_some_and(|coding| coding.eq_ignore_ascii_case(b"chunked"))
+}
+
+/// Returns the length of the chunked body at the start of `buf`, including the last chunk and
+/// trailer section, or `None` if it is incomplete or malformed.
+/// Defined by RFC 9112 Section 7.1 by ABNF
+/// ```abnf
+/// chunked-body = *chunk last-chunk trailer-section CRLF
+/// chunk        = chunk-size [ chunk-ext ] CRLF chunk-data CRLF
+/// last-chunk   = 1*("0") [ chunk-ext ] CRLF
+/// ```
+#[inline]
+fn chunked_body_len(buf: &[u8], config: &ParserConfig) -> Option<usize> {
+    match parse_chunked_body(buf, 0, &mut Vec::new(), config) {
+        Ok(Status::Complete(n)) => Some(n),
+        _ => None,
+    }
+}
+
+/// Parses the chunked body starting at `pos`, returning the position after it. Fields of the
+/// trailer section are appended to `trailers`, with ranges indexing into `buf`. Chunk sizes that
+/// are not hexadecimal, or chunk data not followed by CRLF, fail with [`ParseError::Chunk`].
+#[inline]
+fn parse_chunked_body(
+    buf: &[u8],
+    mut pos: usize,
+    trailers: &mut Vec<Header>,
+    config: &ParserConfig,
+) -> ParseResult<usize> {
+    loop {
+        let Some(line_end) = find_crlf(buf, pos) else {
+            return Ok(Status::Partial);
+        };
+        let size = buf[pos..line_end]
+            .split(|&b| b == b';')
+            .next()
+            .unwrap_or_default();
+        let size = parse_chunk_size(trim_whitespace(size)).ok_or(ParseError::Chunk)?;
+        pos = line_end + 2;
+
+        if size == 0 {
+            break;
+        }
+
+        let data_end = pos
+            .checked_add(size)
+            .and_then(|end| end.checked_add(2))
+            .ok_or(ParseError::Chunk)?;
+        match buf.get(data_end - 2..data_end) {
+            Some(b"\r\n") => pos = data_end,
+            Some(_) => return Err(ParseError::Chunk),
+            None => return Ok(Status::Partial),
+        }
+    }
+
+    let pos = match parse_headers(buf, pos, trailers, config) {
+        Ok(Status::Complete(n)) => n,
+        Ok(Status::Partial) => return Ok(Status::Partial),
+        Err(err) => return Err(err),
+    };
+
+    discard_newline(buf, pos, ParseError::NewLine, config)
+}
+
+/// Fields that must not be sent as trailers, as they are needed to frame, route or authenticate
+/// the message before its body has been received.
+/// [RFC 9110 Section 6.5.1](https://www.rfc-editor.org/rfc/rfc9110#section-6.5.1)
+const DISALLOWED_TRAILERS: [&[u8]; 6] = [
+    b"Content-Length",
+    b"Transfer-Encoding",
+    b"Trailer",
+    b"Host",
+    b"Authorization",
+    b"Proxy-Authorization",
+];
+
+/// Rejects trailer fields in [`DISALLOWED_TRAILERS`] with [`ParseError::InvalidTrailer`]
+#[inline]
+fn validate_trailers(buf: &[u8], trailers: &[Header]) -> Result<(), ParseError> {
+    let disallowed = trailers.iter().any(|trailer| {
+        DISALLOWED_TRAILERS
+            .iter()
+            .any(|name| buf[trailer.name.clone()].eq_ignore_ascii_case(name))
+    });
+    if disallowed {
+        return Err(ParseError::InvalidTrailer);
+    }
+
+    Ok(())
+}
+
+/// Parses the trailer section that follows the last chunk of a chunked body, including the empty
+/// line that ends the body, returning its length. Trailer fields are appended to `trailers`.
+///
+/// Trailers are subject to the same limits as the header section, so a trailer section cannot
+/// be used to consume more memory than the headers were permitted. More than [`MAX_HEADERS`]
+/// fields fail with [`ParseError::TooManyHeaders`], and a section larger than
+/// [`ParserConfig::max_header_bytes`] fails with [`ParseError::HeadersTooLarge`].
+///
+/// Defined by RFC 9112 Section 7.1.2 by ABNF
+/// ```abnf
+/// trailer-section = *( field-line CRLF )
+/// ```
+///
+/// ```rust
+/// # use rask::parser::Status;
+/// # use rask::parser::h1::{config::ParserConfig, request::parse_trailers};
+/// let buf = b"Expires: Wed, 21 Oct 2015 07:28:00 GMT\r\n\r\n";
+/// let mut trailers = Vec::new();
+///
+/// let result = parse_trailers(buf, &mut trailers, &ParserConfig::default());
+/// assert_eq!(Ok(Status::Complete(buf.len())), result);
+/// assert_eq!(1, trailers.len());
+/// ```
+pub fn parse_trailers(
+    buf: &[u8],
+    trailers: &mut Vec<Header>,
+    config: &ParserConfig,
+) -> ParseResult<usize> {
+    let pos = match parse_headers(buf, 0, trailers, config) {
+        Ok(Status::Complete(n)) => n,
+        Ok(Status::Partial) => return Ok(Status::Partial),
+        Err(err) => return Err(err),
+    };
+
+    discard_newline(buf, pos, ParseError::NewLine, config)
+}
+
+#[inline]
+fn find_crlf(buf: &[u8], pos: usize) -> Option<usize> {
+    buf[pos..]
+        .windows(2)
+        .position(|w| w == b"\r\n")
+        .map(|n| pos + n)
+}
+
+#[inline]
+pub(super) fn parse_chunk_size(size: &[u8]) -> Option<usize> {
+    if size.is_empty() {
+        return None;
+    }
+
+    size.iter().try_fold(0usize, |acc, &b| {
+        let digit = char::from(b).to_digit(16)?;
+        acc.checked_mul(16)?.checked_add(digit as usize)
+    })
+}
+
+#[derive(Debug)]
+struct RequestLine {
+    method: Method,
+    target: Range<usize>,
+    target_form: TargetForm,
+    version: Version,
+}
+
+/// Form of a request target, which determines how the target identifies the requested resource.
+/// [RFC 9112 Section 3.2](https://www.rfc-editor.org/rfc/rfc9112#section-3.2)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TargetForm {
+    /// Absolute path and optional query, such as `/where?q=now`. Used for requests to an origin
+    /// server.
+    Origin,
+    /// Absolute URI, such as `http://www.example.org/pub/WWW/`. Used for requests to a proxy.
+    Absolute,
+    /// Host and port, such as `www.example.com:80`. Used only for CONNECT requests.
+    Authority,
+    /// A single `*`. Used only for server-wide OPTIONS requests.
+    Asterisk,
+}
+
+/// Classifies a request target into its [`TargetForm`]. CONNECT requests must use
+/// authority-form, which no other method may use, and only OPTIONS requests may use
+/// asterisk-form. Fails with [`ParseError::Target`] if the target does not have a form permitted
+/// for `method`.
+///
+/// Defined by RFC 9112 Section 3.2 by ABNF
+/// ```abnf
+/// request-target = origin-form / absolute-form / authority-form / asterisk-form
+/// origin-form    = absolute-path [ "?" query ]
+/// absolute-form  = absolute-URI
+/// authority-form = uri-host ":" port
+/// asterisk-form  = "*"
+/// ```
+///
+/// ```rust
+/// # use rask::parser::{Method, ParseError};
+/// # use rask::parser::h1::request::{classify_target, TargetForm};
+/// assert_eq!(Ok(TargetForm::Origin), classify_target(&Method::Get, b"/where?q=now"));
+/// assert_eq!(Ok(TargetForm::Authority), classify_target(&Method::Connect, b"example.com:443"));
+/// assert_eq!(Err(ParseError::Target), classify_target(&Method::Get, b"*"));
+/// ```
+pub fn classify_target(method: &Method, target: &[u8]) -> Result<TargetForm, ParseError> {
+    if *method == Method::Connect {
+        return if is_authority_form(target) {
+            Ok(TargetForm::Authority)
+        } else {
+            Err(ParseError::Target)
+        };
+    }
+
+    match target {
+        [b'/', ..] => Ok(TargetForm::Origin),
+        b"*" if *method == Method::Options => Ok(TargetForm::Asterisk),
+        _ if has_scheme(target) => Ok(TargetForm::Absolute),
+        _ => Err(ParseError::Target),
+    }
+}
+
+/// Returns true if `target` is a host followed by a non-empty port. User info is not permitted.
+#[inline]
+fn is_authority_form(target: &[u8]) -> bool {
+    let Some(colon) = target.iter().rposition(|&b| b == b':') else {
+        return false;
+    };
+    let (host, port) = (&target[..colon], &target[colon + 1..]);
+
+    !host.is_empty()
+        && !host.iter().any(|b| matches!(b, b'/' | b'?' | b'#' | b'@'))
+        && !port.is_empty()
+        && port.iter().all(u8::is_ascii_digit)
+}
+
+/// Returns true if `target` begins with a URI scheme and its trailing colon.
+/// Defined by RFC 3986 Section 3.1 by ABNF
+/// ```abnf
+/// scheme = ALPHA *( ALPHA / DIGIT / "+" / "-" / "." )
+/// ```
+#[inline]
+fn has_scheme(target: &[u8]) -> bool {
+    let Some(colon) = target.iter().position(|&b| b == b':') else {
+        return false;
+    };
+
+    match &target[..colon] {
+        [first, rest @ ..] => {
+            first.is_ascii_alphabetic()
+                && rest
+                    .iter()
+                    .all(|b| b.is_ascii_alphanumeric() || matches!(b, b'+' | b'-' | b'.'))
+        }
+        [] => false,
+    }
+}
+
+/// Parses the request line, returning the position after its terminating newline.
+/// Defined by RFC 9112 Section 3 by ABNF
+/// ```abnf
+/// request-line = method SP request-target SP HTTP-version
+/// ```
+#[inline]
+fn parse_request_line(buf: &[u8], config: &ParserConfig) -> ParseResult<(usize, RequestLine)> {
+    let result = parse_request_line_components(buf, config);
+
+    let len = match result {
+        Ok(Status::Complete((end, _))) => end,
+        // a partial line is rejected as soon as it grows too long, rather than once the rest has
+        // been buffered
+        Ok(Status::Partial) => buf.len(),
+        Err(_) => return result,
+    };
+
+    if len > config.max_request_line {
+        return Err(ParseError::RequestLineTooLong);
+    }
+
+    result
+}
+
+#[inline]
+fn parse_request_line_components(
+    buf: &[u8],
+    config: &ParserConfig,
+) -> ParseResult<(usize, RequestLine)> {
+    let mut pos = 0;
+
+    if config.allow_leading_crlf {
+        loop {
+            match discard_newline(buf, pos, ParseError::NewLine, config) {
+                Ok(Status::Complete(n)) => pos = n,
+                Ok(Status::Partial) if buf[pos..].is_empty() || buf[pos..] == *b"\r" => {
+                    return Ok(Status::Partial)
+                }
+                _ => break,
             }
+        }
+    }
+
+    let method = match parse_method(&buf[pos..]) {
+        Ok(Status::Complete((read, method))) => {
+            pos += read;
+            method
+        }
+        Ok(Status::Partial) => return Ok(Status::Partial),
+        Err(err) => return Err(err),
+    };
 
-            return Ok(Status::Complete((pos, start..pos)));
+    match discard_separator(buf, pos, ParseError::Method, config) {
+        Ok(Status::Complete(n)) => pos = n,
+        Ok(Status::Partial) => return Ok(Status::Partial),
+        Err(err) => return Err(err),
+    };
+
+    let target = match parse_target(buf, pos) {
+        Ok(Status::Complete((read, target))) => {
+            pos = read;
+            target
         }
+        Ok(Status::Partial) => return Ok(Status::Partial),
+        Err(err) => return Err(err),
+    };
+
+    // absolute-form is only sent to proxies, unless the recipient is configured to act as one
+    let target_form = classify_target(&method, &buf[target.clone()])?;
+    if target_form == TargetForm::Absolute && !config.allow_absolute_form {
+        return Err(ParseError::Target);
     }
 
-    Ok(Status::Partial)
+    match discard_separator(buf, pos, ParseError::Target, config) {
+        Ok(Status::Complete(n)) => pos = n,
+        Ok(Status::Partial) => return Ok(Status::Partial),
+        Err(err) => return Err(err),
+    };
+
+    let version = match parse_version(buf, pos) {
+        Ok(Status::Complete((read, version))) => {
+            pos = read;
+            version
+        }
+        Ok(Status::Partial) => return Ok(Status::Partial),
+        Err(err) => return Err(err),
+    };
+
+    match discard_newline(buf, pos, ParseError::NewLine, config) {
+        Ok(Status::Complete(n)) => pos = n,
+        Ok(Status::Partial) => return Ok(Status::Partial),
+        Err(err) => return Err(err),
+    };
+
+    Ok(Status::Complete((
+        pos,
+        RequestLine {
+            method,
+            target,
+            target_form,
+            version,
+        },
+    )))
 }
 
+/// Consumes the whitespace separating components of the request line. Exactly one SP is
+/// required, unless `config` allows runs of whitespace.
 #[inline]
-fn parse_version(buf: &[u8], pos: usize) -> ParseResult<(usize, Version)> {
-    if buf[pos..].len() < 8 {
+fn discard_separator(
+    buf: &[u8],
+    pos: usize,
+    err_type: ParseError,
+    config: &ParserConfig,
+) -> ParseResult<usize> {
+    if config.allow_multiple_spaces {
+        return discard_required_whitespace(buf, pos, err_type);
+    }
+
+    match buf.get(pos) {
+        None => Ok(Status::Partial),
+        Some(b' ') => Ok(Status::Complete(pos + 1)),
+        Some(_) => Err(err_type),
+    }
+}
+
+/// Consumes a CRLF line terminator, or a bare LF if `config` allows it.
+#[inline]
+fn discard_newline(
+    buf: &[u8],
+    pos: usize,
+    err_type: ParseError,
+    config: &ParserConfig,
+) -> ParseResult<usize> {
+    if config.allow_bare_lf && buf.get(pos) == Some(&b'\n') {
+        return Ok(Status::Complete(pos + 1));
+    }
+
+    discard_required_newline(buf, pos, err_type)
+}
+
+#[inline]
+fn parse_method(buf: &[u8]) -> ParseResult<(usize, Method)> {
+    if buf.len() < 8 {
         return Ok(Status::Partial);
     }
 
-    const SIX_BYTE_MASK: u64 = 0x0000_ffff_ffff_ffff;
-    let eight: [u8; 8] = buf[pos..pos + 8]
-        .try_into()
-        .map_err(|_| ParseError::Version)?;
+    let eight: [u8; 8] = buf[..8].try_into().map_err(|_| ParseError::Method)?;
     let eight = u64::from_ne_bytes(eight);
 
-    if eight == u64::from_le_bytes([b'H', b'T', b'T', b'P', b'/', b'1', b'.', b'1']) {
-        Ok(Status::Complete((pos + 8, Version::H1_1)))
-    } else if eight == u64::from_le_bytes([b'H', b'T', b'T', b'P', b'/', b'1', b'.', b'0']) {
-        Ok(Status::Complete((pos + 8, Version::H1_0)))
-    } else if eight & SIX_BYTE_MASK
-        == u64::from_le_bytes([b'H', b'T', b'T', b'P', b'/', b'2', 0, 0])
+    if eight & 0x0000_0000_00ff_ffff == u64::from_le_bytes([b'G', b'E', b'T', 0, 0, 0, 0, 0]) {
+        Ok(Status::Complete((3, Method::Get)))
+    } else if eight & 0x0000_0000_00ff_ffff == u64::from_le_bytes([b'P', b'U', b'T', 0, 0, 0, 0, 0])
     {
-        Ok(Status::Complete((pos + 6, Version::H2)))
-    } else if eight & SIX_BYTE_MASK
-        == u64::from_le_bytes([b'H', b'T', b'T', b'P', b'/', b'3', 0, 0])
+        Ok(Status::Complete((3, Method::Put)))
+    } else if eight & 0x0000_0000_ffff_ffff
+        == u64::from_le_bytes([b'P', b'O', b'S', b'T', 0, 0, 0, 0])
     {
-        Ok(Status::Complete((pos + 6, Version::H3)))
+        Ok(Status::Complete((4, Method::Post)))
+    } else if eight & 0x0000_0000_ffff_ffff
+        == u64::from_le_bytes([b'H', b'E', b'A', b'D', 0, 0, 0, 0])
+    {
+        Ok(Status::Complete((4, Method::Head)))
+    } else if eight & 0x0000_00ff_ffff_ffff
+        == u64::from_le_bytes([b'T', b'R', b'A', b'C', b'E', 0, 0, 0])
+    {
+        Ok(Status::Complete((5, Method::Trace)))
+    } else if eight & 0x0000_ffff_ffff_ffff
+        == u64::from_le_bytes([b'D', b'E', b'L', b'E', b'T', b'E', 0, 0])
+    {
+        Ok(Status::Complete((6, Method::Delete)))
+    } else if eight & 0x00ff_ffff_ffff_ffff
+        == u64::from_le_bytes([b'O', b'P', b'T', b'I', b'O', b'N', b'S', 0])
+    {
+        Ok(Status::Complete((7, Method::Options)))
+    } else if eight & 0x00ff_ffff_ffff_ffff
+        == u64::from_le_bytes([b'C', b'O', b'N', b'N', b'E', b'C', b'T', 0])
+    {
+        Ok(Status::Complete((7, Method::Connect)))
     } else {
-        Err(ParseError::Version)
+        Err(ParseError::Method)
     }
 }
 
-#[cfg(all(
-    target_feature = "avx2",
-    any(target_arch = "x86", target_arch = "x86_64")
-))]
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+#[target_feature(enable = "avx2")]
 #[inline]
 #[allow(overflowing_literals)]
-fn validate_header_name_avx2(buf: &[u8], mut pos: usize) -> Result<usize, usize> {
+unsafe fn parse_target_vectorized_avx2(buf: &[u8], mut pos: usize) -> Result<usize, usize> {
     #[cfg(target_arch = "x86")]
     use core::arch::x86::*;
     #[cfg(target_arch = "x86_64")]
@@ -476,10 +1500,10 @@ fn validate_header_name_avx2(buf: &[u8], mut pos: usize) -> Result<usize, usize>
             0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
         );
         let col_map = _mm256_setr_epi8(
-            0xe8, 0xfc, 0xf8, 0xfc, 0xfc, 0xfc, 0xfc, 0xfc, // prevent fmt
-            0xf8, 0xf8, 0xf4, 0x54, 0xd0, 0x54, 0xf4, 0x70, // prevent fmt
-            0xe8, 0xfc, 0xf8, 0xfc, 0xfc, 0xfc, 0xfc, 0xfc, // prevent fmt
-            0xf8, 0xf8, 0xf4, 0x54, 0xd0, 0x54, 0xf4, 0x70,
+            0xf8, 0xfc, 0xfc, 0xfc, 0xfc, 0xfc, 0xfc, 0xfc, // prevent fmt
+            0xfc, 0xfc, 0xfc, 0xfc, 0xf4, 0xfc, 0xf4, 0x7c, // prevent fmt
+            0xf8, 0xfc, 0xfc, 0xfc, 0xfc, 0xfc, 0xfc, 0xfc, // prevent fmt
+            0xfc, 0xfc, 0xfc, 0xfc, 0xf4, 0xfc, 0xf4, 0x7c,
         );
         let lower_mask = _mm256_set1_epi8(0x0f);
 
@@ -506,28 +1530,26 @@ fn validate_header_name_avx2(buf: &[u8], mut pos: usize) -> Result<usize, usize>
     Err(pos)
 }
 
-#[cfg(all(
-    target_feature = "ssse3",
-    any(target_arch = "x86", target_arch = "x86_64")
-))]
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+#[target_feature(enable = "ssse3")]
 #[inline]
 #[allow(overflowing_literals)]
-fn validate_header_name_ssse3(buf: &[u8], mut pos: usize) -> Result<usize, usize> {
+unsafe fn parse_target_vectorized_ssse3(buf: &[u8], mut pos: usize) -> Result<usize, usize> {
     #[cfg(target_arch = "x86")]
     use core::arch::x86::*;
     #[cfg(target_arch = "x86_64")]
     use core::arch::x86_64::*;
 
     unsafe {
-        let row_map = _mm_setr_epi8(
+        let row_map: __m128i = _mm_setr_epi8(
             0x01, 0x02, 0x04, 0x08, 0x10, 0x20, 0x40, 0x80, // prevent fmt
             0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
         );
-        let col_map = _mm_setr_epi8(
-            0xe8, 0xfc, 0xf8, 0xfc, 0xfc, 0xfc, 0xfc, 0xfc, // prevent fmt
-            0xf8, 0xf8, 0xf4, 0x54, 0xd0, 0x54, 0xf4, 0x70,
+        let col_map: __m128i = _mm_setr_epi8(
+            0xf8, 0xfc, 0xfc, 0xfc, 0xfc, 0xfc, 0xfc, 0xfc, // prevent fmt
+            0xfc, 0xfc, 0xfc, 0xfc, 0xf4, 0xfc, 0xf4, 0x7c,
         );
-        let lower_mask = _mm_set1_epi8(0x0f);
+        let lower_mask: __m128i = _mm_set1_epi8(0x0f);
 
         while buf[pos..].len() >= 16 {
             let data = _mm_lddqu_si128(buf[pos..].as_ptr() as *const _);
@@ -552,12 +1574,257 @@ fn validate_header_name_ssse3(buf: &[u8], mut pos: usize) -> Result<usize, usize
     Err(pos)
 }
 
-#[cfg(all(
-    target_feature = "avx2",
-    any(target_arch = "x86", target_arch = "x86_64")
-))]
+#[cfg(target_arch = "aarch64")]
 #[inline]
-fn validate_header_value_avx2(buf: &[u8], mut pos: usize) -> Result<usize, usize> {
+fn parse_target_vectorized_neon(buf: &[u8], mut pos: usize) -> Result<usize, usize> {
+    use core::arch::aarch64::*;
+
+    const ROW_MAP: [u8; 16] = [
+        0x01, 0x02, 0x04, 0x08, 0x10, 0x20, 0x40, 0x80, // prevent fmt
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    ];
+    const COL_MAP: [u8; 16] = [
+        0xf8, 0xfc, 0xfc, 0xfc, 0xfc, 0xfc, 0xfc, 0xfc, // prevent fmt
+        0xfc, 0xfc, 0xfc, 0xfc, 0xf4, 0xfc, 0xf4, 0x7c,
+    ];
+
+    unsafe {
+        let row_map = vld1q_u8(ROW_MAP.as_ptr());
+        let col_map = vld1q_u8(COL_MAP.as_ptr());
+        let lower_mask = vdupq_n_u8(0x0f);
+
+        while buf[pos..].len() >= 16 {
+            let data = vld1q_u8(buf[pos..].as_ptr());
+
+            // table lookups yield zero for out of range indices, so bytes above 0x7f have no row
+            let row_mask = vqtbl1q_u8(row_map, vshrq_n_u8(data, 4));
+            let col_mask = vqtbl1q_u8(col_map, vandq_u8(data, lower_mask));
+
+            let valid = vtstq_u8(row_mask, col_mask);
+
+            // NEON has no equivalent of movemask, so each lane is narrowed to four bits instead
+            let invalid = vshrn_n_u16(vreinterpretq_u16_u8(vmvnq_u8(valid)), 4);
+            let bits = vget_lane_u64(vreinterpret_u64_u8(invalid), 0);
+            let num_valid = (bits.trailing_zeros() / 4) as usize;
+
+            pos += num_valid;
+
+            if num_valid != 16 {
+                return Ok(pos);
+            }
+        }
+    }
+
+    Err(pos)
+}
+
+#[inline]
+fn parse_target(buf: &[u8], pos: usize) -> ParseResult<(usize, Range<usize>)> {
+    let start = pos;
+
+    match validate_target(buf, pos) {
+        Ok(n) if n == start => Err(ParseError::Target),
+        Ok(n) => Ok(Status::Complete((n, start..n))),
+        // the target may continue in data that hasn't been received yet
+        Err(_) => Ok(Status::Partial),
+    }
+}
+
+/// Returns the position of the first byte at or after `pos` that is not a request target token,
+/// or `Err` if every remaining byte is. Dispatches as [`find_header_terminator`] does, using
+/// NEON on aarch64, where it is always available.
+#[inline]
+#[cfg_attr(
+    not(any(target_arch = "x86", target_arch = "x86_64", target_arch = "aarch64")),
+    allow(unused_mut)
+)]
+fn validate_target(buf: &[u8], mut pos: usize) -> Result<usize, usize> {
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    {
+        if has_avx2() {
+            // SAFETY: AVX2 support was detected at runtime
+            match unsafe { parse_target_vectorized_avx2(buf, pos) } {
+                Ok(n) => return Ok(n),
+                Err(n) => pos = n,
+            };
+        }
+
+        if has_ssse3() {
+            // SAFETY: SSSE3 support was detected at runtime
+            match unsafe { parse_target_vectorized_ssse3(buf, pos) } {
+                Ok(n) => return Ok(n),
+                Err(n) => pos = n,
+            };
+        }
+    }
+
+    #[cfg(target_arch = "aarch64")]
+    match parse_target_vectorized_neon(buf, pos) {
+        Ok(n) => return Ok(n),
+        Err(n) => pos = n,
+    };
+
+    validate_target_scalar(buf, pos)
+}
+
+#[inline]
+fn validate_target_scalar(buf: &[u8], mut pos: usize) -> Result<usize, usize> {
+    for window in buf[pos..].chunks(64) {
+        let res = window.iter().enumerate().fold(0, |acc, (i, b)| {
+            (((*b == b'=' || (b'!'..=b';').contains(b) || (b'?'..=b'~').contains(b)) as u64) << i)
+                | acc
+        });
+
+        let num_valid = res.trailing_ones();
+        pos += num_valid as usize;
+
+        if num_valid != 64 {
+            return if pos == buf.len() { Err(pos) } else { Ok(pos) };
+        }
+    }
+
+    Err(pos)
+}
+
+#[inline]
+fn parse_version(buf: &[u8], pos: usize) -> ParseResult<(usize, Version)> {
+    if buf[pos..].len() < 8 {
+        return Ok(Status::Partial);
+    }
+
+    const SIX_BYTE_MASK: u64 = 0x0000_ffff_ffff_ffff;
+    let eight: [u8; 8] = buf[pos..pos + 8]
+        .try_into()
+        .map_err(|_| ParseError::Version)?;
+    let eight = u64::from_ne_bytes(eight);
+
+    if eight == u64::from_le_bytes([b'H', b'T', b'T', b'P', b'/', b'1', b'.', b'1']) {
+        Ok(Status::Complete((pos + 8, Version::H1_1)))
+    } else if eight == u64::from_le_bytes([b'H', b'T', b'T', b'P', b'/', b'1', b'.', b'0']) {
+        Ok(Status::Complete((pos + 8, Version::H1_0)))
+    } else if eight & SIX_BYTE_MASK
+        == u64::from_le_bytes([b'H', b'T', b'T', b'P', b'/', b'2', 0, 0])
+    {
+        Ok(Status::Complete((pos + 6, Version::H2)))
+    } else if eight & SIX_BYTE_MASK
+        == u64::from_le_bytes([b'H', b'T', b'T', b'P', b'/', b'3', 0, 0])
+    {
+        Ok(Status::Complete((pos + 6, Version::H3)))
+    } else if is_version_shape(&buf[pos..pos + 8]) {
+        Err(ParseError::UnsupportedVersion)
+    } else {
+        Err(ParseError::Version)
+    }
+}
+
+/// Returns true if `version` matches the shape `HTTP/DIGIT.DIGIT`, regardless of whether the
+/// version is supported.
+/// Defined by RFC 9112 Section 2.3 by ABNF
+/// ```abnf
+/// HTTP-version = HTTP-name "/" DIGIT "." DIGIT
+/// ```
+#[inline]
+fn is_version_shape(version: &[u8]) -> bool {
+    matches!(version, [b'H', b'T', b'T', b'P', b'/', major, b'.', minor]
+        if major.is_ascii_digit() && minor.is_ascii_digit())
+}
+
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+#[target_feature(enable = "avx2")]
+#[inline]
+#[allow(overflowing_literals)]
+unsafe fn validate_header_name_avx2(buf: &[u8], mut pos: usize) -> Result<usize, usize> {
+    #[cfg(target_arch = "x86")]
+    use core::arch::x86::*;
+    #[cfg(target_arch = "x86_64")]
+    use core::arch::x86_64::*;
+
+    unsafe {
+        let row_map = _mm256_setr_epi8(
+            0x01, 0x02, 0x04, 0x08, 0x10, 0x20, 0x40, 0x80, // prevent fmt
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // prevent fmt
+            0x01, 0x02, 0x04, 0x08, 0x10, 0x20, 0x40, 0x80, // prevent fmt
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        );
+        let col_map = _mm256_setr_epi8(
+            0xe8, 0xfc, 0xf8, 0xfc, 0xfc, 0xfc, 0xfc, 0xfc, // prevent fmt
+            0xf8, 0xf8, 0xf4, 0x54, 0xd0, 0x54, 0xf4, 0x70, // prevent fmt
+            0xe8, 0xfc, 0xf8, 0xfc, 0xfc, 0xfc, 0xfc, 0xfc, // prevent fmt
+            0xf8, 0xf8, 0xf4, 0x54, 0xd0, 0x54, 0xf4, 0x70,
+        );
+        let lower_mask = _mm256_set1_epi8(0x0f);
+
+        while buf[pos..].len() >= 32 {
+            let data = _mm256_lddqu_si256(buf[pos..].as_ptr() as *const _);
+
+            // divide by 2^4 to get row and take lower half as shuffle control mask
+            let lower_div16 = _mm256_and_si256(lower_mask, _mm256_srli_epi16(data, 4));
+            let row_mask = _mm256_shuffle_epi8(row_map, lower_div16);
+            let col_mask = _mm256_shuffle_epi8(col_map, data);
+
+            let row_col = _mm256_and_si256(row_mask, col_mask);
+            let valid = _mm256_cmpeq_epi8(row_col, _mm256_setzero_si256());
+            let num_valid = (_mm256_movemask_epi8(valid) as u32).trailing_zeros();
+
+            pos += num_valid as usize;
+
+            if num_valid != 32 {
+                return Ok(pos);
+            }
+        }
+    }
+
+    Err(pos)
+}
+
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+#[target_feature(enable = "ssse3")]
+#[inline]
+#[allow(overflowing_literals)]
+unsafe fn validate_header_name_ssse3(buf: &[u8], mut pos: usize) -> Result<usize, usize> {
+    #[cfg(target_arch = "x86")]
+    use core::arch::x86::*;
+    #[cfg(target_arch = "x86_64")]
+    use core::arch::x86_64::*;
+
+    unsafe {
+        let row_map = _mm_setr_epi8(
+            0x01, 0x02, 0x04, 0x08, 0x10, 0x20, 0x40, 0x80, // prevent fmt
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        );
+        let col_map = _mm_setr_epi8(
+            0xe8, 0xfc, 0xf8, 0xfc, 0xfc, 0xfc, 0xfc, 0xfc, // prevent fmt
+            0xf8, 0xf8, 0xf4, 0x54, 0xd0, 0x54, 0xf4, 0x70,
+        );
+        let lower_mask = _mm_set1_epi8(0x0f);
+
+        while buf[pos..].len() >= 16 {
+            let data = _mm_lddqu_si128(buf[pos..].as_ptr() as *const _);
+
+            // divide by 2^4 and only take lower half
+            let lower_div16 = _mm_and_si128(lower_mask, _mm_srli_epi16(data, 4));
+            let row_mask = _mm_shuffle_epi8(row_map, lower_div16);
+            let col_mask = _mm_shuffle_epi8(col_map, data);
+
+            let row_col = _mm_and_si128(row_mask, col_mask);
+            let valid = _mm_cmpeq_epi8(row_col, _mm_setzero_si128());
+            let num_valid = (0xffff_0000 | _mm_movemask_epi8(valid) as u32).trailing_zeros();
+
+            pos += num_valid as usize;
+
+            if num_valid != 16 {
+                return Ok(pos);
+            }
+        }
+    }
+
+    Err(pos)
+}
+
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+#[target_feature(enable = "avx2")]
+#[inline]
+unsafe fn validate_header_value_avx2(buf: &[u8], mut pos: usize) -> Result<usize, usize> {
     #[cfg(target_arch = "x86")]
     use core::arch::x86::*;
     #[cfg(target_arch = "x86_64")]
@@ -566,14 +1833,15 @@ fn validate_header_value_avx2(buf: &[u8], mut pos: usize) -> Result<usize, usize
     unsafe {
         let tab = _mm256_set1_epi8(0x09);
         let del = _mm256_set1_epi8(0x7f);
-        let low = _mm256_set1_epi8(0x1f);
+        let space = _mm256_set1_epi8(0x20);
 
         while buf[pos..].len() >= 32 {
             let data = _mm256_lddqu_si256(buf[pos..].as_ptr() as *const _);
 
             let is_tab = _mm256_cmpeq_epi8(data, tab);
             let is_del = _mm256_cmpeq_epi8(data, del);
-            let above_low = _mm256_cmpgt_epi8(data, low);
+            // compared unsigned, so obs-text (0x80 to 0xff) is accepted
+            let above_low = _mm256_cmpeq_epi8(_mm256_max_epu8(data, space), data);
             let above_low_or_tab = _mm256_or_si256(above_low, is_tab);
 
             let valid = _mm256_andnot_si256(is_del, above_low_or_tab);
@@ -591,12 +1859,10 @@ fn validate_header_value_avx2(buf: &[u8], mut pos: usize) -> Result<usize, usize
     Err(pos)
 }
 
-#[cfg(all(
-    target_feature = "ssse3",
-    any(target_arch = "x86", target_arch = "x86_64")
-))]
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+#[target_feature(enable = "ssse3")]
 #[inline]
-fn validate_header_value_ssse3(buf: &[u8], mut pos: usize) -> Result<usize, usize> {
+unsafe fn validate_header_value_ssse3(buf: &[u8], mut pos: usize) -> Result<usize, usize> {
     #[cfg(target_arch = "x86")]
     use core::arch::x86::*;
     #[cfg(target_arch = "x86_64")]
@@ -605,14 +1871,15 @@ fn validate_header_value_ssse3(buf: &[u8], mut pos: usize) -> Result<usize, usiz
     unsafe {
         let tab = _mm_set1_epi8(0x09);
         let del = _mm_set1_epi8(0x7f);
-        let low = _mm_set1_epi8(0x1f);
+        let space = _mm_set1_epi8(0x20);
 
         while buf[pos..].len() >= 16 {
             let data = _mm_lddqu_si128(buf[pos..].as_ptr() as *const _);
 
             let is_tab = _mm_cmpeq_epi8(data, tab);
             let is_del = _mm_cmpeq_epi8(data, del);
-            let above_low = _mm_cmpgt_epi8(data, low);
+            // compared unsigned, so obs-text (0x80 to 0xff) is accepted
+            let above_low = _mm_cmpeq_epi8(_mm_max_epu8(data, space), data);
             let above_low_or_tab = _mm_or_si128(above_low, is_tab);
 
             let valid = _mm_andnot_si128(is_del, above_low_or_tab);
@@ -630,93 +1897,243 @@ fn validate_header_value_ssse3(buf: &[u8], mut pos: usize) -> Result<usize, usiz
     Err(pos)
 }
 
+#[cfg(target_arch = "aarch64")]
 #[inline]
-fn get_header_name(buf: &[u8], mut pos: usize) -> ParseResult<(usize, Range<usize>)> {
-    let start = pos;
+fn validate_header_name_neon(buf: &[u8], mut pos: usize) -> Result<usize, usize> {
+    use core::arch::aarch64::*;
+
+    const ROW_MAP: [u8; 16] = [
+        0x01, 0x02, 0x04, 0x08, 0x10, 0x20, 0x40, 0x80, // prevent fmt
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    ];
+    const COL_MAP: [u8; 16] = [
+        0xe8, 0xfc, 0xf8, 0xfc, 0xfc, 0xfc, 0xfc, 0xfc, // prevent fmt
+        0xf8, 0xf8, 0xf4, 0x54, 0xd0, 0x54, 0xf4, 0x70,
+    ];
 
-    #[cfg(all(
-        target_feature = "avx2",
-        any(target_arch = "x86", target_arch = "x86_64")
-    ))]
-    match validate_header_name_avx2(buf, pos) {
-        Ok(n) => return Ok(Status::Complete((n, start..n))),
-        Err(n) => pos = n,
-    };
+    unsafe {
+        let row_map = vld1q_u8(ROW_MAP.as_ptr());
+        let col_map = vld1q_u8(COL_MAP.as_ptr());
+        let lower_mask = vdupq_n_u8(0x0f);
 
-    #[cfg(all(
-        target_feature = "ssse3",
-        any(target_arch = "x86", target_arch = "x86_64")
-    ))]
-    match validate_header_name_ssse3(buf, pos) {
-        Ok(n) => return Ok(Status::Complete((n, start..n))),
-        Err(n) => pos = n,
-    };
+        while buf[pos..].len() >= 16 {
+            let data = vld1q_u8(buf[pos..].as_ptr());
 
-    for &b in &buf[pos..] {
-        if !is_header_name_token(b) {
-            if start == pos {
-                return Err(ParseError::HeaderName);
-            }
+            // table lookups yield zero for out of range indices, so bytes above 0x7f have no row
+            let row_mask = vqtbl1q_u8(row_map, vshrq_n_u8(data, 4));
+            let col_mask = vqtbl1q_u8(col_map, vandq_u8(data, lower_mask));
+
+            let valid = vtstq_u8(row_mask, col_mask);
 
-            return Ok(Status::Complete((pos, start..pos)));
+            // NEON has no equivalent of movemask, so each lane is narrowed to four bits instead
+            let invalid = vshrn_n_u16(vreinterpretq_u16_u8(vmvnq_u8(valid)), 4);
+            let bits = vget_lane_u64(vreinterpret_u64_u8(invalid), 0);
+            let num_valid = (bits.trailing_zeros() / 4) as usize;
+
+            pos += num_valid;
+
+            if num_valid != 16 {
+                return Ok(pos);
+            }
         }
+    }
+
+    Err(pos)
+}
+
+#[cfg(target_arch = "aarch64")]
+#[inline]
+fn validate_header_value_neon(buf: &[u8], mut pos: usize) -> Result<usize, usize> {
+    use core::arch::aarch64::*;
+
+    unsafe {
+        let tab = vdupq_n_u8(0x09);
+        let del = vdupq_n_u8(0x7f);
+        let space = vdupq_n_u8(0x20);
+
+        while buf[pos..].len() >= 16 {
+            let data = vld1q_u8(buf[pos..].as_ptr());
+
+            let is_tab = vceqq_u8(data, tab);
+            let is_del = vceqq_u8(data, del);
+            // compared unsigned, so obs-text (0x80 to 0xff) is accepted
+            let above_low = vcgeq_u8(data, space);
+            let above_low_or_tab = vorrq_u8(above_low, is_tab);
 
-        pos += 1;
+            let valid = vbicq_u8(above_low_or_tab, is_del);
+
+            let invalid = vshrn_n_u16(vreinterpretq_u16_u8(vmvnq_u8(valid)), 4);
+            let bits = vget_lane_u64(vreinterpret_u64_u8(invalid), 0);
+            let num_valid = (bits.trailing_zeros() / 4) as usize;
+
+            pos += num_valid;
+
+            if num_valid != 16 {
+                return Ok(pos);
+            }
+        }
     }
 
-    Ok(Status::Partial)
+    Err(pos)
 }
 
+/// Returns the position of the first byte at or after `pos` that is not a header name token, or
+/// `Err` if every remaining byte is. Dispatches as [`validate_target`] does.
 #[inline]
-fn get_header_value(buf: &[u8], mut pos: usize) -> ParseResult<(usize, Range<usize>)> {
-    let start = pos;
+#[cfg_attr(
+    not(any(target_arch = "x86", target_arch = "x86_64", target_arch = "aarch64")),
+    allow(unused_mut)
+)]
+fn validate_header_name(buf: &[u8], mut pos: usize) -> Result<usize, usize> {
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    {
+        if has_avx2() {
+            // SAFETY: AVX2 support was detected at runtime
+            match unsafe { validate_header_name_avx2(buf, pos) } {
+                Ok(n) => return Ok(n),
+                Err(n) => pos = n,
+            };
+        }
+
+        if has_ssse3() {
+            // SAFETY: SSSE3 support was detected at runtime
+            match unsafe { validate_header_name_ssse3(buf, pos) } {
+                Ok(n) => return Ok(n),
+                Err(n) => pos = n,
+            };
+        }
+    }
 
-    #[cfg(all(
-        target_feature = "avx2",
-        any(target_arch = "x86", target_arch = "x86_64")
-    ))]
-    match validate_header_value_avx2(buf, pos) {
-        Ok(n) => return Ok(Status::Complete((n, start..n))),
+    #[cfg(target_arch = "aarch64")]
+    match validate_header_name_neon(buf, pos) {
+        Ok(n) => return Ok(n),
         Err(n) => pos = n,
     };
 
-    #[cfg(all(
-        target_feature = "ssse3",
-        any(target_arch = "x86", target_arch = "x86_64")
-    ))]
-    match validate_header_value_ssse3(buf, pos) {
-        Ok(n) => return Ok(Status::Complete((n, start..n))),
+    validate_header_name_scalar(buf, pos)
+}
+
+#[inline]
+fn validate_header_name_scalar(buf: &[u8], pos: usize) -> Result<usize, usize> {
+    buf[pos..]
+        .iter()
+        .position(|&b| !is_header_name_token(b))
+        .map(|n| pos + n)
+        .ok_or(buf.len())
+}
+
+/// Returns the position of the first byte at or after `pos` that is not a header value token,
+/// or `Err` if every remaining byte is. Dispatches as [`validate_target`] does.
+#[inline]
+#[cfg_attr(
+    not(any(target_arch = "x86", target_arch = "x86_64", target_arch = "aarch64")),
+    allow(unused_mut)
+)]
+fn validate_header_value(buf: &[u8], mut pos: usize) -> Result<usize, usize> {
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    {
+        if has_avx2() {
+            // SAFETY: AVX2 support was detected at runtime
+            match unsafe { validate_header_value_avx2(buf, pos) } {
+                Ok(n) => return Ok(n),
+                Err(n) => pos = n,
+            };
+        }
+
+        if has_ssse3() {
+            // SAFETY: SSSE3 support was detected at runtime
+            match unsafe { validate_header_value_ssse3(buf, pos) } {
+                Ok(n) => return Ok(n),
+                Err(n) => pos = n,
+            };
+        }
+    }
+
+    #[cfg(target_arch = "aarch64")]
+    match validate_header_value_neon(buf, pos) {
+        Ok(n) => return Ok(n),
         Err(n) => pos = n,
     };
 
-    for &b in &buf[pos..] {
-        if !is_header_value_token(b) {
-            if start == pos {
-                return Err(ParseError::HeaderValue);
-            }
+    validate_header_value_scalar(buf, pos)
+}
 
-            return Ok(Status::Complete((pos, start..pos)));
-        }
+#[inline]
+fn validate_header_value_scalar(buf: &[u8], pos: usize) -> Result<usize, usize> {
+    buf[pos..]
+        .iter()
+        .position(|&b| !is_header_value_token(b))
+        .map(|n| pos + n)
+        .ok_or(buf.len())
+}
+
+#[inline]
+fn get_header_name(buf: &[u8], pos: usize) -> ParseResult<(usize, Range<usize>)> {
+    let start = pos;
 
-        pos += 1;
+    match validate_header_name(buf, pos) {
+        Ok(n) if n == start => Err(ParseError::HeaderName),
+        Ok(n) => Ok(Status::Complete((n, start..n))),
+        Err(_) => Ok(Status::Partial),
     }
+}
+
+#[inline]
+fn get_header_value(buf: &[u8], pos: usize) -> ParseResult<(usize, Range<usize>)> {
+    let start = pos;
 
-    Ok(Status::Partial)
+    // the value may be empty, in which case the line ending is validated by the caller
+    match validate_header_value(buf, pos) {
+        Ok(n) => Ok(Status::Complete((n, start..n))),
+        Err(_) => Ok(Status::Partial),
+    }
 }
 
-#[derive(Debug)]
-enum HeaderStatus {
-    Complete((usize, usize)),
-    Partial(usize),
+/// Excludes trailing optional whitespace from a header value, per RFC 9110 Section 5.5. Leading
+/// whitespace has already been discarded, and whitespace within the value is kept.
+#[inline]
+fn trim_trailing_whitespace(buf: &[u8], value: Range<usize>) -> Range<usize> {
+    let end = buf[value.clone()]
+        .iter()
+        .rposition(|&b| b != b' ' && b != b'\t')
+        .map_or(value.start, |n| value.start + n + 1);
+
+    value.start..end
 }
 
+/// Parses header field lines until the empty line ending the section, enforcing the section
+/// size limit of `config`. Returns the position of the empty line.
 #[inline]
 fn parse_headers(
     buf: &[u8],
     pos: usize,
-    headers: &mut [MaybeUninit<Header>],
-) -> Result<HeaderStatus, ParseError> {
-    let mut idx: usize = 0;
+    headers: &mut Vec<Header>,
+    config: &ParserConfig,
+) -> ParseResult<usize> {
+    let result = parse_header_lines(buf, pos, headers, config);
+
+    let end = match result {
+        Ok(Status::Complete(end)) => end,
+        // a partial section is rejected as soon as it grows too large, rather than once the rest
+        // has been buffered
+        Ok(Status::Partial) => buf.len(),
+        Err(_) => return result,
+    };
+
+    if end - pos > config.max_header_bytes {
+        return Err(ParseError::HeadersTooLarge);
+    }
+
+    result
+}
+
+#[inline]
+fn parse_header_lines(
+    buf: &[u8],
+    pos: usize,
+    headers: &mut Vec<Header>,
+    config: &ParserConfig,
+) -> ParseResult<usize> {
     let mut pos = pos;
     loop {
         let name = match get_header_name(buf, pos) {
@@ -724,63 +2141,156 @@ fn parse_headers(
                 pos = read;
                 name
             }
-            Ok(Status::Partial) => return Ok(HeaderStatus::Partial(idx)),
+            Ok(Status::Partial) => return Ok(Status::Partial),
             Err(err) => {
-                if buf[pos..].len() >= 2 && buf[pos..pos + 2].cmp(b"\r\n").is_eq() {
-                    return Ok(HeaderStatus::Complete((pos, idx)));
+                if buf[pos..].starts_with(b"\r\n")
+                    || (config.allow_bare_lf && buf[pos..].starts_with(b"\n"))
+                {
+                    return Ok(Status::Complete(pos));
                 }
+
+                // read ended between the CR and LF of the final empty line
+                if &buf[pos..] == b"\r" {
+                    return Ok(Status::Partial);
+                }
+
                 return Err(err);
             }
         };
 
-        if buf[pos] == b':' {
-            pos += 1;
-        } else {
-            return Err(ParseError::HeaderName);
+        match buf[pos] {
+            b':' => pos += 1,
+            // must be rejected, as recipients that tolerate it may disagree on the field name,
+            // allowing requests to be smuggled past an intermediary. See RFC 9112 Section 5.1
+            b' ' | b'\t' => return Err(ParseError::WhitespaceBeforeColon),
+            _ => return Err(ParseError::HeaderName),
         }
 
         match discard_whitespace(buf, pos) {
             Some(n) => pos = n,
-            None => return Ok(HeaderStatus::Partial(idx)),
+            None => return Ok(Status::Partial),
         };
 
-        let value = match get_header_value(buf, pos) {
+        let mut value = match get_header_value(buf, pos) {
             Ok(Status::Complete((read, value))) => {
                 pos = read;
                 value
             }
-            Ok(Status::Partial) => return Ok(HeaderStatus::Partial(idx)),
+            Ok(Status::Partial) => return Ok(Status::Partial),
             Err(err) => return Err(err),
         };
 
-        headers[idx].write(Header { name, value });
-        idx += 1;
+        let mut folds = 0;
+        loop {
+            let continuation = match find_obs_fold(buf, pos, config) {
+                Ok(Status::Complete(Some(n))) if config.allow_obs_fold => n,
+                Ok(Status::Complete(Some(_))) => return Err(ParseError::ObsFold),
+                Ok(Status::Complete(None)) => break,
+                Ok(Status::Partial) => return Ok(Status::Partial),
+                Err(err) => return Err(err),
+            };
+
+            folds += 1;
+            if folds > config.max_obs_fold_lines {
+                return Err(ParseError::ObsFold);
+            }
 
-        match discard_whitespace(buf, pos) {
+            match get_header_value(buf, continuation) {
+                Ok(Status::Complete((read, _))) => {
+                    pos = read;
+                    value.end = read;
+                }
+                // an unterminated continuation line may already be too long
+                Ok(Status::Partial) if buf.len() - value.start > config.max_folded_value_bytes => {
+                    return Err(ParseError::ObsFold)
+                }
+                Ok(Status::Partial) => return Ok(Status::Partial),
+                Err(err) => return Err(err),
+            };
+
+            if value.len() > config.max_folded_value_bytes {
+                return Err(ParseError::ObsFold);
+            }
+        }
+        let value = trim_trailing_whitespace(buf, value);
+
+        if headers.len() == MAX_HEADERS {
+            return Err(ParseError::TooManyHeaders);
+        }
+        headers.push(Header { name, value });
+
+        match discard_whitespace(buf, pos) {
             Some(n) => pos = n,
-            None => return Ok(HeaderStatus::Partial(idx)),
+            None => return Ok(Status::Partial),
         };
 
-        match discard_required_newline(buf, pos, ParseError::HeaderValue) {
+        match discard_newline(buf, pos, ParseError::HeaderValue, config) {
             Ok(Status::Complete(n)) => pos = n,
-            Ok(Status::Partial) => return Ok(HeaderStatus::Partial(idx)),
+            Ok(Status::Partial) => return Ok(Status::Partial),
             Err(err) => return Err(err),
         };
     }
 }
 
+/// Finds an obs-fold at the end of the header line at `pos`, returning the start of the line
+/// continuing the value if the next line begins with whitespace.
+/// Defined by RFC 9112 Section 5.2 by ABNF
+/// ```abnf
+/// obs-fold = OWS CRLF RWS
+/// ```
+#[inline]
+fn find_obs_fold(buf: &[u8], pos: usize, config: &ParserConfig) -> ParseResult<Option<usize>> {
+    let next_line = match buf[pos..] {
+        [b'\r', b'\n', ..] => pos + 2,
+        [b'\n', ..] if config.allow_bare_lf => pos + 1,
+        [] | [b'\r'] => return Ok(Status::Partial),
+        // not a line ending, which is reported once the line is terminated
+        _ => return Ok(Status::Complete(None)),
+    };
+
+    match buf.get(next_line) {
+        None => Ok(Status::Partial),
+        Some(b' ' | b'\t') => Ok(Status::Complete(Some(next_line))),
+        Some(_) => Ok(Status::Complete(None)),
+    }
+}
+
+/// Replaces the line breaks of every obs-fold within the header values with SP, so each value
+/// reads as a single line.
+/// [RFC 9112 Section 5.2](https://www.rfc-editor.org/rfc/rfc9112#section-5.2)
+#[inline]
+fn unfold_header_values(buf: &mut [u8], headers: &[Header]) {
+    for header in headers {
+        for b in &mut buf[header.value.clone()] {
+            if *b == b'\r' || *b == b'\n' {
+                *b = b' ';
+            }
+        }
+    }
+}
+
 #[cfg(test)]
 mod test {
-    use std::{path::PathBuf, str::from_utf8};
+    use std::{io::ErrorKind, path::PathBuf, str::from_utf8};
 
     use fake::{faker::filesystem::en::FilePath, Fake};
 
+    use crate::net::mock_stream::MockStream;
     use crate::parser::{
-        h1::request::{parse_target, Header},
-        Method, Status, Version,
+        h1::{
+            config::{ParserConfig, DEFAULT_MAX_HEADER_BYTES, DEFAULT_MAX_REQUEST_LINE},
+            request::{parse_target, Header},
+        },
+        Method, ParseError, ParseResult, Status, Version,
     };
 
-    use super::{parse_method, H1Request};
+    use super::{
+        classify_target, find_header_terminator, find_header_terminator_scalar,
+        has_complete_headers, parse_borrowed, parse_method, parse_trailers, parse_version,
+        peek_method, split_requests, validate_header_name, validate_header_name_scalar,
+        validate_header_value, validate_header_value_scalar, validate_target,
+        validate_target_scalar, H1Request, TargetForm, MAX_HEADERS,
+    };
 
     const REQ: &[u8] = b"\
 GET /api/v1.0/weather/forecast HTTP/1.1\r\n\
@@ -855,7 +2365,6 @@ Cookie: wp_ozh_wsa_visits=2; wp_ozh_wsa_visit_lasttime=xxxxxxxxxx; __utma=xxxxxx
         assert_eq!(&REQ[4..30], b"/api/v1.0/weather/forecast");
         assert_eq!(Some(4..30), req.target);
         assert_eq!(Some(Version::H1_1), req.version);
-        assert!(req.headers.is_some());
         assert_eq!(&REQ[41..45], b"Host");
         assert_eq!(&REQ[47..62], b"www.example.org");
         assert_eq!(
@@ -863,7 +2372,7 @@ Cookie: wp_ozh_wsa_visits=2; wp_ozh_wsa_visit_lasttime=xxxxxxxxxx; __utma=xxxxxx
                 name: 41..45,
                 value: 47..62
             },
-            req.headers.unwrap()[0]
+            req.headers[0]
         );
     }
 
@@ -877,82 +2386,1555 @@ Cookie: wp_ozh_wsa_visits=2; wp_ozh_wsa_visit_lasttime=xxxxxxxxxx; __utma=xxxxxx
         assert_eq!(&REQ[4..30], b"/api/v1.0/weather/forecast");
         assert_eq!(Some(4..30), req.target);
         assert_eq!(Some(Version::H1_1), req.version);
-        assert!(req.headers.is_some());
         println!("{}", req);
-        println!("{:?}", req.headers.unwrap()[0]);
+        println!("{:?}", req.headers[0]);
         assert_eq!(
             Header {
                 name: 41..47,
                 value: 49..52
             },
-            req.headers.unwrap()[0]
+            req.headers[0]
         );
         assert_eq!(&REQ_MED[41..47], b"Accept");
         assert_eq!(&REQ_MED[49..52], b"*/*");
     }
 
+    fn req_long_with_body() -> Vec<u8> {
+        [REQ_LONG, &[b'x'; 669]].concat()
+    }
+
     #[test]
     pub fn test_req_long() {
+        let input = req_long_with_body();
         let mut req = H1Request::new();
-        let mut buf = REQ_LONG;
+        let mut buf: &[u8] = &input;
         req.fill(&mut buf).unwrap();
-        assert_eq!(Ok(Status::Complete(REQ_LONG.len())), req.parse());
+        assert_eq!(Ok(Status::Complete(input.len())), req.parse());
         assert_eq!(format!("{}", req), from_utf8(REQ_LONG).unwrap());
+        assert_eq!(Some(&[b'x'; 669] as &[u8]), req.body_bytes());
     }
 
     #[test]
-    pub fn test_req_comp() {
+    pub fn fill_exact_appends_across_records() {
+        let input = req_long_with_body();
+        let (head, body) = input.split_at(REQ_LONG.len());
         let mut req = H1Request::new();
-        let mut buf = REQ_COMP;
+
+        req.fill_exact(&mut &head[..], head.len()).unwrap();
+        assert_eq!(Ok(Status::Partial), req.parse());
+
+        req.fill_exact(&mut &body[..], body.len()).unwrap();
+        assert_eq!(Ok(Status::Complete(input.len())), req.parse());
+        assert_eq!(input.as_slice(), req.raw());
+    }
+
+    const REQ_CHUNKED: &[u8] = b"\
+POST /upload HTTP/1.1\r\n\
+Host: www.example.org\r\n\
+Transfer-Encoding: gzip, chunked\r\n\r\n\
+5;name=value\r\nhello\r\n\
+1A\r\nabcdefghijklmnopqrstuvwxyz\r\n\
+0\r\n\
+Expires: never\r\n\r\n";
+
+    #[test]
+    pub fn has_complete_headers_finds_terminator_in_every_position() {
+        for input in [REQ, REQ_MED, REQ_LONG, REQ_COMP] {
+            let with_body = [input, b"\r\n\r\n"].concat();
+            assert_eq!(Some(input.len()), has_complete_headers(input));
+            assert_eq!(Some(input.len()), has_complete_headers(&with_body));
+
+            // the terminator straddles reads ending anywhere before its final byte
+            for end in 0..input.len() {
+                assert_eq!(None, has_complete_headers(&input[..end]));
+            }
+        }
+    }
+
+    #[test]
+    pub fn has_complete_headers_ignores_partial_terminators() {
+        let input = [&[b'a'; 40] as &[u8], b"\r\n\r\r\n\n\r\n\r\n"].concat();
+        assert_eq!(Some(input.len()), has_complete_headers(&input));
+
+        assert_eq!(None, has_complete_headers(b"\n\r\n\r"));
+        assert_eq!(Some(4), has_complete_headers(b"\r\n\r\n"));
+    }
+
+    #[test]
+    pub fn absolute_form_requires_proxy_config() {
+        const ABSOLUTE: &[u8] = b"GET http://www.example.org/pub/WWW/ HTTP/1.1\r\n\r\n";
+        let proxy = ParserConfig {
+            allow_absolute_form: true,
+            ..ParserConfig::strict()
+        };
+
+        let (_, result) = parse_with(ParserConfig::strict(), ABSOLUTE);
+        assert_eq!(Err(ParseError::Target), result);
+
+        let (req, result) = parse_with(proxy, ABSOLUTE);
+        assert_eq!(Ok(Status::Complete(ABSOLUTE.len())), result);
+        assert_eq!(
+            Some(b"www.example.org" as &[u8]),
+            req.uri().unwrap().authority()
+        );
+    }
+
+    #[test]
+    pub fn origin_config_accepts_connect_and_asterisk_form() {
+        for input in [
+            b"CONNECT www.example.org:443 HTTP/1.1\r\n\r\n" as &[u8],
+            b"OPTIONS * HTTP/1.1\r\n\r\n",
+        ] {
+            let (_, result) = parse_with(ParserConfig::strict(), input);
+            assert_eq!(Ok(Status::Complete(input.len())), result);
+        }
+    }
+
+    #[test]
+    pub fn classify_target_matches_method() {
+        let cases: [(Method, &[u8], Result<TargetForm, ParseError>); 12] = [
+            (Method::Get, b"/a/b?x=1", Ok(TargetForm::Origin)),
+            (Method::Options, b"/", Ok(TargetForm::Origin)),
+            (
+                Method::Get,
+                b"http://www.example.org/",
+                Ok(TargetForm::Absolute),
+            ),
+            (
+                Method::Post,
+                b"urn:isbn:0451450523",
+                Ok(TargetForm::Absolute),
+            ),
+            (Method::Options, b"*", Ok(TargetForm::Asterisk)),
+            (
+                Method::Connect,
+                b"www.example.org:443",
+                Ok(TargetForm::Authority),
+            ),
+            (Method::Connect, b"[::1]:8080", Ok(TargetForm::Authority)),
+            (Method::Get, b"*", Err(ParseError::Target)),
+            (Method::Get, b"www.example.org", Err(ParseError::Target)),
+            (Method::Connect, b"/", Err(ParseError::Target)),
+            (
+                Method::Connect,
+                b"user@www.example.org:443",
+                Err(ParseError::Target),
+            ),
+            (
+                Method::Connect,
+                b"www.example.org:",
+                Err(ParseError::Target),
+            ),
+        ];
+
+        for (method, target, expected) in cases {
+            assert_eq!(expected, classify_target(&method, target), "{:?}", target);
+        }
+    }
+
+    #[test]
+    pub fn path_and_query_split_target() {
+        let (req, _) = parse_with(
+            ParserConfig::default(),
+            b"GET /a/b?x=1&y=2 HTTP/1.1\r\n\r\n",
+        );
+        assert_eq!(Some(&b"/a/b"[..]), req.path());
+        assert_eq!(Some(&b"x=1&y=2"[..]), req.query());
+
+        let (req, _) = parse_with(ParserConfig::default(), b"GET /a?b?c HTTP/1.1\r\n\r\n");
+        assert_eq!(Some(&b"/a"[..]), req.path());
+        assert_eq!(Some(&b"b?c"[..]), req.query());
+
+        let (req, _) = parse_with(ParserConfig::default(), REQ);
+        assert_eq!(Some(&b"/api/v1.0/weather/forecast"[..]), req.path());
+        assert_eq!(None, req.query());
+
+        let req = H1Request::new();
+        assert_eq!(None, req.path());
+        assert_eq!(None, req.query());
+    }
+
+    #[test]
+    pub fn decoded_path_decodes_percent_encoding() {
+        let (req, _) = parse_with(
+            ParserConfig::default(),
+            b"GET /a%2Fb%20c?x=%zz HTTP/1.1\r\n\r\n",
+        );
+        assert_eq!(Ok(b"/a/b c".to_vec()), req.decoded_path());
+
+        let (req, _) = parse_with(ParserConfig::default(), b"GET /a%zz HTTP/1.1\r\n\r\n");
+        assert_eq!(Err(ParseError::PercentEncoding), req.decoded_path());
+
+        let (req, _) = parse_with(ParserConfig::default(), b"GET /a% HTTP/1.1\r\n\r\n");
+        assert_eq!(Err(ParseError::PercentEncoding), req.decoded_path());
+
+        assert_eq!(Err(ParseError::Target), H1Request::new().decoded_path());
+    }
+
+    #[test]
+    pub fn parse_stores_target_form() {
+        for (input, form) in [
+            (REQ, TargetForm::Origin),
+            (b"OPTIONS * HTTP/1.1\r\n\r\n" as &[u8], TargetForm::Asterisk),
+            (
+                b"CONNECT www.example.org:443 HTTP/1.1\r\n\r\n",
+                TargetForm::Authority,
+            ),
+            (
+                b"GET http://www.example.org/ HTTP/1.1\r\n\r\n",
+                TargetForm::Absolute,
+            ),
+        ] {
+            let (mut req, result) = parse_with(ParserConfig::compatibility(), input);
+            assert_eq!(Ok(Status::Complete(input.len())), result);
+            assert_eq!(Some(form), req.target_form);
+
+            req.reset();
+            assert_eq!(None, req.target_form);
+        }
+
+        let (_, result) = parse_with(ParserConfig::compatibility(), b"GET * HTTP/1.1\r\n\r\n");
+        assert_eq!(Err(ParseError::Target), result);
+    }
+
+    #[test]
+    pub fn split_requests_frames_concatenated_requests() {
+        let req_long = req_long_with_body();
+        let input = [REQ_COMP, &req_long, REQ_CHUNKED].concat();
+
+        let first = REQ_COMP.len();
+        let second = first + req_long.len();
+        assert_eq!(
+            vec![0..first, first..second, second..input.len()],
+            split_requests(&input)
+        );
+    }
+
+    #[test]
+    pub fn split_requests_excludes_incomplete_request() {
+        let req_long = req_long_with_body();
+        let input = [REQ, &req_long, &REQ_CHUNKED[..REQ_CHUNKED.len() - 2]].concat();
+
+        let first = REQ.len();
+        assert_eq!(
+            vec![0..first, first..first + req_long.len()],
+            split_requests(&input)
+        );
+        assert_eq!(
+            vec![0..first],
+            split_requests(&input[..input.len() - 1 - REQ_CHUNKED.len()])
+        );
+    }
+
+    #[test]
+    pub fn fill_appends_chunks_larger_than_read_size() {
+        let chunks: Vec<Vec<u8>> = (0..4u8)
+            .map(|n| vec![b'a' + n; 4096 * usize::from(n + 1) + 7])
+            .collect();
+        let mut stream = MockStream::new();
+        let mut req = H1Request::new();
+
+        // a partial request already buffered before each fill
+        req.fill(&mut &b"POST / HTTP/1.1\r\n"[..]).unwrap();
+        let mut expected = b"POST / HTTP/1.1\r\n".to_vec();
+        for chunk in &chunks {
+            stream.push(chunk);
+            assert_eq!(chunk.len(), req.fill(&mut stream).unwrap());
+            expected.extend_from_slice(chunk);
+            assert_eq!(expected.as_slice(), req.raw());
+        }
+
+        for chunk in &chunks {
+            stream.push(chunk);
+        }
+        let total: usize = chunks.iter().map(Vec::len).sum();
+        assert_eq!(total, req.fill(&mut stream).unwrap());
+        assert_eq!(expected.len() + total, req.raw().len());
+        assert_eq!(
+            ErrorKind::WouldBlock,
+            req.fill(&mut stream).unwrap_err().kind()
+        );
+    }
+
+    #[test]
+    pub fn from_vec_parses_like_fill() {
+        let req_long = req_long_with_body();
+        for input in [REQ, REQ_COMP, &req_long, &req_long[..req_long.len() - 1]] {
+            let mut filled = H1Request::new();
+            filled.fill(&mut &input[..]).unwrap();
+            let filled_result = filled.parse();
+
+            let data = input.to_vec();
+            let ptr = data.as_ptr();
+            let mut owned = H1Request::from_vec(data);
+            assert_eq!(filled_result, owned.parse());
+            // parsed in place, without copying
+            assert_eq!(ptr, owned.raw().as_ptr());
+
+            assert_eq!(filled.method, owned.method);
+            assert_eq!(filled.target, owned.target);
+            assert_eq!(filled.version, owned.version);
+            assert_eq!(filled.headers, owned.headers);
+            assert_eq!(filled.body, owned.body);
+        }
+    }
+
+    #[test]
+    pub fn reset_parses_pipelined_request() {
+        let req_long = req_long_with_body();
+        let input = [&req_long, REQ].concat();
+        let mut req = H1Request::new();
+        req.fill(&mut &input[..]).unwrap();
+
+        assert_eq!(Ok(Status::Complete(req_long.len())), req.parse());
+        assert_eq!(Some(Method::Post), req.method);
+        let capacity = req.raw().len();
+
+        req.reset();
+        assert!(!req.complete);
+        assert_eq!(REQ, req.raw());
+        assert_eq!(Ok(Status::Complete(REQ.len())), req.parse());
+        assert_eq!(Some(Method::Get), req.method);
+        assert_eq!(format!("{}", req), from_utf8(REQ).unwrap());
+
+        req.reset();
+        assert!(req.raw().is_empty());
+        assert!(req.headers.is_empty());
+        assert_eq!(None, req.method);
+        assert!(req.data.capacity() >= capacity);
+    }
+
+    #[test]
+    pub fn zero_content_length_completes_pipelined_request() {
+        let first: &[u8] = b"POST /x HTTP/1.1\r\nContent-Length: 0\r\n\r\n";
+        let input = [first, REQ].concat();
+        let mut req = H1Request::new();
+        req.fill(&mut &input[..]).unwrap();
+
+        assert_eq!(Ok(Status::Complete(first.len())), req.parse());
+        assert_eq!(Some(Method::Post), req.method);
+        assert_eq!(Some(&[] as &[u8]), req.body_bytes());
+        assert!(req.should_keep_alive());
+
+        req.reset();
+        assert_eq!(Ok(Status::Complete(REQ.len())), req.parse());
+        assert_eq!(Some(Method::Get), req.method);
+        assert_eq!(
+            vec![0..first.len(), first.len()..input.len()],
+            split_requests(&input)
+        );
+    }
+
+    #[test]
+    pub fn parse_final_rejects_truncated_request() {
+        let input = req_long_with_body();
+        let mut stream = MockStream::new();
+        stream.push(&input[..input.len() - 1]);
+        stream.eof = true;
+
+        let mut req = H1Request::new();
+        assert_eq!(0, req.fill(&mut stream).unwrap());
+        assert_eq!(Ok(Status::Partial), req.parse());
+        assert_eq!(Err(ParseError::Incomplete), req.parse_final());
+
+        let mut req = H1Request::new();
+        assert_eq!(Ok(Status::Partial), req.parse_final());
+    }
+
+    #[test]
+    pub fn parse_final_rejects_unterminated_header_section() {
+        let cases: [&[u8]; 3] = [
+            b"GET / HTTP/1.1\r\n",
+            b"GET / HTTP/1.1\r\nHost: www.example.org\r\n",
+            b"GET / HTTP/1.1\r\nHost: www.example.org\r\n\r",
+        ];
+
+        for input in cases {
+            let mut req = H1Request::new();
+            req.fill(&mut &input[..]).unwrap();
+            assert_eq!(Ok(Status::Partial), req.parse());
+            assert_eq!(Err(ParseError::UnterminatedHeaders), req.parse_final());
+        }
+        assert_eq!(
+            400,
+            ParseError::UnterminatedHeaders.response_status().as_u16()
+        );
+
+        // truncated within the request line
+        let mut req = H1Request::new();
+        req.fill(&mut &b"GET / HTTP/1.1\r"[..]).unwrap();
+        assert_eq!(Err(ParseError::Incomplete), req.parse_final());
+    }
+
+    #[test]
+    pub fn body_partial_until_content_length_received() {
+        let input = req_long_with_body();
+        let mut req = H1Request::new();
+        let mut buf: &[u8] = &input[..input.len() - 1];
         req.fill(&mut buf).unwrap();
-        assert_eq!(Ok(Status::Complete(REQ_COMP.len())), req.parse());
-        assert_eq!(format!("{}", req), from_utf8(REQ_COMP).unwrap());
+
+        assert_eq!(Ok(Status::Partial), req.parse());
+        assert_eq!(None, req.body_bytes());
+        assert!(!req.complete);
     }
 
     #[test]
-    pub fn test_multiple() {
-        let inputs = [REQ, REQ_MED, REQ_COMP, REQ_LONG];
+    pub fn body_empty_without_content_length() {
+        let mut req = H1Request::new();
+        let mut buf = REQ;
+        req.fill(&mut buf).unwrap();
 
-        for &input in inputs.iter() {
-            let mut i = Vec::default();
-            i.extend_from_slice(input);
+        assert_eq!(Ok(Status::Complete(REQ.len())), req.parse());
+        assert_eq!(Some(&[] as &[u8]), req.body_bytes());
+    }
 
-            let mut i: &[u8] = &i;
+    #[test]
+    pub fn body_rejects_invalid_content_length() {
+        for value in ["12a", "-1", "1 2", "18446744073709551616"] {
+            let input = format!("POST / HTTP/1.1\r\nContent-Length: {}\r\n\r\n", value);
             let mut req = H1Request::new();
-            req.fill(&mut i).unwrap();
-            req.parse().unwrap();
-            println!("{}\n{}", from_utf8(input).unwrap(), req);
-            assert_eq!(from_utf8(input).unwrap(), format!("{}", req));
+            let mut buf = input.as_bytes();
+            req.fill(&mut buf).unwrap();
+
+            assert_eq!(Err(ParseError::ContentLength), req.parse());
         }
     }
 
     #[test]
-    pub fn method_returns_ok_with_valid_http_verb() {
-        let verbs = [
-            ("GET     ", Method::Get),
-            ("PUT     ", Method::Put),
-            ("POST    ", Method::Post),
-            ("HEAD    ", Method::Head),
-            ("TRACE   ", Method::Trace),
-            ("DELETE  ", Method::Delete),
-            ("OPTIONS ", Method::Options),
-            ("CONNECT ", Method::Connect),
+    pub fn body_rejects_empty_content_length() {
+        let mut req = H1Request::new();
+        let mut buf = &b"POST / HTTP/1.1\r\nContent-Length:\r\n\r\n"[..];
+        req.fill(&mut buf).unwrap();
+
+        assert_eq!(Err(ParseError::ContentLength), req.parse());
+    }
+
+    const REQ_BORDERLINE: &[u8] = b"\
+\r\n\
+POST  /upload \tHTTP/1.1\n\
+Host: www.example.org\r\n\
+Content-Length: 5\n\
+Content-Length: 5\r\n\
+\n\
+hello";
+
+    fn parse_with(config: ParserConfig, input: &[u8]) -> (H1Request, ParseResult<usize>) {
+        let mut req = H1Request::with_config(config);
+        let mut buf = input;
+        req.fill(&mut buf).unwrap();
+        let result = req.parse();
+        (req, result)
+    }
+
+    #[test]
+    pub fn h2c_upgrade_requires_every_header() {
+        let cases: [(&[u8], Option<&[u8]>); 6] = [
+            (
+                b"GET / HTTP/1.1\r\nConnection: Upgrade, HTTP2-Settings\r\nUpgrade: h2c\r\nHTTP2-Settings: AAMAAABk\r\n\r\n",
+                Some(b"AAMAAABk"),
+            ),
+            (
+                b"GET / HTTP/1.1\r\nconnection: http2-settings\r\nConnection: upgrade\r\nUpgrade: websocket, H2C\r\nHTTP2-Settings: \r\n\r\n",
+                Some(b""),
+            ),
+            (
+                b"GET / HTTP/1.1\r\nConnection: Upgrade\r\nUpgrade: h2c\r\nHTTP2-Settings: AAMAAABk\r\n\r\n",
+                None,
+            ),
+            (
+                b"GET / HTTP/1.1\r\nConnection: Upgrade, HTTP2-Settings\r\nUpgrade: h2\r\nHTTP2-Settings: AAMAAABk\r\n\r\n",
+                None,
+            ),
+            (
+                b"GET / HTTP/1.1\r\nConnection: Upgrade, HTTP2-Settings\r\nUpgrade: h2c\r\nHTTP2-Settings: AAMAAABk\r\nHTTP2-Settings: AAMAAABk\r\n\r\n",
+                None,
+            ),
+            (
+                b"GET / HTTP/1.0\r\nConnection: Upgrade, HTTP2-Settings\r\nUpgrade: h2c\r\nHTTP2-Settings: AAMAAABk\r\n\r\n",
+                None,
+            ),
         ];
 
-        for (verb, expected) in verbs {
-            let result = parse_method(verb.as_bytes());
+        for (input, expected) in cases {
+            let (req, result) = parse_with(ParserConfig::strict(), input);
+            assert_eq!(Ok(Status::Complete(input.len())), result);
+            assert_eq!(expected, req.h2c_upgrade_settings(), "{}", req);
+        }
+    }
 
-            assert_eq!(result, Ok(Status::Complete((verb.trim().len(), expected))));
+    #[test]
+    pub fn method_with_colon_rejected() {
+        for method in ["GET:", "G:ET", ":GET", "POST:", "OPTIONS:"] {
+            let input = format!("{} / HTTP/1.1\r\nHost: www.example.org\r\n\r\n", method);
+            for config in [ParserConfig::strict(), ParserConfig::compatibility()] {
+                let (_, result) = parse_with(config, input.as_bytes());
+                assert_eq!(Err(ParseError::Method), result, "{}", method);
+            }
+            assert_eq!(Err(ParseError::Method), Method::try_from(method));
         }
     }
 
     #[test]
-    pub fn target_returns_ok_with_valid_path() {
-        let path: PathBuf = FilePath().fake();
-        let path = path.to_str().unwrap();
+    pub fn parse_version_distinguishes_unsupported_from_invalid() {
+        for version in [&b"HTTP/1.2\r\n"[..], b"HTTP/9.9\r\n", b"HTTP/0.9\r\n"] {
+            assert_eq!(
+                Err(ParseError::UnsupportedVersion),
+                parse_version(version, 0)
+            );
+        }
+        for version in [&b"GET /x\r\n"[..], b"HTTP/1.x\r\n", b"http/1.1\r\n"] {
+            assert_eq!(Err(ParseError::Version), parse_version(version, 0));
+        }
+
+        let mut req = H1Request::new();
+        req.fill(&mut &b"GET / HTTP/1.2\r\n\r\n"[..]).unwrap();
+        let err = req.parse().unwrap_err();
+        assert_eq!(ParseError::UnsupportedVersion, err);
+        assert_eq!(505, err.response_status().as_u16());
+    }
 
-        let result = parse_target(path.as_bytes(), 0);
+    #[test]
+    pub fn strict_config_rejects_borderline_request() {
+        let (_, result) = parse_with(ParserConfig::strict(), REQ_BORDERLINE);
 
-        assert_eq!(result, Ok(Status::Complete((path.len(), 0..path.len()))));
+        assert_eq!(Err(ParseError::Method), result);
+    }
+
+    #[test]
+    pub fn compatibility_config_accepts_borderline_request() {
+        let (req, result) = parse_with(ParserConfig::compatibility(), REQ_BORDERLINE);
+
+        assert_eq!(Ok(Status::Complete(REQ_BORDERLINE.len())), result);
+        assert_eq!(Some(Method::Post), req.method);
+        assert_eq!(b"/upload", &req.raw()[req.target.clone().unwrap()]);
+        assert_eq!(Some(Version::H1_1), req.version);
+        assert_eq!(Some(b"hello" as &[u8]), req.body_bytes());
+    }
+
+    #[test]
+    pub fn config_flags_relax_individually() {
+        let cases: [(&[u8], ParseError, ParserConfig); 5] = [
+            (
+                b"\r\nGET / HTTP/1.1\r\n\r\n",
+                ParseError::Method,
+                ParserConfig {
+                    allow_leading_crlf: true,
+                    ..ParserConfig::strict()
+                },
+            ),
+            (
+                b"GET  / HTTP/1.1\r\n\r\n",
+                ParseError::Target,
+                ParserConfig {
+                    allow_multiple_spaces: true,
+                    ..ParserConfig::strict()
+                },
+            ),
+            (
+                b"GET / HTTP/1.1\nHost: www.example.org\n\n",
+                ParseError::NewLine,
+                ParserConfig {
+                    allow_bare_lf: true,
+                    ..ParserConfig::strict()
+                },
+            ),
+            (
+                b"GET / HTTP/1.1\r\nContent-Length: 0\r\nContent-Length: 0\r\n\r\n",
+                ParseError::ContentLength,
+                ParserConfig {
+                    allow_duplicate_content_length: true,
+                    ..ParserConfig::strict()
+                },
+            ),
+            (
+                b"GET / HTTP/1.1\r\nAccept: text/html,\r\n text/plain\r\n\r\n",
+                ParseError::ObsFold,
+                ParserConfig {
+                    allow_obs_fold: true,
+                    ..ParserConfig::strict()
+                },
+            ),
+        ];
+
+        for (input, err, config) in cases {
+            assert_eq!(Err(err), parse_with(ParserConfig::strict(), input).1);
+            assert_eq!(
+                Ok(Status::Complete(input.len())),
+                parse_with(config, input).1
+            );
+        }
+    }
+
+    #[test]
+    pub fn obs_fold_rejected_by_default() {
+        let input = b"GET / HTTP/1.1\r\nX-Folded: a\r\n\tb\r\nHost: www.example.org\r\n\r\n";
+
+        let (_, result) = parse_with(ParserConfig::strict(), input);
+        assert_eq!(Err(ParseError::ObsFold), result);
+        assert_eq!(400, ParseError::ObsFold.response_status().as_u16());
+    }
+
+    #[test]
+    pub fn obs_fold_merged_into_single_value() {
+        let input =
+            b"GET / HTTP/1.1\r\nX-Folded: a, \r\n  b\r\n\tc  \r\nHost: www.example.org\r\n\r\n";
+        let config = ParserConfig {
+            allow_obs_fold: true,
+            ..ParserConfig::strict()
+        };
+
+        let (req, result) = parse_with(config, input);
+        assert_eq!(Ok(Status::Complete(input.len())), result);
+        assert_eq!(2, req.headers.len());
+        assert_eq!(Some(&b"a,     b  \tc"[..]), req.header(b"X-Folded"));
+        assert_eq!(Some(&b"www.example.org"[..]), req.header(b"Host"));
+
+        // partial reads ending at the fold must wait for the following line
+        for end in 0..input.len() {
+            let (_, result) = parse_with(config, &input[..end]);
+            assert_eq!(Ok(Status::Partial), result, "{}", end);
+        }
+    }
+
+    #[test]
+    pub fn obs_fold_limits_enforced_in_lenient_mode() {
+        let config = ParserConfig {
+            allow_obs_fold: true,
+            ..ParserConfig::strict()
+        };
+        let folded = |lines: usize, len: usize| {
+            let mut req = b"GET / HTTP/1.1\r\nX-Folded: a".to_vec();
+            for _ in 0..lines {
+                req.extend_from_slice(b"\r\n ");
+                req.extend(std::iter::repeat(b'b').take(len));
+            }
+            req.extend_from_slice(b"\r\nHost: www.example.org\r\n\r\n");
+            req
+        };
+
+        let input = folded(config.max_obs_fold_lines, 1);
+        assert_eq!(
+            Ok(Status::Complete(input.len())),
+            parse_with(config, &input).1
+        );
+
+        let input = folded(config.max_obs_fold_lines + 1, 1);
+        assert_eq!(Err(ParseError::ObsFold), parse_with(config, &input).1);
+
+        let input = folded(1000, 1);
+        assert_eq!(Err(ParseError::ObsFold), parse_with(config, &input).1);
+
+        let len = config.max_folded_value_bytes / 2;
+        let input = folded(2, len);
+        assert_eq!(Err(ParseError::ObsFold), parse_with(config, &input).1);
+        // rejected before the oversized continuation line is terminated
+        let end = input.len() - b"\r\nHost: www.example.org\r\n\r\n".len();
+        assert_eq!(
+            Err(ParseError::ObsFold),
+            parse_with(config, &input[..end]).1
+        );
+    }
+
+    #[test]
+    pub fn duplicate_content_length_must_match() {
+        let input = b"POST / HTTP/1.1\r\nContent-Length: 1\r\nContent-Length: 2\r\n\r\nab";
+        let (_, result) = parse_with(ParserConfig::compatibility(), input);
+
+        assert_eq!(Err(ParseError::ContentLength), result);
+    }
+
+    fn request_with_headers(count: usize) -> Vec<u8> {
+        let mut req = b"GET / HTTP/1.1\r\n".to_vec();
+        for i in 0..count {
+            req.extend_from_slice(format!("X-Header-{}: {}\r\n", i, i).as_bytes());
+        }
+        req.extend_from_slice(b"\r\n");
+        req
+    }
+
+    #[test]
+    pub fn long_target_rejected_while_partial() {
+        let target = vec![b'a'; 16 * 1024];
+        let input = [&b"GET /"[..], &target, b" HTTP/1.1\r\n\r\n"].concat();
+        let mut stream = MockStream::new();
+        let mut chunks = input.chunks(1024);
+
+        let mut req = H1Request::new();
+        let result = loop {
+            stream.push(chunks.next().unwrap());
+            req.fill(&mut stream).unwrap();
+            match req.parse() {
+                Ok(Status::Partial) => continue,
+                result => break result,
+            }
+        };
+
+        assert_eq!(Err(ParseError::RequestLineTooLong), result);
+        assert!(req.raw().len() <= DEFAULT_MAX_REQUEST_LINE + 1024);
+        assert_eq!(
+            414,
+            ParseError::RequestLineTooLong.response_status().as_u16()
+        );
+    }
+
+    #[test]
+    pub fn with_limits_bounds_request_line_and_headers() {
+        let line = b"GET /abc HTTP/1.1\r\n".len();
+
+        let mut req = H1Request::with_limits(line, 64);
+        req.fill(&mut &b"GET /abc HTTP/1.1\r\n\r\n"[..]).unwrap();
+        assert_eq!(Ok(Status::Complete(line + 2)), req.parse());
+
+        let mut req = H1Request::with_limits(line - 1, 64);
+        req.fill(&mut &b"GET /abc HTTP/1.1\r\n\r\n"[..]).unwrap();
+        assert_eq!(Err(ParseError::RequestLineTooLong), req.parse());
+
+        let mut req = H1Request::with_limits(line, 8);
+        req.fill(&mut &b"GET /abc HTTP/1.1\r\nHost: www.example.org\r\n\r\n"[..])
+            .unwrap();
+        assert_eq!(Err(ParseError::HeadersTooLarge), req.parse());
+    }
+
+    #[test]
+    pub fn header_section_size_limited() {
+        let input = request_with_headers(4);
+        let head_len = input.len() - b"GET / HTTP/1.1\r\n".len() - 2;
+        let config = |max| ParserConfig {
+            max_header_bytes: max,
+            ..ParserConfig::default()
+        };
+
+        let (_, result) = parse_with(config(head_len), &input);
+        assert_eq!(Ok(Status::Complete(input.len())), result);
+
+        let (_, result) = parse_with(config(head_len - 1), &input);
+        assert_eq!(Err(ParseError::HeadersTooLarge), result);
+
+        // rejected before the section is complete
+        let (_, result) = parse_with(config(head_len - 1), &input[..input.len() - 2]);
+        assert_eq!(Err(ParseError::HeadersTooLarge), result);
+    }
+
+    #[test]
+    pub fn vectorized_dispatch_matches_scalar() {
+        let mut inputs = vec![
+            REQ.to_vec(),
+            REQ_MED.to_vec(),
+            REQ_LONG.to_vec(),
+            REQ_COMP.to_vec(),
+            request_with_headers(8),
+            large_header_block(),
+        ];
+
+        // places every byte value at offsets around the 16 and 32 byte block boundaries
+        for b in 0..=u8::MAX {
+            for at in [0, 1, 15, 16, 17, 31, 32, 33, 47, 63, 64, 69] {
+                let mut input = vec![b'a'; 70];
+                input[at] = b;
+                inputs.push(input);
+            }
+        }
+
+        for input in &inputs {
+            for pos in 0..input.len() {
+                assert_eq!(
+                    validate_target_scalar(input, pos),
+                    validate_target(input, pos)
+                );
+                assert_eq!(
+                    validate_header_name_scalar(input, pos),
+                    validate_header_name(input, pos)
+                );
+                assert_eq!(
+                    validate_header_value_scalar(input, pos),
+                    validate_header_value(input, pos)
+                );
+                assert_eq!(
+                    find_header_terminator_scalar(input, pos),
+                    find_header_terminator(input, pos)
+                );
+            }
+        }
+    }
+
+    #[test]
+    pub fn transfer_encoding_rejected_in_http_1_0() {
+        let mut req = H1Request::new();
+        req.fill(&mut &b"POST /upload HTTP/1.0\r\nTransfer-Encoding: chunked\r\n\r\n0\r\n\r\n"[..])
+            .unwrap();
+
+        assert_eq!(Err(ParseError::ConflictingFraming), req.parse());
+    }
+
+    #[test]
+    pub fn transfer_encoding_must_end_in_chunked() {
+        for input in [
+            &b"POST / HTTP/1.1\r\nTransfer-Encoding: gzip\r\n\r\n"[..],
+            b"POST / HTTP/1.1\r\nTransfer-Encoding: chunked, gzip\r\n\r\n",
+            b"POST / HTTP/1.1\r\nTransfer-Encoding: chunked\r\nTransfer-Encoding: gzip\r\n\r\n",
+        ] {
+            let mut req = H1Request::new();
+            req.fill(&mut &input[..]).unwrap();
+
+            assert_eq!(Err(ParseError::ConflictingFraming), req.parse());
+            assert!(split_requests(input).is_empty());
+        }
+    }
+
+    #[test]
+    pub fn transfer_codings_joined_across_headers() {
+        const REQ: &[u8] = b"POST / HTTP/1.1\r\nTransfer-Encoding: gzip\r\n\
+Transfer-Encoding: chunked\r\n\r\n5\r\nhello\r\n0\r\n\r\n";
+        const NEXT: &[u8] = b"GET / HTTP/1.1\r\n\r\n";
+        let input = [REQ, NEXT].concat();
+        let mut req = H1Request::new();
+        req.fill(&mut input.as_slice()).unwrap();
+
+        assert_eq!(Ok(Status::Complete(REQ.len())), req.parse());
+        assert_eq!(
+            vec![0..REQ.len(), REQ.len()..input.len()],
+            split_requests(&input)
+        );
+    }
+
+    #[test]
+    pub fn large_header_section_rejected_at_default_limit() {
+        let mut stream = MockStream::new();
+        stream.push(b"GET / HTTP/1.1\r\n");
+
+        let mut req = H1Request::new();
+        let mut i = 0;
+        let result = loop {
+            req.fill(&mut stream).unwrap();
+            match req.parse() {
+                Ok(Status::Partial) => {}
+                result => break result,
+            }
+
+            stream.push(format!("X-Header-{}: {}\r\n", i, "x".repeat(1000)).as_bytes());
+            i += 1;
+            assert!(i < 64, "header section was never rejected");
+        };
+
+        assert_eq!(Err(ParseError::HeadersTooLarge), result);
+        assert!(req.raw().len() < DEFAULT_MAX_HEADER_BYTES + 1024 + 16);
+    }
+
+    #[test]
+    pub fn carriage_return_at_read_boundary_resumes() {
+        const REQ: &[u8] = b"GET / HTTP/1.1\r\nHost: www.example.org\r\n\r\n";
+        // every read boundary directly after a CR
+        let boundaries = [15, 38, 40];
+
+        for config in [ParserConfig::strict(), ParserConfig::compatibility()] {
+            for boundary in boundaries {
+                assert_eq!(b'\r', REQ[boundary - 1]);
+
+                let mut req = H1Request::with_config(config);
+                req.fill(&mut &REQ[..boundary]).unwrap();
+                assert_eq!(Ok(Status::Partial), req.parse());
+
+                req.fill(&mut &REQ[boundary..]).unwrap();
+                assert_eq!(Ok(Status::Complete(REQ.len())), req.parse());
+            }
+        }
+    }
+
+    #[test]
+    pub fn carriage_return_without_line_feed_rejected() {
+        for config in [ParserConfig::strict(), ParserConfig::compatibility()] {
+            // the peer closed the connection after the CR, so the line can never complete
+            let mut req = H1Request::with_config(config);
+            req.fill(&mut &b"GET / HTTP/1.1\r"[..]).unwrap();
+            assert_eq!(Ok(Status::Partial), req.parse());
+            assert_eq!(Err(ParseError::Incomplete), req.parse_final());
+
+            let mut req = H1Request::with_config(config);
+            req.fill(&mut &b"GET / HTTP/1.1\rHost: www.example.org\r\n\r\n"[..])
+                .unwrap();
+            assert_eq!(Err(ParseError::NewLine), req.parse());
+        }
+    }
+
+    #[test]
+    pub fn chunked_body_trailers_parsed() {
+        const HEAD: &[u8] =
+            b"POST / HTTP/1.1\r\nHost: localhost\r\nTransfer-Encoding: chunked\r\n\r\n";
+        const BODY: &[u8] = b"5\r\nhello\r\n0\r\nExpires: Wed, 21 Oct 2015 07:28:00 GMT\r\n\r\n";
+        let input = [HEAD, BODY].concat();
+        let mut req = H1Request::new();
+
+        // the body is incomplete until the empty line after the trailer section
+        req.fill(&mut &input[..input.len() - 2]).unwrap();
+        assert_eq!(Ok(Status::Partial), req.parse());
+        assert!(req.trailers.is_none());
+
+        req.fill(&mut &input[input.len() - 2..]).unwrap();
+        assert_eq!(Ok(Status::Complete(input.len())), req.parse());
+        assert_eq!(Some(BODY), req.body_bytes());
+
+        let trailers = req.trailers.as_ref().unwrap();
+        assert_eq!(1, trailers.len());
+        assert_eq!(b"Expires", &req.raw()[trailers[0].name.clone()]);
+        assert_eq!(
+            b"Wed, 21 Oct 2015 07:28:00 GMT",
+            &req.raw()[trailers[0].value.clone()]
+        );
+        assert_eq!(2, req.headers.len());
+    }
+
+    #[test]
+    pub fn chunked_body_rejects_disallowed_trailers() {
+        for trailer in [
+            &b"Content-Length: 5"[..],
+            b"transfer-encoding: chunked",
+            b"Host: a",
+        ] {
+            let input = [
+                &b"POST / HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n0\r\n"[..],
+                trailer,
+                b"\r\n\r\n",
+            ]
+            .concat();
+            let mut req = H1Request::new();
+            req.fill(&mut &input[..]).unwrap();
+
+            assert_eq!(Err(ParseError::InvalidTrailer), req.parse());
+        }
+
+        let mut req = H1Request::new();
+        req.fill(&mut &b"POST / HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\nz\r\n"[..])
+            .unwrap();
+        assert_eq!(Err(ParseError::Chunk), req.parse());
+    }
+
+    #[test]
+    pub fn trailers_share_header_limits() {
+        let mut trailers = Vec::new();
+        let section = b"X-Checksum: abc\r\nX-Count: 3\r\n\r\n";
+        assert_eq!(
+            Ok(Status::Complete(section.len())),
+            parse_trailers(section, &mut trailers, &ParserConfig::default())
+        );
+        assert_eq!(2, trailers.len());
+
+        let config = ParserConfig {
+            max_header_bytes: 16,
+            ..ParserConfig::default()
+        };
+        trailers.clear();
+        assert_eq!(
+            Err(ParseError::HeadersTooLarge),
+            parse_trailers(section, &mut trailers, &config)
+        );
+
+        let too_many = request_with_headers(MAX_HEADERS + 1);
+        let too_many = &too_many[b"GET / HTTP/1.1\r\n".len()..];
+        trailers.clear();
+        assert_eq!(
+            Err(ParseError::TooManyHeaders),
+            parse_trailers(too_many, &mut trailers, &ParserConfig::default())
+        );
+
+        // a chunked request with oversized trailers is never complete
+        let request = [
+            &b"POST / HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n0\r\n"[..],
+            too_many,
+        ]
+        .concat();
+        assert!(split_requests(&request).is_empty());
+    }
+
+    #[test]
+    pub fn parse_accepts_max_headers() {
+        let input = request_with_headers(MAX_HEADERS);
+        let mut req = H1Request::new();
+        let mut buf: &[u8] = &input;
+        req.fill(&mut buf).unwrap();
+
+        assert_eq!(Ok(Status::Complete(input.len())), req.parse());
+        assert!(
+            matches!(parse_borrowed(&input), Ok(Status::Complete((_, r))) if r.headers.len() == MAX_HEADERS)
+        );
+    }
+
+    #[test]
+    pub fn parse_rejects_too_many_headers() {
+        let input = request_with_headers(MAX_HEADERS + 1);
+        let mut req = H1Request::new();
+        let mut buf: &[u8] = &input;
+        req.fill(&mut buf).unwrap();
+
+        assert_eq!(Err(ParseError::TooManyHeaders), req.parse());
+        assert_eq!(Err(ParseError::TooManyHeaders), parse_borrowed(&input));
+    }
+
+    #[test]
+    pub fn header_value_excludes_trailing_whitespace() {
+        let input = b"GET / HTTP/1.1\r\nHost: example.org \t \t\r\nUser-Agent: a b\t\r\n\r\n";
+        let mut req = H1Request::new();
+        req.fill(&mut &input[..]).unwrap();
+        assert_eq!(Ok(Status::Complete(input.len())), req.parse());
+
+        let host = &req.header_ranges()[0];
+        assert_eq!(22..33, host.value);
+        assert_eq!(b"example.org", &req.raw()[host.value.clone()]);
+        assert_eq!(Some(b"a b" as &[u8]), req.header(b"user-agent"));
+    }
+
+    #[test]
+    pub fn header_value_empty_or_all_whitespace() {
+        let input = b"GET / HTTP/1.1\r\nX-Pad:    \r\nX-Empty:\r\nX-Tab:\t\r\nHost: a\r\n\r\n";
+        let mut req = H1Request::new();
+        req.fill(&mut &input[..]).unwrap();
+        assert_eq!(Ok(Status::Complete(input.len())), req.parse());
+
+        for name in [&b"X-Pad"[..], b"X-Empty", b"X-Tab"] {
+            assert_eq!(Some(&[] as &[u8]), req.header(name));
+        }
+        assert_eq!(Some(&b"a"[..]), req.header(b"Host"));
+
+        let (_, result) = parse_with(
+            ParserConfig::default(),
+            b"GET / HTTP/1.1\r\nX-Bad: \x01\r\n\r\n",
+        );
+        assert_eq!(Err(ParseError::HeaderValue), result);
+    }
+
+    #[test]
+    pub fn whitespace_before_colon_rejected() {
+        for config in [ParserConfig::strict(), ParserConfig::compatibility()] {
+            for input in [
+                &b"GET / HTTP/1.1\r\nHost : x\r\n\r\n"[..],
+                b"GET / HTTP/1.1\r\nHost\t: x\r\n\r\n",
+            ] {
+                let (_, result) = parse_with(config, input);
+                assert_eq!(Err(ParseError::WhitespaceBeforeColon), result);
+            }
+        }
+
+        let (_, result) = parse_with(
+            ParserConfig::default(),
+            b"GET / HTTP/1.1\r\nHost\x01: x\r\n\r\n",
+        );
+        assert_eq!(Err(ParseError::HeaderName), result);
+    }
+
+    #[test]
+    pub fn header_lookup_ignores_case() {
+        let mut req = H1Request::new();
+        let mut buf = REQ_LONG;
+        req.fill(&mut buf).unwrap();
+        req.parse().unwrap();
+
+        assert_eq!(Some(b"669" as &[u8]), req.header(b"content-length"));
+        assert_eq!(Some(b"trailers" as &[u8]), req.header(b"TE"));
+        assert_eq!(None, req.header(b"Transfer-Encoding"));
+    }
+
+    #[test]
+    pub fn header_str_requires_utf8() {
+        // ë encoded as UTF-8, then as Latin-1
+        let input = [
+            &b"GET / HTTP/1.1\r\nX-Name: "[..],
+            "Zoë".as_bytes(),
+            b"\r\nX-Latin1: Zo\xeb\r\n\r\n",
+        ]
+        .concat();
+        let (req, result) = parse_with(ParserConfig::default(), &input);
+        assert_eq!(Ok(Status::Complete(input.len())), result);
+
+        assert_eq!(Some(Ok("Zoë")), req.header_str("X-Name"));
+        assert_eq!(
+            Some(Err(ParseError::HeaderValue)),
+            req.header_str("x-latin1")
+        );
+        assert_eq!(None, req.header_str("X-Missing"));
+    }
+
+    #[test]
+    pub fn host_port_defaults_to_scheme_port() {
+        let cases: [(&[u8], Option<u16>, Option<u16>); 9] = [
+            (b"example.com", Some(80), Some(443)),
+            (b"example.com:8080", Some(8080), Some(8080)),
+            (b"example.com:", Some(80), Some(443)),
+            (b"[::1]:9000", Some(9000), Some(9000)),
+            (b"[::1]", Some(80), Some(443)),
+            (b"::1", None, None),
+            (b"[::1]9000", None, None),
+            (b"example.com:65536", None, None),
+            (b"example.com:+80", None, None),
+        ];
+
+        for (host, plain, tls) in cases {
+            let input = [&b"GET / HTTP/1.1\r\nHost: "[..], host, b"\r\n\r\n"].concat();
+            let (req, result) = parse_with(ParserConfig::default(), &input);
+            assert_eq!(Ok(Status::Complete(input.len())), result);
+            assert_eq!(plain, req.host_port(false), "{}", req);
+            assert_eq!(tls, req.host_port(true), "{}", req);
+        }
+
+        let (req, _) = parse_with(ParserConfig::default(), b"GET / HTTP/1.1\r\n\r\n");
+        assert_eq!(None, req.host_port(false));
+    }
+
+    #[test]
+    pub fn validate_requires_single_host() {
+        let cases: [(&[u8], Result<(), ParseError>); 6] = [
+            (b"GET / HTTP/1.1\r\nHost: www.example.org\r\n\r\n", Ok(())),
+            (
+                b"GET / HTTP/1.1\r\nAccept: */*\r\n\r\n",
+                Err(ParseError::MissingHost),
+            ),
+            (
+                b"GET / HTTP/1.1\r\nHost: www.example.org\r\nhost: www.example.com\r\n\r\n",
+                Err(ParseError::DuplicateHost),
+            ),
+            (b"GET http://www.example.org/ HTTP/1.1\r\n\r\n", Ok(())),
+            (b"GET / HTTP/1.0\r\n\r\n", Ok(())),
+            (
+                b"GET / HTTP/1.0\r\nHost: www.example.org\r\nHost: www.example.org\r\n\r\n",
+                Err(ParseError::DuplicateHost),
+            ),
+        ];
+
+        for (input, expected) in cases {
+            let (req, result) = parse_with(ParserConfig::compatibility(), input);
+            assert_eq!(Ok(Status::Complete(input.len())), result);
+            assert_eq!(expected, req.validate(), "{}", req);
+        }
+    }
+
+    #[test]
+    pub fn unsupported_expectation_detects_extra_tokens() {
+        let input = b"PUT /upload HTTP/1.1\r\nExpect: 100-continue, bar\r\n\r\n";
+        let mut req = H1Request::new();
+        req.fill(&mut &input[..]).unwrap();
+        req.parse().unwrap();
+
+        assert!(req.unsupported_expectation());
+    }
+
+    #[test]
+    pub fn unsupported_expectation_allows_100_continue() {
+        let input = b"PUT /upload HTTP/1.1\r\nExpect: 100-Continue,\r\n\r\n";
+        let mut req = H1Request::new();
+        req.fill(&mut &input[..]).unwrap();
+        req.parse().unwrap();
+        assert!(!req.unsupported_expectation());
+
+        let mut req = H1Request::new();
+        let mut buf = REQ;
+        req.fill(&mut buf).unwrap();
+        req.parse().unwrap();
+        assert!(!req.unsupported_expectation());
+    }
+
+    #[test]
+    pub fn expects_continue_requires_body() {
+        let cases: [(&[u8], bool); 5] = [
+            (
+                b"PUT /upload HTTP/1.1\r\nExpect: 100-continue\r\nContent-Length: 5\r\n\r\n",
+                true,
+            ),
+            (
+                b"PUT /upload HTTP/1.1\r\nExpect: 100-continue\r\nTransfer-Encoding: chunked\r\n\r\n",
+                true,
+            ),
+            (
+                b"PUT /upload HTTP/1.1\r\nExpect: 100-continue\r\nContent-Length: 0\r\n\r\n",
+                false,
+            ),
+            (
+                b"PUT /upload HTTP/1.0\r\nExpect: 100-continue\r\nContent-Length: 5\r\n\r\n",
+                false,
+            ),
+            (b"PUT /upload HTTP/1.1\r\nContent-Length: 5\r\n\r\n", false),
+        ];
+
+        for (input, expected) in cases {
+            let mut req = H1Request::new();
+            req.fill(&mut &input[..]).unwrap();
+            req.parse().unwrap();
+            assert_eq!(
+                expected,
+                req.expects_continue(),
+                "{}",
+                from_utf8(input).unwrap()
+            );
+        }
+    }
+
+    #[test]
+    pub fn should_keep_alive_follows_version_defaults() {
+        let cases: [(&[u8], bool); 6] = [
+            (b"GET / HTTP/1.1\r\n\r\n", true),
+            (b"GET / HTTP/1.1\r\nConnection: Close\r\n\r\n", false),
+            (
+                b"GET / HTTP/1.1\r\nConnection: upgrade, close\r\n\r\n",
+                false,
+            ),
+            (b"GET / HTTP/1.0\r\n\r\n", false),
+            (b"GET / HTTP/1.0\r\nConnection: Keep-Alive\r\n\r\n", true),
+            (
+                b"GET / HTTP/1.0\r\nConnection: keep-alive, close\r\n\r\n",
+                false,
+            ),
+        ];
+
+        for (input, expected) in cases {
+            let mut req = H1Request::new();
+            req.fill(&mut &input[..]).unwrap();
+            req.parse().unwrap();
+
+            assert_eq!(expected, req.should_keep_alive(), "{}", req);
+        }
+    }
+
+    #[test]
+    pub fn is_keep_alive_truth_table() {
+        let cases: [(&[u8], bool); 10] = [
+            (b"GET / HTTP/1.1\r\n\r\n", true),
+            (b"GET / HTTP/1.1\r\nConnection: Upgrade\r\n\r\n", true),
+            (b"GET / HTTP/1.1\r\nConnection: TE, CLOSE\r\n\r\n", false),
+            (b"GET / HTTP/1.1\r\nConnection: close\r\n\r\n", false),
+            (b"GET / HTTP/1.1\r\nConnection: keep-alive\r\n\r\n", true),
+            (b"GET / HTTP/1.0\r\n\r\n", false),
+            (b"GET / HTTP/1.0\r\nConnection: close\r\n\r\n", false),
+            (b"GET / HTTP/1.0\r\nConnection: keep-alive\r\n\r\n", true),
+            (
+                b"POST / HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n",
+                true,
+            ),
+            (
+                b"POST / HTTP/1.1\r\nTransfer-Encoding: chunked\r\nContent-Length: 5\r\n\r\n0\r\n\r\n",
+                false,
+            ),
+        ];
+
+        for (input, expected) in cases {
+            let mut req = H1Request::new();
+            req.fill(&mut &input[..]).unwrap();
+            req.parse().unwrap();
+
+            assert_eq!(expected, req.is_keep_alive(), "{}", req);
+        }
+    }
+
+    #[test]
+    pub fn test_req_comp() {
+        let mut req = H1Request::new();
+        let mut buf = REQ_COMP;
+        req.fill(&mut buf).unwrap();
+        assert_eq!(Ok(Status::Complete(REQ_COMP.len())), req.parse());
+        assert_eq!(format!("{}", req), from_utf8(REQ_COMP).unwrap());
+    }
+
+    #[test]
+    pub fn test_multiple() {
+        let inputs = [REQ, REQ_MED, REQ_COMP, REQ_LONG];
+
+        for &input in inputs.iter() {
+            let mut i = Vec::default();
+            i.extend_from_slice(input);
+
+            let mut i: &[u8] = &i;
+            let mut req = H1Request::new();
+            req.fill(&mut i).unwrap();
+            req.parse().unwrap();
+            println!("{}\n{}", from_utf8(input).unwrap(), req);
+            assert_eq!(from_utf8(input).unwrap(), format!("{}", req));
+        }
+    }
+
+    #[test]
+    pub fn parse_one_byte_at_a_time() {
+        let req_long = req_long_with_body();
+        for input in [REQ, REQ_MED, REQ_COMP, &req_long] {
+            let head_len = input.windows(4).position(|w| w == b"\r\n\r\n").unwrap() + 4;
+            let mut req = H1Request::new();
+            let mut stream = MockStream::new();
+
+            for (i, byte) in input.iter().enumerate() {
+                stream.push(&[*byte]);
+                assert_eq!(1, req.fill(&mut stream).unwrap());
+
+                let parsed = req.parse();
+                let borrowed = parse_borrowed(req.raw()).map(|s| matches!(s, Status::Complete(_)));
+
+                if i + 1 == input.len() {
+                    assert_eq!(Ok(Status::Complete(input.len())), parsed);
+                } else {
+                    assert_eq!(Ok(Status::Partial), parsed);
+                }
+                assert_eq!(Ok(i + 1 >= head_len), borrowed);
+            }
+
+            let Ok(Status::Complete((_, borrowed))) = parse_borrowed(req.raw()) else {
+                panic!("Result status is not complete");
+            };
+            let lines = input.windows(2).filter(|w| w == b"\r\n").count();
+
+            assert_eq!(input, req.raw());
+            assert_eq!(input.len() - head_len, req.body_bytes().unwrap().len());
+            assert_eq!(from_utf8(&input[..head_len]).unwrap(), format!("{}", req));
+            assert_eq!(Some(borrowed.method), req.method);
+            assert_eq!(borrowed.target, &input[req.target.clone().unwrap()]);
+            assert_eq!(Some(borrowed.version), req.version);
+            assert_eq!(lines - 2, borrowed.headers.len());
+        }
+    }
+
+    fn large_header_block() -> Vec<u8> {
+        let mut req = b"GET / HTTP/1.1\r\n".to_vec();
+        for i in 0..64 {
+            req.extend_from_slice(format!("X-Header-{}: {}\r\n", i, "x".repeat(48)).as_bytes());
+        }
+        req.extend_from_slice(b"\r\n");
+        req
+    }
+
+    #[test]
+    pub fn parse_within_budget_completes() {
+        let input = large_header_block();
+        let mut req = H1Request::with_parse_budget(input.len());
+        let mut buf: &[u8] = &input;
+        req.fill(&mut buf).unwrap();
+
+        assert_eq!(Ok(Status::Complete(input.len())), req.parse());
+    }
+
+    #[test]
+    pub fn parse_over_budget_fails() {
+        let input = large_header_block();
+        let mut req = H1Request::with_parse_budget(input.len() * 4);
+        let mut stream = MockStream::new();
+
+        let mut result = Ok(Status::Partial);
+        for chunk in input.chunks(256) {
+            stream.push(chunk);
+            req.fill(&mut stream).unwrap();
+            result = req.parse();
+            if result.is_err() {
+                break;
+            }
+        }
+
+        assert_eq!(Err(ParseError::BudgetExceeded), result);
+        assert_eq!(400, ParseError::BudgetExceeded.response_status() as u16);
+    }
+
+    #[test]
+    pub fn header_ranges_index_into_raw() {
+        let mut req = H1Request::new();
+        let mut buf = REQ_COMP;
+        req.fill(&mut buf).unwrap();
+        req.parse().unwrap();
+
+        let names: Vec<&[u8]> = req
+            .header_ranges()
+            .iter()
+            .map(|header| &req.raw()[header.name.clone()])
+            .collect();
+
+        assert_eq!(
+            names,
+            [
+                b"Host" as &[u8],
+                b"User-Agent",
+                b"Accept",
+                b"Accept-Language",
+                b"Accept-Encoding",
+                b"Accept-Charset",
+                b"Keep-Alive",
+                b"Connection",
+                b"Cookie",
+            ]
+        );
+        assert_eq!(
+            b"Shift_JIS,utf-8;q=0.7,*;q=0.7",
+            &req.raw()[req.header_ranges()[5].value.clone()]
+        );
+    }
+
+    #[test]
+    pub fn header_ranges_empty_before_parse() {
+        let req = H1Request::new();
+        assert!(req.header_ranges().is_empty());
+    }
+
+    #[test]
+    pub fn unusual_token_header_names_round_trip() {
+        const REQ_TOKENS: &[u8] = b"\
+GET / HTTP/1.1\r\n\
+Sec-Fetch-Dest: empty\r\n\
+Sec-Fetch-Mode: cors\r\n\
+Sec-Fetch-Site: same-origin\r\n\
+X-Forwarded-For-2: 10.0.0.1\r\n\
+123: numeric\r\n\
+X-Very-Long-Header-Name-With-Many-Hyphens-1-2-3-4-5-6-7-8-9: long\r\n\
+!#$%&'*+-.^_`|~09AZaz: every-tchar\r\n\r\n";
+
+        let mut req = H1Request::new();
+        let mut buf = REQ_TOKENS;
+        req.fill(&mut buf).unwrap();
+        assert_eq!(Ok(Status::Complete(REQ_TOKENS.len())), req.parse());
+        assert_eq!(7, req.header_ranges().len());
+        assert_eq!(from_utf8(REQ_TOKENS).unwrap(), format!("{}", req));
+    }
+
+    #[test]
+    pub fn uri_segments_iterate_target_path() {
+        let mut req = H1Request::new();
+        let mut buf = REQ;
+        req.fill(&mut buf).unwrap();
+        req.parse().unwrap();
+
+        let uri = req.uri().unwrap();
+        let segments: Vec<Vec<u8>> = uri.segments().map(|s| s.into_owned()).collect();
+
+        assert_eq!(b"/api/v1.0/weather/forecast", uri.path());
+        assert_eq!(None, uri.query());
+        assert_eq!(
+            vec![
+                b"api".to_vec(),
+                b"v1.0".to_vec(),
+                b"weather".to_vec(),
+                b"forecast".to_vec()
+            ],
+            segments
+        );
+    }
+
+    #[test]
+    pub fn uri_requires_parsed_target() {
+        let req = H1Request::new();
+        assert_eq!(Err(ParseError::Target), req.uri());
+    }
+
+    #[test]
+    pub fn borrowed_round_trips() {
+        for input in [REQ, REQ_MED, REQ_COMP, REQ_LONG] {
+            let Ok(Status::Complete((read, req))) = parse_borrowed(input) else {
+                panic!("Result status is not complete");
+            };
+
+            let mut serialized = format!(
+                "{} {} {}\r\n",
+                req.method,
+                from_utf8(req.target).unwrap(),
+                req.version
+            );
+            for (name, value) in req.headers {
+                serialized.push_str(&format!(
+                    "{}: {}\r\n",
+                    from_utf8(name).unwrap(),
+                    from_utf8(value).unwrap()
+                ));
+            }
+            serialized.push_str("\r\n");
+
+            assert_eq!(input.len(), read);
+            assert_eq!(from_utf8(input).unwrap(), serialized);
+        }
+    }
+
+    #[test]
+    pub fn borrowed_partial_until_head_complete() {
+        assert_eq!(Ok(Status::Partial), parse_borrowed(&REQ[..45]));
+        assert_eq!(Ok(Status::Partial), parse_borrowed(&REQ[..REQ.len() - 2]));
+    }
+
+    #[test]
+    pub fn method_returns_ok_with_valid_http_verb() {
+        let verbs = [
+            ("GET     ", Method::Get),
+            ("PUT     ", Method::Put),
+            ("POST    ", Method::Post),
+            ("HEAD    ", Method::Head),
+            ("TRACE   ", Method::Trace),
+            ("DELETE  ", Method::Delete),
+            ("OPTIONS ", Method::Options),
+            ("CONNECT ", Method::Connect),
+        ];
+
+        for (verb, expected) in verbs {
+            let result = parse_method(verb.as_bytes());
+
+            assert_eq!(result, Ok(Status::Complete((verb.trim().len(), expected))));
+        }
+    }
+
+    #[test]
+    pub fn peek_method_classifies_short_buffers() {
+        let verbs = [
+            ("GET", Method::Get),
+            ("PUT", Method::Put),
+            ("POST", Method::Post),
+            ("HEAD", Method::Head),
+            ("TRACE", Method::Trace),
+            ("DELETE", Method::Delete),
+            ("OPTIONS", Method::Options),
+            ("CONNECT", Method::Connect),
+        ];
+
+        for (verb, expected) in verbs {
+            for len in 0..=verb.len() {
+                assert_eq!(None, peek_method(&verb.as_bytes()[..len]));
+            }
+
+            assert_eq!(Some(expected), peek_method(format!("{} ", verb).as_bytes()));
+        }
+    }
+
+    #[test]
+    pub fn peek_method_rejects_unknown_methods() {
+        assert_eq!(None, peek_method(b"PATCH / HTTP/1.1"));
+        assert_eq!(None, peek_method(b"get / HTTP/1.1"));
+        assert_eq!(None, peek_method(b" GET / HTTP/1.1"));
+        assert_eq!(None, peek_method(b"GETTING / HTTP/1.1"));
+    }
+
+    #[test]
+    pub fn target_returns_ok_with_valid_path() {
+        let path: PathBuf = FilePath().fake();
+        let path = path.to_str().unwrap();
+
+        let buf = format!("{} ", path);
+
+        let result = parse_target(buf.as_bytes(), 0);
+
+        assert_eq!(result, Ok(Status::Complete((path.len(), 0..path.len()))));
+    }
+
+    #[test]
+    pub fn target_rejects_empty_target() {
+        assert_eq!(
+            Err(ParseError::Target),
+            parse_target(b"GET  HTTP/1.1\r\n", 4)
+        );
+        assert_eq!(Err(ParseError::Target), parse_target(b"GET \r\n", 4));
+    }
+
+    #[test]
+    pub fn request_with_missing_target_fails() {
+        for input in [
+            b"GET  HTTP/1.1\r\nHost: www.example.org\r\n\r\n" as &[u8],
+            b"GET HTTP/1.1\r\nHost: www.example.org\r\n\r\n",
+            b"GET \r\nHost: www.example.org\r\n\r\n",
+        ] {
+            let mut req = H1Request::new();
+            let mut buf = input;
+            req.fill(&mut buf).unwrap();
+
+            assert_eq!(Err(ParseError::Target), req.parse());
+            assert_eq!(Err(ParseError::Target), parse_borrowed(input));
+        }
+    }
+
+    #[test]
+    pub fn target_partial_at_end_of_buffer() {
+        assert_eq!(Ok(Status::Partial), parse_target(b"GET /api/v1.0", 4));
+        assert_eq!(Ok(Status::Partial), parse_target(b"GET ", 4));
     }
 }
@@ -1,16 +1,38 @@
 //! Response model
 
-use crate::parser::{status::Status, Version};
+use std::borrow::Cow;
+use std::fmt::Write;
+use std::fs::File;
+use std::io;
+use std::path::Path;
+use std::time::Duration;
 
-use super::request::Header;
+use crate::parser::{h1::request::H1Request, status::Status, Method, Version};
+use crate::rng;
+
+/// Body of a [`Response`]
+#[derive(Debug)]
+pub enum Body {
+    /// Body held in memory
+    Bytes(Vec<u8>),
+    /// Body streamed from a file as the response is written, without first reading it into memory
+    File {
+        /// File to stream
+        file: File,
+        /// Number of bytes to stream from the start of the file
+        len: u64,
+    },
+}
 
 /// Response model
 #[derive(Debug)]
 pub struct Response {
     version: Version,
     status: Status,
-    headers: Option<Vec<Header>>,
-    body: String,
+    headers: Vec<(String, String)>,
+    body: Body,
+    close: bool,
+    canonical: bool,
 }
 
 impl Response {
@@ -19,13 +41,740 @@ impl Response {
         Response {
             version,
             status,
-            headers: None,
-            body: String::new(),
+            headers: Vec::new(),
+            body: Body::Bytes(Vec::new()),
+            close: false,
+            canonical: false,
         }
     }
 
-    /// TODO
-    pub fn get_serialized(&self) -> &str {
-        "HTTP/1.1 204\r\nServer: rask/0.0.1\r\nConnection: keep-alive\r\n\r\n"
+    /// Returns a builder for a response with the given status line.
+    ///
+    /// ```rust
+    /// # use rask::parser::{status::Status, Version};
+    /// # use rask::parser::h1::response::Response;
+    /// let response = Response::builder(Version::H1_1, Status::Ok)
+    ///     .header("Content-Type", "text/plain")
+    ///     .body(b"hello".to_vec())
+    ///     .build();
+    ///
+    /// assert_eq!(vec![("Content-Type".to_owned(), "text/plain".to_owned())], response.headers());
+    /// ```
+    pub fn builder(version: Version, status: Status) -> ResponseBuilder {
+        ResponseBuilder {
+            response: Self::new_with_status_line(version, status),
+        }
+    }
+
+    /// Creates a response whose body is the contents of the file at `path`. On connections that
+    /// support it, the file is sent directly from the page cache to the socket.
+    pub fn from_file<P: AsRef<Path>>(
+        path: P,
+        version: Version,
+        status: Status,
+    ) -> io::Result<Self> {
+        let file = File::open(path)?;
+        let len = file.metadata()?.len();
+
+        Ok(Response {
+            version,
+            status,
+            headers: Vec::new(),
+            body: Body::File { file, len },
+            close: false,
+            canonical: false,
+        })
+    }
+
+    /// Returns the status of the response
+    pub fn status(&self) -> Status {
+        self.status
+    }
+
+    /// Replaces the status of the response, such as when middleware overrides the status chosen
+    /// by a handler.
+    pub fn set_status(&mut self, status: Status) {
+        self.status = status;
+    }
+
+    /// Returns the HTTP version of the status line
+    pub fn version(&self) -> &Version {
+        &self.version
+    }
+
+    /// Replaces the HTTP version of the status line
+    pub fn set_version(&mut self, version: Version) {
+        self.version = version;
+    }
+
+    /// Returns the header fields of the response, in the order they were added. Fields managed
+    /// by the response itself, such as `Connection` and `Content-Length`, are not included.
+    pub fn headers(&self) -> &[(String, String)] {
+        &self.headers
+    }
+
+    /// Returns the response body
+    pub fn body(&self) -> &Body {
+        &self.body
+    }
+
+    /// Consumes the response, returning its body
+    pub fn into_body(self) -> Body {
+        self.body
+    }
+
+    /// Sets whether the response tells the client to close the connection, sending
+    /// `Connection: close` rather than `Connection: keep-alive`.
+    /// [RFC 9112 Section 9.6](https://www.rfc-editor.org/rfc/rfc9112#section-9.6)
+    pub fn set_close(&mut self, close: bool) {
+        self.close = close;
+    }
+
+    /// Returns true if the connection will be closed after this response is sent
+    pub fn is_close(&self) -> bool {
+        self.close
+    }
+
+    /// Sets whether header fields are serialized in a canonical order and casing, so that
+    /// responses are byte-for-byte deterministic regardless of the order headers were added.
+    ///
+    /// Canonical responses send `Date`, `Server`, `Content-Type`, and `Content-Length` first,
+    /// followed by the remaining fields in the order they were added. Every field name is
+    /// capitalized at the start of each hyphen-separated word, as in `Cache-Control`, except for
+    /// the conventional spellings `ETag`, `WWW-Authenticate`, `TE`, and `DNT`.
+    ///
+    /// ```rust
+    /// # use rask::parser::{status::Status, Version};
+    /// # use rask::parser::h1::response::Response;
+    /// let mut response = Response::builder(Version::H1_1, Status::Ok)
+    ///     .header("x-request-id", "42")
+    ///     .header("content-type", "text/plain")
+    ///     .build();
+    /// response.set_canonical(true);
+    ///
+    /// let serialized = String::from_utf8(response.serialize()).unwrap();
+    /// assert!(serialized.contains("Server: rask/0.0.1\r\nContent-Type: text/plain\r\n"));
+    /// assert!(serialized.contains("Connection: keep-alive\r\nX-Request-Id: 42\r\n"));
+    /// ```
+    pub fn set_canonical(&mut self, canonical: bool) {
+        self.canonical = canonical;
+    }
+
+    /// Returns true if header fields are serialized in canonical order and casing
+    pub fn is_canonical(&self) -> bool {
+        self.canonical
+    }
+
+    /// Adds a `WWW-Authenticate` header carrying an authentication challenge, such as
+    /// `Basic realm="api"`. It is sent with `401 Unauthorized` responses to indicate how the
+    /// client may authenticate. Each call adds a separate header line, so several challenges can
+    /// be offered.
+    /// [RFC 9110 Section 11.6.1](https://www.rfc-editor.org/rfc/rfc9110#section-11.6.1)
+    pub fn www_authenticate(&mut self, challenge: &str) {
+        self.headers
+            .push(("WWW-Authenticate".to_owned(), challenge.to_owned()));
+    }
+
+    /// Sets the `Allow` header to the methods supported by the target resource, replacing any
+    /// `Allow` header already added. It is sent in response to `OPTIONS` requests, and with
+    /// `405 Method Not Allowed`. An `OPTIONS` request with an origin-form target, such as
+    /// `OPTIONS /api`, asks about that resource, while one with an asterisk-form target,
+    /// `OPTIONS *`, asks about the server as a whole. See
+    /// [`TargetForm`](crate::parser::h1::request::TargetForm).
+    /// [RFC 9110 Section 10.2.1](https://www.rfc-editor.org/rfc/rfc9110#section-10.2.1)
+    ///
+    /// ```rust
+    /// # use rask::parser::{status::Status, Method, Version};
+    /// # use rask::parser::h1::response::Response;
+    /// let mut response = Response::new_with_status_line(Version::H1_1, Status::NoContent);
+    /// response.allow(&[Method::Get, Method::Head, Method::Options]);
+    ///
+    /// let serialized = String::from_utf8(response.serialize()).unwrap();
+    /// assert!(serialized.contains("Allow: GET, HEAD, OPTIONS\r\n"));
+    /// ```
+    pub fn allow(&mut self, methods: &[Method]) {
+        let mut value = String::new();
+        for method in methods {
+            if !value.is_empty() {
+                value.push_str(", ");
+            }
+
+            let _ = write!(value, "{}", method);
+        }
+
+        self.headers
+            .retain(|(name, _)| !name.eq_ignore_ascii_case("Allow"));
+        self.headers.push(("Allow".to_owned(), value));
+    }
+
+    /// Prevents the response from being stored by any cache, for dynamic or sensitive content.
+    /// Sets `Cache-Control: no-store, no-cache, must-revalidate`, along with `Pragma: no-cache` and
+    /// `Expires: 0` for HTTP/1.0 caches, replacing any of these headers already added.
+    /// [RFC 9111 Section 5.2.2](https://www.rfc-editor.org/rfc/rfc9111#section-5.2.2)
+    pub fn no_cache(&mut self) {
+        const FIELDS: [(&str, &str); 3] = [
+            ("Cache-Control", "no-store, no-cache, must-revalidate"),
+            ("Pragma", "no-cache"),
+            ("Expires", "0"),
+        ];
+
+        self.headers.retain(|(name, _)| {
+            !FIELDS
+                .iter()
+                .any(|(field, _)| field.eq_ignore_ascii_case(name))
+        });
+        self.headers.extend(
+            FIELDS
+                .iter()
+                .map(|(name, value)| ((*name).to_owned(), (*value).to_owned())),
+        );
+    }
+
+    /// Adds an `X-Request-ID` header echoing the ID sent by the client, so the request can be
+    /// traced across services. If the request has no ID, or it is not valid UTF-8, a new random
+    /// ID formatted like a UUID is generated in its place. The attached ID is returned so that
+    /// logs can reference it.
+    ///
+    /// ```rust
+    /// # use rask::parser::{status::Status, Version};
+    /// # use rask::parser::h1::{request::H1Request, response::Response};
+    /// let mut request = H1Request::new();
+    /// request.fill(&mut &b"GET / HTTP/1.1\r\nX-Request-ID: abc\r\n\r\n"[..]).unwrap();
+    /// request.parse().unwrap();
+    ///
+    /// let mut response = Response::new_with_status_line(Version::H1_1, Status::Ok);
+    /// assert_eq!("abc", response.echo_request_id(&request));
+    /// ```
+    pub fn echo_request_id(&mut self, request: &H1Request) -> String {
+        let id = request
+            .request_id()
+            .filter(|id| !id.is_empty())
+            .and_then(|id| std::str::from_utf8(id).ok())
+            .map_or_else(generate_request_id, str::to_owned);
+
+        self.headers.push(("X-Request-ID".to_owned(), id.clone()));
+        id
+    }
+
+    /// Adds a `Server-Timing` header describing how long the server spent on each named metric,
+    /// so that backend timings are visible in browser devtools. Durations are reported in
+    /// milliseconds.
+    /// [W3C Server Timing](https://www.w3.org/TR/server-timing/#the-server-timing-header-field)
+    ///
+    /// ```rust
+    /// # use std::time::Duration;
+    /// # use rask::parser::{status::Status, Version};
+    /// # use rask::parser::h1::response::Response;
+    /// let mut response = Response::new_with_status_line(Version::H1_1, Status::Ok);
+    /// response.server_timing(&[("db", Duration::from_micros(53_200))]);
+    ///
+    /// let serialized = String::from_utf8(response.serialize()).unwrap();
+    /// assert!(serialized.contains("Server-Timing: db;dur=53.2\r\n"));
+    /// ```
+    pub fn server_timing(&mut self, metrics: &[(&str, Duration)]) {
+        let mut value = String::new();
+        for (name, duration) in metrics {
+            if !value.is_empty() {
+                value.push_str(", ");
+            }
+
+            let _ = write!(value, "{};dur={:.1}", name, duration.as_secs_f64() * 1000.0);
+        }
+
+        self.headers.push(("Server-Timing".to_owned(), value));
+    }
+
+    /// Serializes the response, followed by its body if it is held in memory. File bodies are
+    /// not included, and must be written after the serialized bytes. Each header field is written
+    /// on its own line, including repeated names.
+    ///
+    /// A `Content-Length` header is computed from the body for every status that permits
+    /// content. Informational, `204 No Content`, and `304 Not Modified` responses are sent
+    /// without a body.
+    /// [RFC 9110 Section 8.6](https://www.rfc-editor.org/rfc/rfc9110#section-8.6)
+    pub fn serialize(&self) -> Vec<u8> {
+        let connection = if self.close { "close" } else { "keep-alive" };
+        let mut fields: Vec<(&str, Cow<'_, str>)> = Vec::with_capacity(self.headers.len() + 3);
+        fields.push(("Server", Cow::Borrowed("rask/0.0.1")));
+        fields.push(("Connection", Cow::Borrowed(connection)));
+        fields.extend(
+            self.headers
+                .iter()
+                .map(|(name, value)| (name.as_str(), Cow::Borrowed(value.as_str()))),
+        );
+
+        let permits_content = self.permits_content();
+        match self.body {
+            Body::Bytes(ref body) if permits_content => {
+                fields.push(("Content-Length", Cow::Owned(body.len().to_string())));
+            }
+            Body::File { len, .. } if permits_content => {
+                fields.push(("Content-Length", Cow::Owned(len.to_string())));
+            }
+            _ => {}
+        }
+
+        let mut head = format!(
+            "{} {} {}\r\n",
+            self.version,
+            self.status,
+            self.status.reason()
+        );
+        if self.canonical {
+            // stable, so fields outside the fixed order keep their insertion order
+            fields.sort_by_key(|(name, _)| canonical_rank(name));
+            for (name, value) in &fields {
+                push_canonical_name(&mut head, name);
+                let _ = write!(head, ": {}\r\n", value);
+            }
+        } else {
+            for (name, value) in &fields {
+                let _ = write!(head, "{}: {}\r\n", name, value);
+            }
+        }
+        head.push_str("\r\n");
+
+        let mut bytes = head.into_bytes();
+        if let Body::Bytes(ref body) = self.body {
+            if permits_content {
+                bytes.extend_from_slice(body);
+            }
+        }
+
+        bytes
+    }
+
+    #[inline]
+    fn permits_content(&self) -> bool {
+        !matches!(self.status.as_u16(), 100..=199 | 204 | 304)
+    }
+}
+
+/// Generates a random ID in the textual format of a version 4 UUID, such as
+/// `f81d4fae-7dec-41d0-a765-00a0c91e6bf6`.
+/// [RFC 9562 Section 5.4](https://www.rfc-editor.org/rfc/rfc9562#section-5.4)
+fn generate_request_id() -> String {
+    let high = (rng::next_u64() & !0xf000) | 0x4000;
+    let low = (rng::next_u64() & !(0b11 << 62)) | (0b10 << 62);
+
+    format!(
+        "{:08x}-{:04x}-{:04x}-{:04x}-{:012x}",
+        high >> 32,
+        (high >> 16) & 0xffff,
+        high & 0xffff,
+        low >> 48,
+        low & 0xffff_ffff_ffff
+    )
+}
+
+/// Position of a header field in canonical order. Fields not listed share the last position.
+#[inline]
+fn canonical_rank(name: &str) -> u8 {
+    const ORDER: [&str; 4] = ["Date", "Server", "Content-Type", "Content-Length"];
+
+    ORDER
+        .iter()
+        .position(|field| field.eq_ignore_ascii_case(name))
+        .unwrap_or(ORDER.len()) as u8
+}
+
+/// Appends the canonical spelling of a header field name to `buf`.
+fn push_canonical_name(buf: &mut String, name: &str) {
+    const EXCEPTIONS: [&str; 4] = ["ETag", "WWW-Authenticate", "TE", "DNT"];
+
+    if let Some(exception) = EXCEPTIONS.iter().find(|e| e.eq_ignore_ascii_case(name)) {
+        buf.push_str(exception);
+        return;
+    }
+
+    let mut word_start = true;
+    for c in name.chars() {
+        if word_start {
+            buf.push(c.to_ascii_uppercase());
+        } else {
+            buf.push(c.to_ascii_lowercase());
+        }
+        word_start = c == '-';
+    }
+}
+
+/// Builds a [`Response`] with headers and a body. See [`Response::builder`].
+#[derive(Debug)]
+pub struct ResponseBuilder {
+    response: Response,
+}
+
+impl ResponseBuilder {
+    /// Appends a header field. Repeated names are sent as separate fields, in the order they
+    /// were added, and are never combined into a comma-separated list. Fields such as
+    /// `Set-Cookie`, whose values may themselves contain commas, rely on this.
+    /// [RFC 9110 Section 5.3](https://www.rfc-editor.org/rfc/rfc9110#section-5.3)
+    pub fn header(mut self, name: &str, value: &str) -> Self {
+        self.response
+            .headers
+            .push((name.to_owned(), value.to_owned()));
+        self
+    }
+
+    /// Serializes header fields in canonical order and casing. See [`Response::set_canonical`].
+    pub fn canonical(mut self) -> Self {
+        self.response.canonical = true;
+        self
+    }
+
+    /// Sets the response body, sent with a `Content-Length` header.
+    pub fn body(mut self, body: Vec<u8>) -> Self {
+        self.response.body = Body::Bytes(body);
+        self
+    }
+
+    /// Builds the response
+    pub fn build(self) -> Response {
+        self.response
+    }
+}
+
+/// A response serialized once up front, for endpoints that return identical responses to every
+/// request, such as health checks or static error pages. Write it with
+/// [`Connection::write_raw`](crate::connection::Connection::write_raw) to skip serializing the
+/// response per request.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResponseTemplate {
+    bytes: Box<[u8]>,
+}
+
+impl ResponseTemplate {
+    /// Serializes `response` into a new template
+    pub fn new(response: &Response) -> Self {
+        Self {
+            bytes: response.serialize().into(),
+        }
+    }
+
+    /// Returns the serialized response
+    #[inline]
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+}
+
+impl From<Response> for ResponseTemplate {
+    fn from(response: Response) -> Self {
+        Self::new(&response)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs;
+    use std::time::Duration;
+
+    use super::{Body, Response, ResponseTemplate};
+    use crate::parser::h1::request::TargetForm;
+    use crate::parser::{h1::request::H1Request, status::Status, Method, Version};
+
+    #[test]
+    fn file_response_carries_content_length() {
+        let path = std::env::temp_dir().join(format!("rask-response-{}", std::process::id()));
+        fs::write(&path, b"hello, world").unwrap();
+
+        let response = Response::from_file(&path, Version::H1_1, Status::Ok).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(
+            b"HTTP/1.1 200 OK\r\nServer: rask/0.0.1\r\nConnection: keep-alive\r\nContent-Length: 12\r\n\r\n",
+            response.serialize().as_slice()
+        );
+        assert!(matches!(response.into_body(), Body::File { len: 12, .. }));
+    }
+
+    #[test]
+    fn builder_serializes_headers_and_content_length() {
+        let response = Response::builder(Version::H1_1, Status::Ok)
+            .header("Content-Type", "text/plain")
+            .header("Vary", "Accept")
+            .body(b"hello".to_vec())
+            .build();
+
+        assert_eq!(
+            b"HTTP/1.1 200 OK\r\nServer: rask/0.0.1\r\nConnection: keep-alive\r\nContent-Type: text/plain\r\nVary: Accept\r\nContent-Length: 5\r\n\r\nhello",
+            response.serialize().as_slice()
+        );
+        assert!(matches!(response.body(), Body::Bytes(body) if body == b"hello"));
+    }
+
+    #[test]
+    fn serialize_writes_status_line_header_and_body() {
+        let mut response = Response::builder(Version::H1_1, Status::Ok)
+            .header("Content-Type", "text/plain")
+            .body(b"hi".to_vec())
+            .build();
+        response.set_close(true);
+
+        assert_eq!(
+            b"HTTP/1.1 200 OK\r\nServer: rask/0.0.1\r\nConnection: close\r\nContent-Type: text/plain\r\nContent-Length: 2\r\n\r\nhi",
+            response.serialize().as_slice()
+        );
+    }
+
+    #[test]
+    fn set_status_and_version_reflect_in_serialization() {
+        let mut response = Response::builder(Version::H1_1, Status::Ok)
+            .body(b"hi".to_vec())
+            .build();
+        assert_eq!(Status::Ok as u16, response.status().as_u16());
+        assert_eq!(&Version::H1_1, response.version());
+
+        response.set_status(Status::NotFound);
+        response.set_version(Version::H1_0);
+        assert_eq!(404, response.status().as_u16());
+        assert_eq!(&Version::H1_0, response.version());
+        assert_eq!(
+            b"HTTP/1.0 404 Not Found\r\nServer: rask/0.0.1\r\nConnection: keep-alive\r\nContent-Length: 2\r\n\r\nhi",
+            response.serialize().as_slice()
+        );
+
+        response.set_status(Status::NoContent);
+        assert!(!response.serialize().ends_with(b"hi"));
+    }
+
+    #[test]
+    fn serialize_omits_body_for_no_content() {
+        let response = Response::builder(Version::H1_1, Status::NoContent)
+            .body(b"ignored".to_vec())
+            .build();
+
+        assert_eq!(
+            b"HTTP/1.1 204 No Content\r\nServer: rask/0.0.1\r\nConnection: keep-alive\r\n\r\n",
+            response.serialize().as_slice()
+        );
+    }
+
+    #[test]
+    fn www_authenticate_adds_single_challenge() {
+        let mut response = Response::new_with_status_line(Version::H1_1, Status::Unauthorized);
+        response.www_authenticate(r#"Basic realm="api""#);
+
+        assert_eq!(
+            b"HTTP/1.1 401 Unauthorized\r\nServer: rask/0.0.1\r\nConnection: keep-alive\r\nWWW-Authenticate: Basic realm=\"api\"\r\nContent-Length: 0\r\n\r\n",
+            response.serialize().as_slice()
+        );
+    }
+
+    #[test]
+    fn www_authenticate_repeats_for_each_challenge() {
+        let mut response = Response::new_with_status_line(Version::H1_1, Status::Unauthorized);
+        response.www_authenticate(r#"Bearer realm="api""#);
+        response.www_authenticate(r#"Basic realm="api", charset="UTF-8""#);
+
+        assert_eq!(
+            vec![
+                (
+                    "WWW-Authenticate".to_owned(),
+                    r#"Bearer realm="api""#.to_owned()
+                ),
+                (
+                    "WWW-Authenticate".to_owned(),
+                    r#"Basic realm="api", charset="UTF-8""#.to_owned()
+                ),
+            ],
+            response.headers()
+        );
+    }
+
+    #[test]
+    fn repeated_set_cookie_serializes_as_separate_lines() {
+        let response = Response::builder(Version::H1_1, Status::Ok)
+            .header(
+                "Set-Cookie",
+                "id=a3fWa; Expires=Wed, 21 Oct 2015 07:28:00 GMT",
+            )
+            .header("Set-Cookie", "theme=dark")
+            .build();
+
+        assert_eq!(
+            b"HTTP/1.1 200 OK\r\nServer: rask/0.0.1\r\nConnection: keep-alive\r\nSet-Cookie: id=a3fWa; Expires=Wed, 21 Oct 2015 07:28:00 GMT\r\nSet-Cookie: theme=dark\r\nContent-Length: 0\r\n\r\n",
+            response.serialize().as_slice()
+        );
+    }
+
+    #[test]
+    fn canonical_keeps_repeated_headers_in_order() {
+        let response = Response::builder(Version::H1_1, Status::Ok)
+            .header("set-cookie", "id=a3fWa")
+            .header("content-type", "text/plain")
+            .header("set-cookie", "theme=dark")
+            .canonical()
+            .build();
+
+        let serialized = String::from_utf8(response.serialize()).unwrap();
+        assert!(serialized.contains("Set-Cookie: id=a3fWa\r\nSet-Cookie: theme=dark\r\n"));
+    }
+
+    #[test]
+    fn no_cache_emits_every_cache_header() {
+        let mut response = Response::new_with_status_line(Version::H1_1, Status::Ok);
+        response.no_cache();
+
+        assert_eq!(
+            b"HTTP/1.1 200 OK\r\nServer: rask/0.0.1\r\nConnection: keep-alive\r\nCache-Control: no-store, no-cache, must-revalidate\r\nPragma: no-cache\r\nExpires: 0\r\nContent-Length: 0\r\n\r\n",
+            response.serialize().as_slice()
+        );
+    }
+
+    #[test]
+    fn no_cache_replaces_existing_cache_headers() {
+        let mut response = Response::builder(Version::H1_1, Status::Ok)
+            .header("cache-control", "max-age=3600")
+            .header("Content-Type", "text/plain")
+            .build();
+        response.no_cache();
+
+        let names: Vec<&str> = response
+            .headers()
+            .iter()
+            .map(|(name, _)| name.as_str())
+            .collect();
+        assert_eq!(
+            vec!["Content-Type", "Cache-Control", "Pragma", "Expires"],
+            names
+        );
+    }
+
+    #[test]
+    fn options_allow_distinguishes_resource_from_server() {
+        fn options_response(input: &[u8]) -> String {
+            let mut request = H1Request::new();
+            request.fill(&mut &input[..]).unwrap();
+            request.parse().unwrap();
+            assert_eq!(Some(Method::Options), request.method);
+
+            let allowed: &[Method] = match (request.target_form, request.path()) {
+                (Some(TargetForm::Asterisk), _) => &[
+                    Method::Get,
+                    Method::Head,
+                    Method::Post,
+                    Method::Delete,
+                    Method::Options,
+                ],
+                (Some(TargetForm::Origin), Some(b"/api")) => {
+                    &[Method::Get, Method::Post, Method::Options]
+                }
+                _ => &[Method::Get, Method::Head, Method::Options],
+            };
+
+            let mut response = Response::new_with_status_line(Version::H1_1, Status::NoContent);
+            response.allow(&[Method::Trace]);
+            response.allow(allowed);
+            String::from_utf8(response.serialize()).unwrap()
+        }
+
+        let api = options_response(b"OPTIONS /api HTTP/1.1\r\nHost: www.example.org\r\n\r\n");
+        assert!(api.contains("Allow: GET, POST, OPTIONS\r\n"));
+        assert_eq!(1, api.matches("Allow").count());
+
+        let server = options_response(b"OPTIONS * HTTP/1.1\r\nHost: www.example.org\r\n\r\n");
+        assert!(server.contains("Allow: GET, HEAD, POST, DELETE, OPTIONS\r\n"));
+    }
+
+    #[test]
+    fn server_timing_formats_every_metric() {
+        let mut response = Response::new_with_status_line(Version::H1_1, Status::Ok);
+        response.server_timing(&[
+            ("db", Duration::from_micros(53_200)),
+            ("app", Duration::from_millis(12)),
+        ]);
+
+        assert_eq!(
+            b"HTTP/1.1 200 OK\r\nServer: rask/0.0.1\r\nConnection: keep-alive\r\nServer-Timing: db;dur=53.2, app;dur=12.0\r\nContent-Length: 0\r\n\r\n",
+            response.serialize().as_slice()
+        );
+    }
+
+    #[test]
+    fn canonical_orders_headers_regardless_of_insertion() {
+        let head: &[u8] = b"HTTP/1.1 200 OK\r\nDate: Tue, 15 Nov 1994 08:12:31 GMT\r\nServer: rask/0.0.1\r\nContent-Type: text/plain\r\nContent-Length: 2\r\nConnection: keep-alive\r\n";
+        let headers = [
+            ("content-type", "text/plain"),
+            ("CACHE-CONTROL", "no-cache"),
+            ("date", "Tue, 15 Nov 1994 08:12:31 GMT"),
+            ("etag", "\"abc\""),
+            ("www-authenticate", "Basic"),
+        ];
+
+        let mut builder = Response::builder(Version::H1_1, Status::Ok).canonical();
+        for (name, value) in headers {
+            builder = builder.header(name, value);
+        }
+        let serialized = builder.body(b"hi".to_vec()).build().serialize();
+        assert!(serialized.starts_with(head));
+        assert_eq!(
+            b"Cache-Control: no-cache\r\nETag: \"abc\"\r\nWWW-Authenticate: Basic\r\n\r\nhi",
+            &serialized[head.len()..]
+        );
+
+        let mut builder = Response::builder(Version::H1_1, Status::Ok);
+        for (name, value) in headers.iter().rev() {
+            builder = builder.header(name, value);
+        }
+        let mut response = builder.body(b"hi".to_vec()).build();
+        response.set_canonical(true);
+        let serialized = response.serialize();
+
+        // only fields outside the fixed order follow insertion order
+        assert!(serialized.starts_with(head));
+        assert_eq!(
+            b"WWW-Authenticate: Basic\r\nETag: \"abc\"\r\nCache-Control: no-cache\r\n\r\nhi",
+            &serialized[head.len()..]
+        );
+    }
+
+    #[test]
+    fn echo_request_id_returns_provided_id() {
+        let mut request = H1Request::new();
+        request
+            .fill(&mut &b"GET / HTTP/1.1\r\nx-request-id: 7f3a-19\r\n\r\n"[..])
+            .unwrap();
+        request.parse().unwrap();
+        assert_eq!(Some(&b"7f3a-19"[..]), request.request_id());
+
+        let mut response = Response::new_with_status_line(Version::H1_1, Status::Ok);
+        assert_eq!("7f3a-19", response.echo_request_id(&request));
+        assert_eq!(
+            vec![("X-Request-ID".to_owned(), "7f3a-19".to_owned())],
+            response.headers()
+        );
+    }
+
+    #[test]
+    fn echo_request_id_generates_missing_id() {
+        let mut request = H1Request::new();
+        request.fill(&mut &b"GET / HTTP/1.1\r\n\r\n"[..]).unwrap();
+        request.parse().unwrap();
+        assert_eq!(None, request.request_id());
+
+        let mut response = Response::new_with_status_line(Version::H1_1, Status::Ok);
+        let id = response.echo_request_id(&request);
+        assert_eq!(
+            vec![("X-Request-ID".to_owned(), id.clone())],
+            response.headers()
+        );
+
+        let groups: Vec<usize> = id.split('-').map(str::len).collect();
+        assert_eq!(vec![8, 4, 4, 4, 12], groups);
+        assert!(id.chars().all(|c| c == '-' || c.is_ascii_hexdigit()));
+        assert_eq!(Some('4'), id.chars().nth(14));
+        assert!(matches!(id.chars().nth(19), Some('8' | '9' | 'a' | 'b')));
+
+        let mut other = Response::new_with_status_line(Version::H1_1, Status::Ok);
+        assert_ne!(id, other.echo_request_id(&request));
+    }
+
+    #[test]
+    fn template_matches_serialized_response() {
+        let response = Response::new_with_status_line(Version::H1_1, Status::NoContent);
+        let template = ResponseTemplate::new(&response);
+
+        assert_eq!(response.serialize(), template.as_bytes());
     }
 }
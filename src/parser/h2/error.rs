@@ -0,0 +1,89 @@
+// Copyright 2022 Ryan Seipp
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! HTTP/2 error handling
+
+use std::fmt::Display;
+
+/// Reason for terminating a stream or connection, sent in `RST_STREAM` and `GOAWAY` frames
+/// [RFC 9113 Section 7](https://www.rfc-editor.org/rfc/rfc9113#section-7)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorCode {
+    /// Graceful shutdown, not the result of an error
+    NoError = 0x0,
+    /// Unspecific protocol error
+    ProtocolError = 0x1,
+    /// Unexpected internal error
+    InternalError = 0x2,
+    /// The peer violated the flow-control protocol
+    FlowControlError = 0x3,
+    /// A `SETTINGS` frame was not acknowledged in time
+    SettingsTimeout = 0x4,
+    /// A frame was received after the stream was half-closed
+    StreamClosed = 0x5,
+    /// A frame had an invalid size
+    FrameSizeError = 0x6,
+    /// The stream was refused before any processing was done
+    RefusedStream = 0x7,
+    /// The stream is no longer needed
+    Cancel = 0x8,
+    /// The field section compression context could not be maintained
+    CompressionError = 0x9,
+    /// The connection established by a CONNECT request was reset or abnormally closed
+    ConnectError = 0xa,
+    /// The peer is exhibiting behavior that might be generating excessive load
+    EnhanceYourCalm = 0xb,
+    /// The underlying transport does not meet minimum security requirements
+    InadequateSecurity = 0xc,
+    /// HTTP/1.1 must be used instead of HTTP/2
+    Http11Required = 0xd,
+}
+
+impl ErrorCode {
+    /// Returns the wire representation of the error code
+    pub fn as_u32(&self) -> u32 {
+        *self as u32
+    }
+}
+
+/// Error detected while processing HTTP/2 frames. Its scope determines whether the peer is sent
+/// `GOAWAY` and the connection closed, or only the affected stream is reset with `RST_STREAM`.
+/// [RFC 9113 Section 5.4](https://www.rfc-editor.org/rfc/rfc9113#section-5.4)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum H2Error {
+    /// The connection can no longer be used
+    Connection(ErrorCode),
+    /// Only the stream with the given identifier is affected
+    Stream(u32, ErrorCode),
+}
+
+impl H2Error {
+    /// Returns the error code sent to the peer
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            H2Error::Connection(code) | H2Error::Stream(_, code) => *code,
+        }
+    }
+}
+
+impl Display for H2Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            H2Error::Connection(code) => write!(f, "Connection error: {:?}", code),
+            H2Error::Stream(id, code) => write!(f, "Stream {} error: {:?}", id, code),
+        }
+    }
+}
+
+impl std::error::Error for H2Error {}
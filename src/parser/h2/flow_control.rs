@@ -0,0 +1,175 @@
+// Copyright 2022 Ryan Seipp
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! HTTP/2 flow control
+//! [RFC 9113 Section 5.2](https://www.rfc-editor.org/rfc/rfc9113#section-5.2)
+
+use super::error::{ErrorCode, H2Error};
+use super::settings::MAX_WINDOW_SIZE;
+
+/// Initial size of every flow-control window, until changed by `SETTINGS_INITIAL_WINDOW_SIZE`
+/// for streams. The connection window can only be changed with `WINDOW_UPDATE` frames.
+pub const DEFAULT_WINDOW_SIZE: i32 = 65_535;
+
+/// A flow-control window, limiting the bytes of `DATA` frames that may be sent before the
+/// receiver grants more capacity with a `WINDOW_UPDATE` frame. The connection and every stream
+/// each have one window per direction.
+/// [RFC 9113 Section 6.9](https://www.rfc-editor.org/rfc/rfc9113#section-6.9)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FlowControl {
+    stream_id: u32,
+    window: i32,
+}
+
+impl FlowControl {
+    /// Creates a window of [`DEFAULT_WINDOW_SIZE`] for the stream `stream_id`, or for the whole
+    /// connection if it is 0. Errors are scoped to the stream or connection accordingly.
+    pub fn new(stream_id: u32) -> Self {
+        Self::with_window(stream_id, DEFAULT_WINDOW_SIZE)
+    }
+
+    /// Creates a window of `window` bytes, such as the `SETTINGS_INITIAL_WINDOW_SIZE` advertised
+    /// by a peer. See [`FlowControl::new`].
+    pub fn with_window(stream_id: u32, window: i32) -> Self {
+        Self { stream_id, window }
+    }
+
+    /// Returns the bytes that may currently be sent. A window may become negative when the
+    /// initial window size is reduced while data is in flight.
+    pub fn window(&self) -> i32 {
+        self.window
+    }
+
+    /// Consumes `n` bytes of the window for a `DATA` frame, including any padding. Fails with a
+    /// `FLOW_CONTROL_ERROR` if `n` exceeds the window, leaving it unchanged.
+    ///
+    /// ```rust
+    /// # use rask::parser::h2::flow_control::FlowControl;
+    /// let mut window = FlowControl::with_window(0, 10);
+    /// assert!(window.consume(10).is_ok());
+    /// assert!(window.consume(1).is_err());
+    /// ```
+    pub fn consume(&mut self, n: i32) -> Result<(), H2Error> {
+        debug_assert!(n >= 0);
+
+        if n > self.window {
+            return Err(self.error(ErrorCode::FlowControlError));
+        }
+
+        self.window -= n;
+        Ok(())
+    }
+
+    /// Grows the window by the increment of a `WINDOW_UPDATE` frame. An increment of 0 fails with
+    /// a `PROTOCOL_ERROR`, and one growing the window past 2^31-1 with a `FLOW_CONTROL_ERROR`.
+    /// The window is unchanged on failure.
+    pub fn update(&mut self, increment: u32) -> Result<(), H2Error> {
+        if increment == 0 {
+            return Err(self.error(ErrorCode::ProtocolError));
+        }
+
+        match i64::from(self.window) + i64::from(increment) {
+            window if window > i64::from(MAX_WINDOW_SIZE) => {
+                Err(self.error(ErrorCode::FlowControlError))
+            }
+            window => {
+                self.window = window as i32;
+                Ok(())
+            }
+        }
+    }
+
+    #[inline]
+    fn error(&self, code: ErrorCode) -> H2Error {
+        match self.stream_id {
+            0 => H2Error::Connection(code),
+            id => H2Error::Stream(id, code),
+        }
+    }
+}
+
+impl Default for FlowControl {
+    fn default() -> Self {
+        Self::new(0)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{FlowControl, DEFAULT_WINDOW_SIZE};
+    use crate::parser::h2::error::{ErrorCode, H2Error};
+    use crate::parser::h2::settings::MAX_WINDOW_SIZE;
+
+    #[test]
+    fn window_exhaustion() {
+        let mut window = FlowControl::new(0);
+        assert_eq!(DEFAULT_WINDOW_SIZE, window.window());
+
+        window.consume(16_384).unwrap();
+        window.consume(DEFAULT_WINDOW_SIZE - 16_384).unwrap();
+        assert_eq!(0, window.window());
+        window.consume(0).unwrap();
+
+        assert_eq!(
+            Err(H2Error::Connection(ErrorCode::FlowControlError)),
+            window.consume(1)
+        );
+        assert_eq!(0, window.window());
+    }
+
+    #[test]
+    fn window_update_restores_capacity() {
+        let mut window = FlowControl::new(3);
+        window.consume(DEFAULT_WINDOW_SIZE).unwrap();
+
+        window.update(1024).unwrap();
+        assert_eq!(1024, window.window());
+        window.consume(1024).unwrap();
+
+        window.update(MAX_WINDOW_SIZE).unwrap();
+        assert_eq!(MAX_WINDOW_SIZE as i32, window.window());
+    }
+
+    #[test]
+    fn window_update_rejects_zero_and_overflow() {
+        let mut window = FlowControl::new(3);
+        assert_eq!(
+            Err(H2Error::Stream(3, ErrorCode::ProtocolError)),
+            window.update(0)
+        );
+
+        let increment = MAX_WINDOW_SIZE - DEFAULT_WINDOW_SIZE as u32;
+        window.update(increment).unwrap();
+        assert_eq!(
+            Err(H2Error::Stream(3, ErrorCode::FlowControlError)),
+            window.update(1)
+        );
+        assert_eq!(MAX_WINDOW_SIZE as i32, window.window());
+
+        let mut window = FlowControl::default();
+        assert_eq!(
+            Err(H2Error::Connection(ErrorCode::FlowControlError)),
+            window.update(MAX_WINDOW_SIZE)
+        );
+    }
+
+    #[test]
+    fn negative_window_recovers_with_updates() {
+        let mut window = FlowControl::with_window(1, -100);
+        assert!(window.consume(1).is_err());
+
+        window.update(150).unwrap();
+        assert_eq!(50, window.window());
+    }
+}
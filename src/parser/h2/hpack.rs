@@ -0,0 +1,316 @@
+// Copyright 2022 Ryan Seipp
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! HPACK field block decoding
+//! [RFC 7541](https://www.rfc-editor.org/rfc/rfc7541)
+
+use super::error::{ErrorCode, H2Error};
+use super::huffman::huffman_decode;
+
+/// Entries of the static table, in order, starting at index 1
+/// [RFC 7541 Appendix A](https://www.rfc-editor.org/rfc/rfc7541#appendix-A)
+pub const STATIC_TABLE: [(&[u8], &[u8]); 61] = [
+    (b":authority", b""),
+    (b":method", b"GET"),
+    (b":method", b"POST"),
+    (b":path", b"/"),
+    (b":path", b"/index.html"),
+    (b":scheme", b"http"),
+    (b":scheme", b"https"),
+    (b":status", b"200"),
+    (b":status", b"204"),
+    (b":status", b"206"),
+    (b":status", b"304"),
+    (b":status", b"400"),
+    (b":status", b"404"),
+    (b":status", b"500"),
+    (b"accept-charset", b""),
+    (b"accept-encoding", b"gzip, deflate"),
+    (b"accept-language", b""),
+    (b"accept-ranges", b""),
+    (b"accept", b""),
+    (b"access-control-allow-origin", b""),
+    (b"age", b""),
+    (b"allow", b""),
+    (b"authorization", b""),
+    (b"cache-control", b""),
+    (b"content-disposition", b""),
+    (b"content-encoding", b""),
+    (b"content-language", b""),
+    (b"content-length", b""),
+    (b"content-location", b""),
+    (b"content-range", b""),
+    (b"content-type", b""),
+    (b"cookie", b""),
+    (b"date", b""),
+    (b"etag", b""),
+    (b"expect", b""),
+    (b"expires", b""),
+    (b"from", b""),
+    (b"host", b""),
+    (b"if-match", b""),
+    (b"if-modified-since", b""),
+    (b"if-none-match", b""),
+    (b"if-range", b""),
+    (b"if-unmodified-since", b""),
+    (b"last-modified", b""),
+    (b"link", b""),
+    (b"location", b""),
+    (b"max-forwards", b""),
+    (b"proxy-authenticate", b""),
+    (b"proxy-authorization", b""),
+    (b"range", b""),
+    (b"referer", b""),
+    (b"refresh", b""),
+    (b"retry-after", b""),
+    (b"server", b""),
+    (b"set-cookie", b""),
+    (b"strict-transport-security", b""),
+    (b"transfer-encoding", b""),
+    (b"user-agent", b""),
+    (b"vary", b""),
+    (b"via", b""),
+    (b"www-authenticate", b""),
+];
+
+/// Errors in a field block can't be recovered from, as the decoding context is shared by every
+/// stream of the connection. See RFC 9113 Section 4.3
+const COMPRESSION_ERROR: H2Error = H2Error::Connection(ErrorCode::CompressionError);
+
+/// Decodes a complete field block into `out`, as name and value pairs in the order received.
+///
+/// Only the static table is supported so far: fields are never added to the dynamic table, so
+/// referencing one of its entries fails with a `COMPRESSION_ERROR`.
+///
+/// ```rust
+/// # use rask::parser::h2::hpack::decode_header_block;
+/// // RFC 7541 Appendix C.2.1
+/// let block = b"\x40\x0acustom-key\x0dcustom-header";
+/// let mut out = Vec::new();
+///
+/// decode_header_block(block, &mut out).unwrap();
+/// assert_eq!(vec![(b"custom-key".to_vec(), b"custom-header".to_vec())], out);
+/// ```
+pub fn decode_header_block(buf: &[u8], out: &mut Vec<(Vec<u8>, Vec<u8>)>) -> Result<(), H2Error> {
+    let mut pos = 0;
+    while pos < buf.len() {
+        let first = buf[pos];
+        if first & 0x80 != 0 {
+            // Indexed Header Field, RFC 7541 Section 6.1
+            let (index, read) = decode_integer(&buf[pos..], 7)?;
+            pos += read;
+            let (name, value) = static_entry(index)?;
+            out.push((name.to_vec(), value.to_vec()));
+        } else if first & 0xe0 == 0x20 {
+            // Dynamic Table Size Update, RFC 7541 Section 6.3
+            let (_, read) = decode_integer(&buf[pos..], 5)?;
+            pos += read;
+        } else {
+            // Literal Header Field with Incremental Indexing (6-bit prefix), without Indexing or
+            // Never Indexed (4-bit prefix), RFC 7541 Section 6.2
+            let prefix = if first & 0x40 != 0 { 6 } else { 4 };
+            let (index, read) = decode_integer(&buf[pos..], prefix)?;
+            pos += read;
+
+            let name = if index == 0 {
+                let (name, read) = decode_string(&buf[pos..])?;
+                pos += read;
+                name
+            } else {
+                static_entry(index)?.0.to_vec()
+            };
+
+            let (value, read) = decode_string(&buf[pos..])?;
+            pos += read;
+            out.push((name, value));
+        }
+    }
+
+    Ok(())
+}
+
+/// Decodes an integer from the start of `buf`, using the low `prefix` bits of the first byte.
+/// Returns the value and the number of bytes read.
+/// [RFC 7541 Section 5.1](https://www.rfc-editor.org/rfc/rfc7541#section-5.1)
+///
+/// ```rust
+/// # use rask::parser::h2::hpack::decode_integer;
+/// // RFC 7541 Appendix C.1.2
+/// assert_eq!(Ok((1337, 3)), decode_integer(&[0x1f, 0x9a, 0x0a], 5));
+/// ```
+pub fn decode_integer(buf: &[u8], prefix: u8) -> Result<(usize, usize), H2Error> {
+    debug_assert!((1..=8).contains(&prefix));
+
+    let mask = (0xffu16 >> (8 - prefix)) as u8;
+    let first = *buf.first().ok_or(COMPRESSION_ERROR)?;
+    let mut value = (first & mask) as usize;
+    if value < mask as usize {
+        return Ok((value, 1));
+    }
+
+    // bounds the value to 28 bits beyond the prefix, far more than any valid length or index
+    for (i, &b) in buf[1..].iter().take(4).enumerate() {
+        value += ((b & 0x7f) as usize) << (7 * i);
+        if b & 0x80 == 0 {
+            return Ok((value, i + 2));
+        }
+    }
+
+    Err(COMPRESSION_ERROR)
+}
+
+/// Decodes a string literal from the start of `buf`, returning it and the number of bytes read.
+/// Huffman-encoded literals are decoded with [`huffman_decode`].
+/// [RFC 7541 Section 5.2](https://www.rfc-editor.org/rfc/rfc7541#section-5.2)
+pub fn decode_string(buf: &[u8]) -> Result<(Vec<u8>, usize), H2Error> {
+    let huffman = buf.first().ok_or(COMPRESSION_ERROR)? & 0x80 != 0;
+    let (len, read) = decode_integer(buf, 7)?;
+
+    let end = read.checked_add(len).ok_or(COMPRESSION_ERROR)?;
+    let string = buf.get(read..end).ok_or(COMPRESSION_ERROR)?;
+
+    if huffman {
+        return Ok((huffman_decode(string)?, end));
+    }
+
+    Ok((string.to_vec(), end))
+}
+
+#[inline]
+fn static_entry(index: usize) -> Result<(&'static [u8], &'static [u8]), H2Error> {
+    // index 0 is not used, and the dynamic table is not yet supported
+    index
+        .checked_sub(1)
+        .and_then(|i| STATIC_TABLE.get(i))
+        .copied()
+        .ok_or(COMPRESSION_ERROR)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{decode_header_block, decode_integer, decode_string, COMPRESSION_ERROR};
+
+    fn decode(block: &[u8]) -> Vec<(String, String)> {
+        let mut out = Vec::new();
+        decode_header_block(block, &mut out).unwrap();
+        out.into_iter()
+            .map(|(n, v)| (String::from_utf8(n).unwrap(), String::from_utf8(v).unwrap()))
+            .collect()
+    }
+
+    fn pairs(fields: &[(&str, &str)]) -> Vec<(String, String)> {
+        fields
+            .iter()
+            .map(|&(n, v)| (n.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn decodes_integer_examples() {
+        // RFC 7541 Appendix C.1
+        assert_eq!(Ok((10, 1)), decode_integer(&[0x0a], 5));
+        assert_eq!(Ok((10, 1)), decode_integer(&[0xea], 5));
+        assert_eq!(Ok((1337, 3)), decode_integer(&[0x1f, 0x9a, 0x0a], 5));
+        assert_eq!(Ok((42, 1)), decode_integer(&[0x2a], 8));
+
+        assert_eq!(Ok((31, 2)), decode_integer(&[0x1f, 0x00], 5));
+        assert_eq!(Ok((255, 2)), decode_integer(&[0xff, 0x00], 8));
+    }
+
+    #[test]
+    fn rejects_truncated_or_overlong_integer() {
+        assert_eq!(Err(COMPRESSION_ERROR), decode_integer(&[], 5));
+        assert_eq!(Err(COMPRESSION_ERROR), decode_integer(&[0x1f, 0x9a], 5));
+        assert_eq!(
+            Err(COMPRESSION_ERROR),
+            decode_integer(&[0x1f, 0xff, 0xff, 0xff, 0xff, 0x0f], 5)
+        );
+    }
+
+    #[test]
+    fn decodes_string_literal() {
+        assert_eq!(Ok((b"abc".to_vec(), 4)), decode_string(b"\x03abcdef"));
+        assert_eq!(Err(COMPRESSION_ERROR), decode_string(b"\x03ab"));
+        assert_eq!(Ok((b"abc".to_vec(), 3)), decode_string(b"\x82\x1c\x64"));
+        assert_eq!(Err(COMPRESSION_ERROR), decode_string(b"\x81\x00"));
+    }
+
+    #[test]
+    fn decodes_literal_field_examples() {
+        // RFC 7541 Appendix C.2.1, with indexing
+        assert_eq!(
+            pairs(&[("custom-key", "custom-header")]),
+            decode(b"\x40\x0acustom-key\x0dcustom-header")
+        );
+        // C.2.2, without indexing
+        assert_eq!(
+            pairs(&[(":path", "/sample/path")]),
+            decode(b"\x04\x0c/sample/path")
+        );
+        // C.2.3, never indexed
+        assert_eq!(
+            pairs(&[("password", "secret")]),
+            decode(b"\x10\x08password\x06secret")
+        );
+        // C.2.4, indexed
+        assert_eq!(pairs(&[(":method", "GET")]), decode(b"\x82"));
+    }
+
+    #[test]
+    fn decodes_request_example() {
+        // RFC 7541 Appendix C.3.1
+        assert_eq!(
+            pairs(&[
+                (":method", "GET"),
+                (":scheme", "http"),
+                (":path", "/"),
+                (":authority", "www.example.com"),
+            ]),
+            decode(b"\x82\x86\x84\x41\x0fwww.example.com")
+        );
+    }
+
+    #[test]
+    fn decodes_huffman_request_example() {
+        // RFC 7541 Appendix C.4.1
+        assert_eq!(
+            pairs(&[
+                (":method", "GET"),
+                (":scheme", "http"),
+                (":path", "/"),
+                (":authority", "www.example.com"),
+            ]),
+            decode(b"\x82\x86\x84\x41\x8c\xf1\xe3\xc2\xe5\xf2\x3a\x6b\xa0\xab\x90\xf4\xff")
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_index() {
+        let mut out = Vec::new();
+        assert_eq!(
+            Err(COMPRESSION_ERROR),
+            decode_header_block(b"\x80", &mut out)
+        );
+        // first entry of the dynamic table
+        assert_eq!(
+            Err(COMPRESSION_ERROR),
+            decode_header_block(b"\xbe", &mut out)
+        );
+        assert_eq!(
+            Err(COMPRESSION_ERROR),
+            decode_header_block(b"\x7e\x03abc", &mut out)
+        );
+        assert!(out.is_empty());
+    }
+}
@@ -0,0 +1,200 @@
+// Copyright 2022 Ryan Seipp
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! HPACK Huffman decoding
+//! [RFC 7541 Section 5.2](https://www.rfc-editor.org/rfc/rfc7541#section-5.2)
+
+use super::error::{ErrorCode, H2Error};
+
+/// Symbol terminating a Huffman-encoded string, which must never be decoded
+const EOS: u16 = 256;
+
+/// Longest code of the Huffman code, used for EOS
+const MAX_CODE_LEN: usize = 30;
+
+/// Number of codes of each length, indexed by length in bits. The Huffman code of
+/// [RFC 7541 Appendix B](https://www.rfc-editor.org/rfc/rfc7541#appendix-B) is canonical: codes
+/// of each length are consecutive, follow the codes of the previous length, and are assigned in
+/// order of symbol. The code of every symbol is therefore determined by these counts and
+/// [`SYMBOLS`].
+const LENGTH_COUNTS: [u32; MAX_CODE_LEN + 1] = [
+    0, 0, 0, 0, 0, 10, 26, 32, 6, 0, 5, 3, 2, 6, 2, 3, 0, 0, 0, 3, 8, 13, 26, 29, 12, 4, 15, 19,
+    29, 0, 4,
+];
+
+/// Symbols ordered by the length of their code, then by value
+const SYMBOLS: [u16; 257] = [
+    48, 49, 50, 97, 99, 101, 105, 111, 115, 116, 32, 37, 45, 46, 47, 51, 52, 53, 54, 55, 56, 57,
+    61, 65, 95, 98, 100, 102, 103, 104, 108, 109, 110, 112, 114, 117, 58, 66, 67, 68, 69, 70, 71,
+    72, 73, 74, 75, 76, 77, 78, 79, 80, 81, 82, 83, 84, 85, 86, 87, 89, 106, 107, 113, 118, 119,
+    120, 121, 122, 38, 42, 44, 59, 88, 90, 33, 34, 40, 41, 63, 39, 43, 124, 35, 62, 0, 36, 64, 91,
+    93, 126, 94, 125, 60, 96, 123, 92, 195, 208, 128, 130, 131, 162, 184, 194, 224, 226, 153, 161,
+    167, 172, 176, 177, 179, 209, 216, 217, 227, 229, 230, 129, 132, 133, 134, 136, 146, 154, 156,
+    160, 163, 164, 169, 170, 173, 178, 181, 185, 186, 187, 189, 190, 196, 198, 228, 232, 233, 1,
+    135, 137, 138, 139, 140, 141, 143, 147, 149, 150, 151, 152, 155, 157, 158, 165, 166, 168, 174,
+    175, 180, 182, 183, 188, 191, 197, 231, 239, 9, 142, 144, 145, 148, 159, 171, 206, 215, 225,
+    236, 237, 199, 207, 234, 235, 192, 193, 200, 201, 202, 205, 210, 213, 218, 219, 238, 240, 242,
+    243, 255, 203, 204, 211, 212, 214, 221, 222, 223, 241, 244, 245, 246, 247, 248, 250, 251, 252,
+    253, 254, 2, 3, 4, 5, 6, 7, 8, 11, 12, 14, 15, 16, 17, 18, 19, 20, 21, 23, 24, 25, 26, 27, 28,
+    29, 30, 31, 127, 220, 249, 10, 13, 22, 256,
+];
+
+/// Decodes a Huffman-encoded string literal.
+///
+/// The encoded string is padded to a whole number of bytes with the most significant bits of
+/// EOS, which are all ones. Padding longer than 7 bits, padding containing a zero bit, or a
+/// decoded EOS fail with a `COMPRESSION_ERROR`.
+///
+/// ```rust
+/// # use rask::parser::h2::huffman::huffman_decode;
+/// // RFC 7541 Appendix C.4.1
+/// let encoded = [0xf1, 0xe3, 0xc2, 0xe5, 0xf2, 0x3a, 0x6b, 0xa0, 0xab, 0x90, 0xf4, 0xff];
+/// assert_eq!(Ok(b"www.example.com".to_vec()), huffman_decode(&encoded));
+/// ```
+pub fn huffman_decode(input: &[u8]) -> Result<Vec<u8>, H2Error> {
+    const COMPRESSION_ERROR: H2Error = H2Error::Connection(ErrorCode::CompressionError);
+
+    // the shortest codes are 5 bits long
+    let mut out = Vec::with_capacity(input.len() * 8 / 5);
+
+    // bits of the code being decoded, and the first code and symbol index of their length
+    let mut code = 0u32;
+    let mut len = 0;
+    let mut first = 0u32;
+    let mut index = 0;
+
+    for &byte in input {
+        for shift in (0..8).rev() {
+            code |= u32::from((byte >> shift) & 1);
+            len += 1;
+
+            let count = LENGTH_COUNTS[len];
+            if code < first + count {
+                let symbol = SYMBOLS[index + (code - first) as usize];
+                if symbol == EOS {
+                    return Err(COMPRESSION_ERROR);
+                }
+
+                out.push(symbol as u8);
+                (code, len, first, index) = (0, 0, 0, 0);
+            } else {
+                // the code is complete, so every code of MAX_CODE_LEN bits is matched above
+                index += count as usize;
+                first = (first + count) << 1;
+                code <<= 1;
+            }
+        }
+    }
+
+    // the remaining bits, shifted left by one, must be a prefix of EOS no longer than 7 bits
+    if len > 7 || code >> 1 != (1 << len) - 1 {
+        return Err(COMPRESSION_ERROR);
+    }
+
+    Ok(out)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{huffman_decode, LENGTH_COUNTS, SYMBOLS};
+    use crate::parser::h2::error::{ErrorCode, H2Error};
+
+    const COMPRESSION_ERROR: H2Error = H2Error::Connection(ErrorCode::CompressionError);
+
+    /// Encodes `input`, assigning codes from the canonical tables
+    fn encode(input: &[u8]) -> Vec<u8> {
+        let mut codes = [(0u32, 0usize); 257];
+        let mut code = 0;
+        let mut symbols = SYMBOLS.iter();
+        for (len, &count) in LENGTH_COUNTS.iter().enumerate() {
+            for &symbol in symbols.by_ref().take(count as usize) {
+                codes[symbol as usize] = (code, len);
+                code += 1;
+            }
+            code <<= 1;
+        }
+
+        let mut bits: Vec<bool> = input
+            .iter()
+            .flat_map(|&b| {
+                let (code, len) = codes[b as usize];
+                (0..len).rev().map(move |shift| (code >> shift) & 1 == 1)
+            })
+            .collect();
+        while bits.len() % 8 != 0 {
+            bits.push(true);
+        }
+
+        bits.chunks(8)
+            .map(|byte| byte.iter().fold(0, |acc, &bit| (acc << 1) | u8::from(bit)))
+            .collect()
+    }
+
+    #[test]
+    fn decodes_rfc_examples() {
+        // RFC 7541 Appendix C.4 and C.6
+        let cases: [(&[u8], &[u8]); 7] = [
+            (
+                &[
+                    0xf1, 0xe3, 0xc2, 0xe5, 0xf2, 0x3a, 0x6b, 0xa0, 0xab, 0x90, 0xf4, 0xff,
+                ],
+                b"www.example.com",
+            ),
+            (&[0xa8, 0xeb, 0x10, 0x64, 0x9c, 0xbf], b"no-cache"),
+            (
+                &[0x25, 0xa8, 0x49, 0xe9, 0x5b, 0xa9, 0x7d, 0x7f],
+                b"custom-key",
+            ),
+            (
+                &[0x25, 0xa8, 0x49, 0xe9, 0x5b, 0xb8, 0xe8, 0xb4, 0xbf],
+                b"custom-value",
+            ),
+            (&[0x64, 0x02], b"302"),
+            (&[0xae, 0xc3, 0x77, 0x1a, 0x4b], b"private"),
+            (
+                &[
+                    0xd0, 0x7a, 0xbe, 0x94, 0x10, 0x54, 0xd4, 0x44, 0xa8, 0x20, 0x05, 0x95, 0x04,
+                    0x0b, 0x81, 0x66, 0xe0, 0x82, 0xa6, 0x2d, 0x1b, 0xff,
+                ],
+                b"Mon, 21 Oct 2013 20:13:21 GMT",
+            ),
+        ];
+
+        for (encoded, decoded) in cases {
+            assert_eq!(Ok(decoded.to_vec()), huffman_decode(encoded));
+            assert_eq!(encoded, encode(decoded));
+        }
+    }
+
+    #[test]
+    fn decodes_every_byte() {
+        let input: Vec<u8> = (0..=255).collect();
+        assert_eq!(Ok(input.clone()), huffman_decode(&encode(&input)));
+        assert_eq!(Ok(Vec::new()), huffman_decode(&[]));
+    }
+
+    #[test]
+    fn rejects_invalid_padding() {
+        // 'a' is 00011, followed by 3 bits of padding containing zeros
+        assert_eq!(Err(COMPRESSION_ERROR), huffman_decode(&[0x18]));
+        assert_eq!(Ok(b"a".to_vec()), huffman_decode(&[0x1f]));
+        // a full byte of padding
+        assert_eq!(Err(COMPRESSION_ERROR), huffman_decode(&[0x1f, 0xff]));
+        // EOS, padded with ones
+        assert_eq!(
+            Err(COMPRESSION_ERROR),
+            huffman_decode(&[0xff, 0xff, 0xff, 0xff])
+        );
+    }
+}
@@ -1,18 +1,481 @@
 //! H2 Parser
 
-/// HTTP/2 Frame
-#[derive(Debug)]
+use std::io::{self, ErrorKind, Read};
+
+use super::{ParseError, ParseResult, Status};
+use settings::{parse_settings, Settings};
+
+pub mod error;
+pub mod flow_control;
+pub mod hpack;
+pub mod huffman;
+pub mod settings;
+pub mod stream;
+
+/// Sent by the client before any frame, confirming the connection uses HTTP/2. It is chosen to
+/// be rejected by HTTP/1.1 servers, which parse it as a request using the unknown `PRI` method.
+/// [RFC 9113 Section 3.4](https://www.rfc-editor.org/rfc/rfc9113#section-3.4)
+pub const CONNECTION_PREFACE: &[u8; 24] = b"PRI * HTTP/2.0\r\n\r\nSM\r\n\r\n";
+
+/// Length in bytes of the header preceding every frame
+pub const FRAME_HEADER_LEN: usize = 9;
+
+/// Initial value of `SETTINGS_MAX_FRAME_SIZE`, the largest frame payload a peer may send until
+/// it advertises a larger size.
+/// [RFC 9113 Section 6.5.2](https://www.rfc-editor.org/rfc/rfc9113#section-6.5.2)
+pub const DEFAULT_MAX_FRAME_SIZE: u32 = 1 << 14;
+
+/// HTTP/2 Frame header
+/// [RFC 9113 Section 4.1](https://www.rfc-editor.org/rfc/rfc9113#section-4.1)
+#[derive(Debug, PartialEq, Eq)]
 pub struct Frame {
-    /// 24 bits only - default max is 2^14
-    length: u32,
-    // should swap this for enum
-    frame_type: u8,
-    // should swap this for enum
-    flags: u8,
-    // 31 bits only (should this be i32 instead with only positive values allowed?)
-    stream_id: u32,
+    /// Length of the frame payload, which follows the header. Only 24 bits are transmitted.
+    pub length: u32,
+    /// Type of the frame, determining the format of its payload
+    pub frame_type: FrameType,
+    /// Boolean flags specific to the frame type
+    pub flags: Flags,
+    /// Stream the frame belongs to, or 0 for frames applying to the whole connection. Only 31
+    /// bits are transmitted.
+    pub stream_id: u32,
+}
+
+/// Type of an HTTP/2 frame
+/// [RFC 9113 Section 6](https://www.rfc-editor.org/rfc/rfc9113#section-6)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FrameType {
+    /// RFC 9113 6.1
+    Data,
+    /// RFC 9113 6.2
+    Headers,
+    /// RFC 9113 6.3
+    Priority,
+    /// RFC 9113 6.4
+    RstStream,
+    /// RFC 9113 6.5
+    Settings,
+    /// RFC 9113 6.6
+    PushPromise,
+    /// RFC 9113 6.7
+    Ping,
+    /// RFC 9113 6.8
+    GoAway,
+    /// RFC 9113 6.9
+    WindowUpdate,
+    /// RFC 9113 6.10
+    Continuation,
+    /// A type this implementation does not know, which must be ignored rather than treated as
+    /// an error. See RFC 9113 Section 5.5.
+    Unknown(u8),
+}
+
+impl From<u8> for FrameType {
+    fn from(value: u8) -> Self {
+        match value {
+            0x0 => FrameType::Data,
+            0x1 => FrameType::Headers,
+            0x2 => FrameType::Priority,
+            0x3 => FrameType::RstStream,
+            0x4 => FrameType::Settings,
+            0x5 => FrameType::PushPromise,
+            0x6 => FrameType::Ping,
+            0x7 => FrameType::GoAway,
+            0x8 => FrameType::WindowUpdate,
+            0x9 => FrameType::Continuation,
+            other => FrameType::Unknown(other),
+        }
+    }
+}
+
+/// Flags of an HTTP/2 frame. Their meaning depends on the frame type, so several share a bit.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Flags(u8);
+
+impl Flags {
+    /// Last frame the sender will send on the stream, for `DATA` and `HEADERS` frames
+    pub const END_STREAM: Flags = Flags(0x1);
+    /// Acknowledges a `SETTINGS` or `PING` frame
+    pub const ACK: Flags = Flags(0x1);
+    /// The frame completes a field block, for `HEADERS`, `PUSH_PROMISE` and `CONTINUATION`
+    /// frames
+    pub const END_HEADERS: Flags = Flags(0x4);
+    /// The payload is preceded by a pad length and followed by padding
+    pub const PADDED: Flags = Flags(0x8);
+    /// A `HEADERS` frame carries the deprecated priority fields
+    pub const PRIORITY: Flags = Flags(0x20);
+
+    /// Creates flags from their wire representation. Undefined bits are kept, and must be
+    /// ignored by the recipient.
+    pub fn from_bits(bits: u8) -> Self {
+        Flags(bits)
+    }
+
+    /// Returns the wire representation of the flags
+    pub fn bits(&self) -> u8 {
+        self.0
+    }
+
+    /// Returns true if every flag set in `other` is also set in `self`
+    pub fn contains(&self, other: Flags) -> bool {
+        self.0 & other.0 == other.0
+    }
+}
+
+/// Parses a frame header from the start of `buf`, rejecting frames larger than
+/// [`DEFAULT_MAX_FRAME_SIZE`]. See [`parse_frame_with_max_size`].
+///
+/// ```rust
+/// # use rask::parser::Status;
+/// # use rask::parser::h2::{parse_frame, Flags, FrameType};
+/// // SETTINGS frame acknowledging the peer's settings, which has no payload
+/// let buf = [0, 0, 0, 0x4, 0x1, 0, 0, 0, 0];
+///
+/// let Ok(Status::Complete((len, frame))) = parse_frame(&buf) else { panic!() };
+/// assert_eq!(9, len);
+/// assert_eq!(FrameType::Settings, frame.frame_type);
+/// assert!(frame.flags.contains(Flags::ACK));
+/// ```
+pub fn parse_frame(buf: &[u8]) -> ParseResult<(usize, Frame)> {
+    parse_frame_with_max_size(buf, DEFAULT_MAX_FRAME_SIZE)
+}
+
+/// Parses a frame header from the start of `buf`, returning the frame and the length of the
+/// whole frame, including its payload. The result is partial until the entire payload has been
+/// received. Frames whose payload exceeds `max_frame_size`, the `SETTINGS_MAX_FRAME_SIZE`
+/// advertised to the peer, fail with [`ParseError::FrameSize`].
+///
+/// Defined by RFC 9113 Section 4.1
+/// ```text
+/// HTTP Frame {
+///   Length (24),
+///   Type (8),
+///   Flags (8),
+///   Reserved (1),
+///   Stream Identifier (31),
+///   Frame Payload (..),
+/// }
+/// ```
+pub fn parse_frame_with_max_size(buf: &[u8], max_frame_size: u32) -> ParseResult<(usize, Frame)> {
+    if buf.len() < FRAME_HEADER_LEN {
+        return Ok(Status::Partial);
+    }
+
+    let length = u32::from_be_bytes([0, buf[0], buf[1], buf[2]]);
+    if length > max_frame_size {
+        return Err(ParseError::FrameSize);
+    }
+
+    let end = FRAME_HEADER_LEN + length as usize;
+    if buf.len() < end {
+        return Ok(Status::Partial);
+    }
+
+    // the reserved bit must be ignored when received
+    let stream_id = u32::from_be_bytes([buf[5], buf[6], buf[7], buf[8]]) & 0x7fff_ffff;
+
+    Ok(Status::Complete((
+        end,
+        Frame {
+            length,
+            frame_type: FrameType::from(buf[3]),
+            flags: Flags::from_bits(buf[4]),
+            stream_id,
+        },
+    )))
+}
+
+/// Validates the client connection preface at the start of `buf`, returning its length once all
+/// of it has been received. A mismatch fails with [`ParseError::Preface`] as soon as it is
+/// received, without waiting for the rest.
+///
+/// ```rust
+/// # use rask::parser::{ParseError, Status};
+/// # use rask::parser::h2::parse_preface;
+/// assert_eq!(Ok(Status::Complete(24)), parse_preface(b"PRI * HTTP/2.0\r\n\r\nSM\r\n\r\n"));
+/// assert_eq!(Ok(Status::Partial), parse_preface(b"PRI * HTTP/2.0\r\n"));
+/// assert_eq!(Err(ParseError::Preface), parse_preface(b"GET / HTTP/1.1\r\n"));
+/// ```
+pub fn parse_preface(buf: &[u8]) -> ParseResult<usize> {
+    let len = buf.len().min(CONNECTION_PREFACE.len());
+    if buf[..len] != CONNECTION_PREFACE[..len] {
+        return Err(ParseError::Preface);
+    }
+
+    if len < CONNECTION_PREFACE.len() {
+        return Ok(Status::Partial);
+    }
+
+    Ok(Status::Complete(len))
+}
+
+/// Server side of the HTTP/2 connection preface, which must be completed before any other frame
+/// is processed. The client sends the [`CONNECTION_PREFACE`] followed by a `SETTINGS` frame.
+/// [RFC 9113 Section 3.4](https://www.rfc-editor.org/rfc/rfc9113#section-3.4)
+#[derive(Debug, Default)]
+pub struct Handshake {
+    data: Vec<u8>,
+    preface_received: bool,
+    settings: Option<Settings>,
+    upgrade_settings: Option<Settings>,
+}
+
+impl Handshake {
+    /// Creates a handshake awaiting the client connection preface
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a handshake for a connection upgraded from HTTP/1.1, where the client sends the
+    /// connection preface after receiving `101 Switching Protocols`. `settings` are those of the
+    /// upgrade request's `HTTP2-Settings` header, and `received` any bytes received after the
+    /// upgrade request.
+    /// [RFC 7540 Section 3.2](https://www.rfc-editor.org/rfc/rfc7540#section-3.2)
+    pub fn upgraded(settings: Settings, received: &[u8]) -> Self {
+        Self {
+            data: received.to_vec(),
+            upgrade_settings: Some(settings),
+            ..Self::default()
+        }
+    }
+
+    /// Reads from `reader` until it would block, buffering the bytes read. Returns 0 if the peer
+    /// closed the connection.
+    pub fn fill<R: Read>(&mut self, reader: &mut R) -> io::Result<usize> {
+        let mut total_read = 0;
+        let mut bytes = [0u8; 4096];
+        loop {
+            match reader.read(&mut bytes) {
+                Ok(0) => return Ok(0),
+                Ok(n) => {
+                    total_read += n;
+                    self.data.extend_from_slice(&bytes[..n]);
+                }
+                Err(e) => match e.kind() {
+                    ErrorKind::WouldBlock if total_read == 0 => return Err(e),
+                    ErrorKind::WouldBlock => return Ok(total_read),
+                    ErrorKind::Interrupted => {}
+                    _ => return Err(e),
+                },
+            }
+        }
+    }
+
+    /// Buffers exactly `n` bytes from `reader`
+    pub fn fill_exact<R: Read>(&mut self, reader: &mut R, n: usize) -> io::Result<()> {
+        let len = self.data.len();
+        self.data.resize(len + n, 0);
+        reader.read_exact(&mut self.data[len..])
+    }
+
+    /// Consumes the connection preface, then the client's initial `SETTINGS` frame. Returns the
+    /// number of bytes consumed once both have been received. Any other frame in place of the
+    /// `SETTINGS` frame, or invalid settings, fail with [`ParseError::Preface`].
+    pub fn parse(&mut self) -> ParseResult<usize> {
+        let mut consumed = 0;
+        if !self.preface_received {
+            match parse_preface(&self.data)? {
+                Status::Complete(n) => {
+                    self.data.drain(..n);
+                    self.preface_received = true;
+                    consumed += n;
+                }
+                Status::Partial => return Ok(Status::Partial),
+            }
+        }
+
+        if self.settings.is_some() {
+            return Ok(Status::Complete(consumed));
+        }
+
+        match parse_frame(&self.data)? {
+            Status::Complete((n, frame)) => {
+                if frame.flags.contains(Flags::ACK) {
+                    return Err(ParseError::Preface);
+                }
+
+                let settings = parse_settings(&frame, &self.data[FRAME_HEADER_LEN..n])
+                    .map_err(|_| ParseError::Preface)?;
+                self.data.drain(..n);
+                self.settings = Some(settings);
+                Ok(Status::Complete(consumed + n))
+            }
+            Status::Partial => Ok(Status::Partial),
+        }
+    }
+
+    /// Returns true once the client connection preface has been received
+    pub fn is_preface_received(&self) -> bool {
+        self.preface_received
+    }
+
+    /// Returns the settings of the client's initial `SETTINGS` frame, once it has been received
+    pub fn settings(&self) -> Option<&Settings> {
+        self.settings.as_ref()
+    }
+
+    /// Returns the settings sent in the `HTTP2-Settings` header of the request that upgraded the
+    /// connection, if it was upgraded from HTTP/1.1
+    pub fn upgrade_settings(&self) -> Option<&Settings> {
+        self.upgrade_settings.as_ref()
+    }
+
+    /// Returns the bytes received after the handshake, such as the frames that follow it
+    pub fn remaining(&self) -> &[u8] {
+        &self.data
+    }
 }
 
-// pub fn parse_frame(req: &[u8]) -> Frame {}
+#[cfg(test)]
+mod test {
+    use super::{
+        parse_frame, parse_frame_with_max_size, parse_preface, Flags, Frame, FrameType, Handshake,
+        CONNECTION_PREFACE, DEFAULT_MAX_FRAME_SIZE,
+    };
+    use crate::parser::{ParseError, Status};
+
+    fn header(length: u32, frame_type: u8, flags: u8, stream_id: u32) -> Vec<u8> {
+        let mut buf = length.to_be_bytes()[1..].to_vec();
+        buf.push(frame_type);
+        buf.push(flags);
+        buf.extend_from_slice(&stream_id.to_be_bytes());
+        buf
+    }
+
+    #[test]
+    fn parses_headers_frame_with_payload() {
+        let mut buf = header(3, 0x1, 0x5, 1);
+        buf.extend_from_slice(&[0x82, 0x86, 0x84]);
+
+        assert_eq!(
+            Ok(Status::Complete((
+                12,
+                Frame {
+                    length: 3,
+                    frame_type: FrameType::Headers,
+                    flags: Flags::from_bits(0x5),
+                    stream_id: 1,
+                }
+            ))),
+            parse_frame(&buf)
+        );
+    }
+
+    #[test]
+    fn partial_until_header_and_payload_received() {
+        let mut buf = header(4, 0x6, 0, 0);
+        buf.extend_from_slice(&[0; 4]);
+
+        for end in 0..buf.len() {
+            assert_eq!(Ok(Status::Partial), parse_frame(&buf[..end]));
+        }
+        assert!(matches!(
+            parse_frame(&buf),
+            Ok(Status::Complete((
+                13,
+                Frame {
+                    frame_type: FrameType::Ping,
+                    ..
+                }
+            )))
+        ));
+    }
 
-struct Stream {}
+    #[test]
+    fn rejects_frames_larger_than_max_size() {
+        let buf = header(DEFAULT_MAX_FRAME_SIZE + 1, 0x0, 0, 1);
+        assert_eq!(Err(ParseError::FrameSize), parse_frame(&buf));
+
+        let mut buf = header(DEFAULT_MAX_FRAME_SIZE + 1, 0x0, 0, 1);
+        buf.resize(buf.len() + DEFAULT_MAX_FRAME_SIZE as usize + 1, 0);
+        assert!(matches!(
+            parse_frame_with_max_size(&buf, DEFAULT_MAX_FRAME_SIZE * 2),
+            Ok(Status::Complete(_))
+        ));
+    }
+
+    #[test]
+    fn ignores_reserved_bit_of_stream_id() {
+        let buf = header(0, 0x3, 0, 0x8000_0007);
+
+        assert!(matches!(
+            parse_frame(&buf),
+            Ok(Status::Complete((_, Frame { stream_id: 7, .. })))
+        ));
+    }
+
+    #[test]
+    fn unknown_frame_types_are_kept() {
+        let buf = header(0, 0xfa, 0, 0);
+
+        assert!(matches!(
+            parse_frame(&buf),
+            Ok(Status::Complete((
+                _,
+                Frame {
+                    frame_type: FrameType::Unknown(0xfa),
+                    ..
+                }
+            )))
+        ));
+    }
+
+    #[test]
+    fn preface_complete_partial_and_corrupted() {
+        assert_eq!(Ok(Status::Complete(24)), parse_preface(CONNECTION_PREFACE));
+
+        for end in 0..CONNECTION_PREFACE.len() {
+            assert_eq!(
+                Ok(Status::Partial),
+                parse_preface(&CONNECTION_PREFACE[..end])
+            );
+        }
+
+        let mut corrupted = *CONNECTION_PREFACE;
+        corrupted[20] = b'X';
+        assert_eq!(Err(ParseError::Preface), parse_preface(&corrupted));
+        assert_eq!(Err(ParseError::Preface), parse_preface(&corrupted[..21]));
+    }
+
+    #[test]
+    fn handshake_consumes_preface_then_settings() {
+        let mut input = CONNECTION_PREFACE.to_vec();
+        input.extend_from_slice(&header(6, 0x4, 0, 0));
+        input.extend_from_slice(&[0x0, 0x3, 0x0, 0x0, 0x0, 0x64]);
+        input.extend_from_slice(&header(0, 0x6, 0, 0));
+
+        let mut handshake = Handshake::new();
+        handshake.fill(&mut &input[..24]).unwrap();
+        assert_eq!(Ok(Status::Partial), handshake.parse());
+        assert!(handshake.is_preface_received());
+        assert_eq!(None, handshake.settings());
+
+        handshake.fill(&mut &input[24..]).unwrap();
+        assert_eq!(Ok(Status::Complete(15)), handshake.parse());
+        assert_eq!(
+            Some(100),
+            handshake.settings().and_then(|s| s.max_concurrent_streams)
+        );
+        assert_eq!(&header(0, 0x6, 0, 0), handshake.remaining());
+    }
+
+    #[test]
+    fn handshake_rejects_frame_other_than_settings() {
+        let mut input = CONNECTION_PREFACE.to_vec();
+        input.extend_from_slice(&header(0, 0x6, 0, 0));
+
+        let mut handshake = Handshake::new();
+        handshake.fill(&mut &input[..]).unwrap();
+        assert_eq!(Err(ParseError::Preface), handshake.parse());
+    }
+
+    #[test]
+    fn flags_share_bits_across_frame_types() {
+        let flags = Flags::from_bits(0x1 | 0x4);
+
+        assert!(flags.contains(Flags::END_STREAM));
+        assert!(flags.contains(Flags::ACK));
+        assert!(flags.contains(Flags::END_HEADERS));
+        assert!(!flags.contains(Flags::PADDED));
+        assert_eq!(0x5, flags.bits());
+    }
+}
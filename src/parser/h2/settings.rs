@@ -0,0 +1,304 @@
+// Copyright 2022 Ryan Seipp
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! HTTP/2 connection settings
+
+use super::error::{ErrorCode, H2Error};
+use super::{Flags, Frame, FrameType};
+
+/// Largest value permitted for `SETTINGS_INITIAL_WINDOW_SIZE`
+pub const MAX_WINDOW_SIZE: u32 = (1 << 31) - 1;
+
+/// Largest value permitted for `SETTINGS_MAX_FRAME_SIZE`
+pub const MAX_MAX_FRAME_SIZE: u32 = (1 << 24) - 1;
+
+const HEADER_TABLE_SIZE: u16 = 0x1;
+const ENABLE_PUSH: u16 = 0x2;
+const MAX_CONCURRENT_STREAMS: u16 = 0x3;
+const INITIAL_WINDOW_SIZE: u16 = 0x4;
+const MAX_FRAME_SIZE: u16 = 0x5;
+const MAX_HEADER_LIST_SIZE: u16 = 0x6;
+
+/// Parameters carried by a `SETTINGS` frame. Parameters the peer did not send are `None`, and
+/// keep their previous value.
+/// [RFC 9113 Section 6.5.2](https://www.rfc-editor.org/rfc/rfc9113#section-6.5.2)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Settings {
+    /// Maximum size of the field compression table used to decode field blocks
+    pub header_table_size: Option<u32>,
+    /// Whether server push is permitted
+    pub enable_push: Option<bool>,
+    /// Maximum number of concurrent streams the sender permits the receiver to create
+    pub max_concurrent_streams: Option<u32>,
+    /// Initial flow-control window size for stream-level flow control
+    pub initial_window_size: Option<u32>,
+    /// Largest frame payload the sender is willing to receive
+    pub max_frame_size: Option<u32>,
+    /// Maximum size of field section the sender is prepared to accept
+    pub max_header_list_size: Option<u32>,
+}
+
+/// Parses the parameters of a `SETTINGS` frame from its payload. An acknowledgement carries no
+/// parameters, so yields empty settings. Parameters with unknown identifiers are ignored. When
+/// a parameter is repeated, the last value wins.
+///
+/// ```rust
+/// # use rask::parser::h2::{parse_frame, settings::parse_settings, error::{ErrorCode, H2Error}};
+/// # use rask::parser::Status;
+/// let buf = [0, 0, 6, 0x4, 0, 0, 0, 0, 0, 0, 0x3, 0, 0, 0, 100];
+/// let Ok(Status::Complete((len, frame))) = parse_frame(&buf) else { panic!() };
+///
+/// let settings = parse_settings(&frame, &buf[9..len]).unwrap();
+/// assert_eq!(Some(100), settings.max_concurrent_streams);
+/// assert_eq!(
+///     Err(H2Error::Connection(ErrorCode::FrameSizeError)),
+///     parse_settings(&frame, &buf[9..len - 1])
+/// );
+/// ```
+///
+/// Defined by RFC 9113 Section 6.5.1
+/// ```text
+/// Setting {
+///   Identifier (16),
+///   Value (32),
+/// }
+/// ```
+pub fn parse_settings(frame: &Frame, payload: &[u8]) -> Result<Settings, H2Error> {
+    if frame.frame_type != FrameType::Settings || frame.stream_id != 0 {
+        return Err(H2Error::Connection(ErrorCode::ProtocolError));
+    }
+
+    if payload.len() != frame.length as usize
+        || payload.len() % 6 != 0
+        || (frame.flags.contains(Flags::ACK) && !payload.is_empty())
+    {
+        return Err(H2Error::Connection(ErrorCode::FrameSizeError));
+    }
+
+    let mut settings = Settings::default();
+    for setting in payload.chunks_exact(6) {
+        let identifier = u16::from_be_bytes([setting[0], setting[1]]);
+        let value = u32::from_be_bytes([setting[2], setting[3], setting[4], setting[5]]);
+
+        match identifier {
+            HEADER_TABLE_SIZE => settings.header_table_size = Some(value),
+            ENABLE_PUSH => match value {
+                0 | 1 => settings.enable_push = Some(value == 1),
+                _ => return Err(H2Error::Connection(ErrorCode::ProtocolError)),
+            },
+            MAX_CONCURRENT_STREAMS => settings.max_concurrent_streams = Some(value),
+            INITIAL_WINDOW_SIZE if value > MAX_WINDOW_SIZE => {
+                return Err(H2Error::Connection(ErrorCode::FlowControlError))
+            }
+            INITIAL_WINDOW_SIZE => settings.initial_window_size = Some(value),
+            MAX_FRAME_SIZE
+                if !(super::DEFAULT_MAX_FRAME_SIZE..=MAX_MAX_FRAME_SIZE).contains(&value) =>
+            {
+                return Err(H2Error::Connection(ErrorCode::ProtocolError))
+            }
+            MAX_FRAME_SIZE => settings.max_frame_size = Some(value),
+            MAX_HEADER_LIST_SIZE => settings.max_header_list_size = Some(value),
+            // must be ignored. See RFC 9113 Section 6.5.2
+            _ => {}
+        }
+    }
+
+    Ok(settings)
+}
+
+/// Decodes the value of an `HTTP2-Settings` header, sent with a request to upgrade a cleartext
+/// connection to HTTP/2. It holds the payload of a `SETTINGS` frame, encoded with the URL and
+/// filename safe base64 alphabet, which is validated as if received in a frame. A value that is
+/// not valid base64url fails with a connection `PROTOCOL_ERROR`.
+/// [RFC 7540 Section 3.2.1](https://www.rfc-editor.org/rfc/rfc7540#section-3.2.1)
+///
+/// ```rust
+/// # use rask::parser::h2::settings::decode_settings_header;
+/// let settings = decode_settings_header(b"AAMAAABkAAQAAP__").unwrap();
+/// assert_eq!(Some(100), settings.max_concurrent_streams);
+/// assert_eq!(Some(65535), settings.initial_window_size);
+/// ```
+pub fn decode_settings_header(value: &[u8]) -> Result<Settings, H2Error> {
+    let payload = decode_base64url(value).ok_or(H2Error::Connection(ErrorCode::ProtocolError))?;
+    let frame = Frame {
+        length: payload.len() as u32,
+        frame_type: FrameType::Settings,
+        flags: Flags::default(),
+        stream_id: 0,
+    };
+
+    parse_settings(&frame, &payload)
+}
+
+/// Decodes base64url, from RFC 4648 Section 5. The trailing padding is optional.
+#[inline]
+fn decode_base64url(value: &[u8]) -> Option<Vec<u8>> {
+    let end = value.iter().rposition(|&b| b != b'=').map_or(0, |n| n + 1);
+    let (value, padding) = value.split_at(end);
+    if value.len() % 4 == 1 || padding.len() > 2 {
+        return None;
+    }
+
+    let mut out = Vec::with_capacity(value.len() * 3 / 4);
+    let mut bits = 0u32;
+    let mut len = 0;
+    for &b in value {
+        let sextet = match b {
+            b'A'..=b'Z' => b - b'A',
+            b'a'..=b'z' => b - b'a' + 26,
+            b'0'..=b'9' => b - b'0' + 52,
+            b'-' => 62,
+            b'_' => 63,
+            _ => return None,
+        };
+
+        bits = (bits << 6) | u32::from(sextet);
+        len += 6;
+        if len >= 8 {
+            len -= 8;
+            out.push((bits >> len) as u8);
+            bits &= (1 << len) - 1;
+        }
+    }
+
+    Some(out)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{decode_settings_header, parse_settings, Settings, MAX_WINDOW_SIZE};
+    use crate::parser::h2::error::{ErrorCode, H2Error};
+    use crate::parser::h2::{Flags, Frame, FrameType};
+
+    fn settings_frame(length: usize, flags: Flags) -> Frame {
+        Frame {
+            length: length as u32,
+            frame_type: FrameType::Settings,
+            flags,
+            stream_id: 0,
+        }
+    }
+
+    fn setting(identifier: u16, value: u32) -> Vec<u8> {
+        [&identifier.to_be_bytes()[..], &value.to_be_bytes()].concat()
+    }
+
+    #[test]
+    fn parses_valid_settings() {
+        let payload = [
+            setting(0x1, 4096),
+            setting(0x2, 0),
+            setting(0x3, 100),
+            setting(0x4, MAX_WINDOW_SIZE),
+            setting(0x5, 1 << 20),
+            setting(0x6, 8192),
+            // unknown identifiers are ignored
+            setting(0xff, 7),
+            // the last value wins
+            setting(0x3, 50),
+        ]
+        .concat();
+
+        let frame = settings_frame(payload.len(), Flags::default());
+        assert_eq!(
+            Ok(Settings {
+                header_table_size: Some(4096),
+                enable_push: Some(false),
+                max_concurrent_streams: Some(50),
+                initial_window_size: Some(MAX_WINDOW_SIZE),
+                max_frame_size: Some(1 << 20),
+                max_header_list_size: Some(8192),
+            }),
+            parse_settings(&frame, &payload)
+        );
+    }
+
+    #[test]
+    fn ack_has_empty_payload() {
+        let frame = settings_frame(0, Flags::ACK);
+        assert_eq!(Ok(Settings::default()), parse_settings(&frame, &[]));
+
+        let payload = setting(0x3, 100);
+        let frame = settings_frame(payload.len(), Flags::ACK);
+        assert_eq!(
+            Err(H2Error::Connection(ErrorCode::FrameSizeError)),
+            parse_settings(&frame, &payload)
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_settings() {
+        let cases = [
+            (
+                setting(0x4, MAX_WINDOW_SIZE + 1),
+                ErrorCode::FlowControlError,
+            ),
+            (setting(0x2, 2), ErrorCode::ProtocolError),
+            (setting(0x5, (1 << 14) - 1), ErrorCode::ProtocolError),
+            (setting(0x5, 1 << 24), ErrorCode::ProtocolError),
+            (setting(0x3, 100)[..5].to_vec(), ErrorCode::FrameSizeError),
+        ];
+
+        for (payload, code) in cases {
+            let frame = settings_frame(payload.len(), Flags::default());
+            assert_eq!(
+                Err(H2Error::Connection(code)),
+                parse_settings(&frame, &payload)
+            );
+        }
+    }
+
+    #[test]
+    fn decodes_settings_header() {
+        // SETTINGS_MAX_CONCURRENT_STREAMS = 100, SETTINGS_INITIAL_WINDOW_SIZE = 65535
+        let expected = Settings {
+            max_concurrent_streams: Some(100),
+            initial_window_size: Some(65535),
+            ..Settings::default()
+        };
+        assert_eq!(Ok(expected), decode_settings_header(b"AAMAAABkAAQAAP__"));
+        assert_eq!(Ok(Settings::default()), decode_settings_header(b""));
+
+        // SETTINGS_ENABLE_PUSH = 0, unpadded and padded
+        let expected = Settings {
+            enable_push: Some(false),
+            ..Settings::default()
+        };
+        assert_eq!(Ok(expected), decode_settings_header(b"AAIAAAAA"));
+
+        for (value, code) in [
+            (&b"AAMAAABk+/"[..], ErrorCode::ProtocolError),
+            (b"AAMAAABkA", ErrorCode::ProtocolError),
+            (b"AAMA", ErrorCode::FrameSizeError),
+            (b"AAIAAAAC", ErrorCode::ProtocolError),
+        ] {
+            assert_eq!(
+                Err(H2Error::Connection(code)),
+                decode_settings_header(value)
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_settings_on_stream() {
+        let frame = Frame {
+            stream_id: 1,
+            ..settings_frame(0, Flags::default())
+        };
+        assert_eq!(
+            Err(H2Error::Connection(ErrorCode::ProtocolError)),
+            parse_settings(&frame, &[])
+        );
+    }
+}
@@ -0,0 +1,340 @@
+// Copyright 2022 Ryan Seipp
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! HTTP/2 stream lifecycle
+//! [RFC 9113 Section 5.1](https://www.rfc-editor.org/rfc/rfc9113#section-5.1)
+
+use super::error::{ErrorCode, H2Error};
+use super::flow_control::FlowControl;
+use super::FrameType;
+
+/// State of a stream, as seen by this endpoint
+/// [RFC 9113 Section 5.1](https://www.rfc-editor.org/rfc/rfc9113#section-5.1)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum StreamState {
+    /// Not yet used by either endpoint
+    #[default]
+    Idle,
+    /// Promised by this endpoint with a `PUSH_PROMISE` frame
+    ReservedLocal,
+    /// Promised by the peer with a `PUSH_PROMISE` frame
+    ReservedRemote,
+    /// Both endpoints may send frames
+    Open,
+    /// This endpoint has finished sending
+    HalfClosedLocal,
+    /// The peer has finished sending
+    HalfClosedRemote,
+    /// Neither endpoint may send frames other than `PRIORITY`
+    Closed,
+}
+
+/// A single HTTP/2 stream, tracking its state as frames are received and sent
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Stream {
+    id: u32,
+    state: StreamState,
+    send_window: FlowControl,
+    recv_window: FlowControl,
+}
+
+impl Stream {
+    /// Creates an idle stream with the identifier `id`
+    pub fn new(id: u32) -> Self {
+        Self {
+            id,
+            state: StreamState::Idle,
+            send_window: FlowControl::new(id),
+            recv_window: FlowControl::new(id),
+        }
+    }
+
+    /// Returns the identifier of the stream
+    pub fn id(&self) -> u32 {
+        self.id
+    }
+
+    /// Returns the current state of the stream
+    pub fn state(&self) -> StreamState {
+        self.state
+    }
+
+    /// Returns the window limiting the data this endpoint may send on the stream
+    pub fn send_window(&mut self) -> &mut FlowControl {
+        &mut self.send_window
+    }
+
+    /// Returns the window limiting the data the peer may send on the stream
+    pub fn recv_window(&mut self) -> &mut FlowControl {
+        &mut self.recv_window
+    }
+
+    /// Transitions the stream for a frame received from the peer. `end_stream` is the
+    /// `END_STREAM` flag, which only applies to `DATA` and `HEADERS` frames. Frames the stream's
+    /// state does not permit fail with a connection `PROTOCOL_ERROR`, or a `STREAM_CLOSED`
+    /// stream error once the peer has finished sending. Frames of unknown types are ignored.
+    ///
+    /// ```rust
+    /// # use rask::parser::h2::{FrameType, stream::{Stream, StreamState}};
+    /// let mut stream = Stream::new(1);
+    /// stream.on_frame(FrameType::Headers, true).unwrap();
+    /// assert_eq!(StreamState::HalfClosedRemote, stream.state());
+    ///
+    /// assert!(stream.on_frame(FrameType::Data, false).is_err());
+    /// ```
+    pub fn on_frame(&mut self, frame_type: FrameType, end_stream: bool) -> Result<(), H2Error> {
+        use FrameType::*;
+        use StreamState::*;
+
+        let end_stream = end_stream && matches!(frame_type, Data | Headers);
+        self.state = match (self.state, frame_type) {
+            (_, Priority | Unknown(_)) => self.state,
+            (Idle, Headers) if end_stream => HalfClosedRemote,
+            (Idle, Headers) => Open,
+            (Idle, PushPromise) => ReservedRemote,
+            (ReservedLocal, WindowUpdate) => ReservedLocal,
+            (ReservedRemote, Headers) if end_stream => Closed,
+            (ReservedRemote, Headers) => HalfClosedLocal,
+            (
+                ReservedLocal | ReservedRemote | Open | HalfClosedLocal | HalfClosedRemote,
+                RstStream,
+            ) => Closed,
+            (Open, _) if end_stream => HalfClosedRemote,
+            (Open, _) => Open,
+            (HalfClosedLocal, _) if end_stream => Closed,
+            (HalfClosedLocal, _) => HalfClosedLocal,
+            (HalfClosedRemote, WindowUpdate) => HalfClosedRemote,
+            // frames already in flight when the stream closed may still arrive
+            (Closed, WindowUpdate | RstStream) => Closed,
+            (HalfClosedRemote | Closed, _) => {
+                return Err(self.stream_error(ErrorCode::StreamClosed))
+            }
+            (Idle | ReservedLocal | ReservedRemote, _) => {
+                return Err(H2Error::Connection(ErrorCode::ProtocolError))
+            }
+        };
+
+        Ok(())
+    }
+
+    /// Transitions the stream for a frame sent to the peer. Frames the stream's state does not
+    /// permit fail as they would if received by the peer, and leave the state unchanged.
+    pub fn on_send(&mut self, frame_type: FrameType, end_stream: bool) -> Result<(), H2Error> {
+        use FrameType::*;
+        use StreamState::*;
+
+        let end_stream = end_stream && matches!(frame_type, Data | Headers);
+        self.state = match (self.state, frame_type) {
+            (_, Priority | Unknown(_)) => self.state,
+            (Idle, Headers) if end_stream => HalfClosedLocal,
+            (Idle, Headers) => Open,
+            (Idle, PushPromise) => ReservedLocal,
+            (ReservedRemote, WindowUpdate) => ReservedRemote,
+            (ReservedLocal, Headers) if end_stream => Closed,
+            (ReservedLocal, Headers) => HalfClosedRemote,
+            (
+                ReservedLocal | ReservedRemote | Open | HalfClosedLocal | HalfClosedRemote,
+                RstStream,
+            ) => Closed,
+            (Open, _) if end_stream => HalfClosedLocal,
+            (Open, _) => Open,
+            (HalfClosedRemote, _) if end_stream => Closed,
+            (HalfClosedRemote, _) => HalfClosedRemote,
+            (HalfClosedLocal, WindowUpdate) => HalfClosedLocal,
+            (HalfClosedLocal | Closed, _) => return Err(self.stream_error(ErrorCode::StreamClosed)),
+            (Idle | ReservedLocal | ReservedRemote, _) => {
+                return Err(H2Error::Connection(ErrorCode::ProtocolError))
+            }
+        };
+
+        Ok(())
+    }
+
+    #[inline]
+    fn stream_error(&self, code: ErrorCode) -> H2Error {
+        H2Error::Stream(self.id, code)
+    }
+}
+
+/// Tracks the identifiers of streams opened by the client, which must be odd and greater than
+/// those of every stream it opened before.
+/// [RFC 9113 Section 5.1.1](https://www.rfc-editor.org/rfc/rfc9113#section-5.1.1)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ClientStreamIds {
+    last: u32,
+}
+
+impl ClientStreamIds {
+    /// Creates a tracker for a connection on which the client has opened no streams
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the identifier of the last stream opened by the client, or 0 if there is none
+    pub fn last(&self) -> u32 {
+        self.last
+    }
+
+    /// Records the client opening the stream `id`, returning it as an idle stream. Identifiers
+    /// that are even or not greater than the last fail with a connection `PROTOCOL_ERROR`.
+    /// Streams skipped over by the new identifier are implicitly closed.
+    ///
+    /// ```rust
+    /// # use rask::parser::h2::stream::ClientStreamIds;
+    /// let mut ids = ClientStreamIds::new();
+    /// assert!(ids.open(3).is_ok());
+    /// assert!(ids.open(1).is_err());
+    /// ```
+    pub fn open(&mut self, id: u32) -> Result<Stream, H2Error> {
+        if id % 2 == 0 || id <= self.last {
+            return Err(H2Error::Connection(ErrorCode::ProtocolError));
+        }
+
+        self.last = id;
+        Ok(Stream::new(id))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{ClientStreamIds, Stream, StreamState};
+    use crate::parser::h2::error::{ErrorCode, H2Error};
+    use crate::parser::h2::flow_control::DEFAULT_WINDOW_SIZE;
+    use crate::parser::h2::FrameType;
+
+    #[test]
+    fn request_response_lifecycle() {
+        let mut ids = ClientStreamIds::new();
+        let mut stream = ids.open(1).unwrap();
+        assert_eq!(StreamState::Idle, stream.state());
+
+        // request with a body
+        stream.on_frame(FrameType::Headers, false).unwrap();
+        assert_eq!(StreamState::Open, stream.state());
+        stream.on_frame(FrameType::Data, false).unwrap();
+        stream.on_frame(FrameType::Data, true).unwrap();
+        assert_eq!(StreamState::HalfClosedRemote, stream.state());
+
+        // flow control continues while the response is sent
+        stream.on_frame(FrameType::WindowUpdate, false).unwrap();
+        stream.on_send(FrameType::Headers, false).unwrap();
+        assert_eq!(StreamState::HalfClosedRemote, stream.state());
+        stream.on_send(FrameType::Data, true).unwrap();
+        assert_eq!(StreamState::Closed, stream.state());
+
+        stream.on_frame(FrameType::Priority, false).unwrap();
+        assert_eq!(StreamState::Closed, stream.state());
+    }
+
+    #[test]
+    fn server_push_lifecycle() {
+        let mut stream = Stream::new(2);
+        stream.on_send(FrameType::PushPromise, false).unwrap();
+        assert_eq!(StreamState::ReservedLocal, stream.state());
+        stream.on_send(FrameType::Headers, false).unwrap();
+        assert_eq!(StreamState::HalfClosedRemote, stream.state());
+        stream.on_send(FrameType::Data, true).unwrap();
+        assert_eq!(StreamState::Closed, stream.state());
+    }
+
+    #[test]
+    fn reset_closes_stream() {
+        let mut stream = Stream::new(1);
+        stream.on_frame(FrameType::Headers, false).unwrap();
+        stream.on_frame(FrameType::RstStream, false).unwrap();
+        assert_eq!(StreamState::Closed, stream.state());
+
+        assert_eq!(
+            Err(H2Error::Stream(1, ErrorCode::StreamClosed)),
+            stream.on_frame(FrameType::Data, false)
+        );
+    }
+
+    #[test]
+    fn rejects_illegal_frames() {
+        let cases = [
+            (StreamState::Idle, FrameType::Data, ErrorCode::ProtocolError),
+            (
+                StreamState::Idle,
+                FrameType::RstStream,
+                ErrorCode::ProtocolError,
+            ),
+            (
+                StreamState::ReservedLocal,
+                FrameType::Headers,
+                ErrorCode::ProtocolError,
+            ),
+            (
+                StreamState::ReservedRemote,
+                FrameType::Data,
+                ErrorCode::ProtocolError,
+            ),
+        ];
+
+        for (state, frame_type, code) in cases {
+            let mut stream = Stream {
+                state,
+                ..Stream::new(1)
+            };
+            assert_eq!(
+                Err(H2Error::Connection(code)),
+                stream.on_frame(frame_type, false)
+            );
+            assert_eq!(state, stream.state());
+        }
+
+        let mut stream = Stream::new(1);
+        stream.on_frame(FrameType::Headers, true).unwrap();
+        assert_eq!(
+            Err(H2Error::Stream(1, ErrorCode::StreamClosed)),
+            stream.on_frame(FrameType::Headers, false)
+        );
+        assert_eq!(StreamState::HalfClosedRemote, stream.state());
+    }
+
+    #[test]
+    fn stream_windows_scope_errors_to_stream() {
+        let mut stream = Stream::new(5);
+        stream.recv_window().consume(DEFAULT_WINDOW_SIZE).unwrap();
+
+        assert_eq!(
+            Err(H2Error::Stream(5, ErrorCode::FlowControlError)),
+            stream.recv_window().consume(1)
+        );
+        assert_eq!(DEFAULT_WINDOW_SIZE, stream.send_window().window());
+    }
+
+    #[test]
+    fn ignores_end_stream_on_other_frames() {
+        let mut stream = Stream::new(1);
+        stream.on_frame(FrameType::Headers, false).unwrap();
+        stream.on_frame(FrameType::WindowUpdate, true).unwrap();
+        assert_eq!(StreamState::Open, stream.state());
+    }
+
+    #[test]
+    fn client_stream_ids_must_be_odd_and_increasing() {
+        let mut ids = ClientStreamIds::new();
+        assert_eq!(1, ids.open(1).unwrap().id());
+        assert_eq!(7, ids.open(7).unwrap().id());
+
+        for id in [2, 7, 5, 0] {
+            assert_eq!(
+                Err(H2Error::Connection(ErrorCode::ProtocolError)),
+                ids.open(id)
+            );
+        }
+        assert_eq!(7, ids.last());
+    }
+}
@@ -1,8 +1,15 @@
 //! Representation of HTTP method
 use std::fmt::Display;
 
+use super::ParseError;
+
 /// Representation of the requested HTTP Method
 /// [IETF RFC 9110 Section 9](https://www.rfc-editor.org/rfc/rfc9110#section-9)
+///
+/// Only the methods defined by RFC 9110 are represented. Extension methods, such as `PATCH`,
+/// are valid tokens but are rejected with [`ParseError::Method`] rather than carried in an
+/// owned variant, which would require an allocation for every request. A server receiving one
+/// should respond with `501 Not Implemented`.
 #[derive(Debug, PartialEq, Eq)]
 pub enum Method {
     /// RFC 9110 9.3.1
@@ -37,3 +44,104 @@ impl Display for Method {
         })
     }
 }
+
+impl Method {
+    /// Returns true if the method is read-only, so a request has no intended effect on the
+    /// origin server.
+    /// [RFC 9110 Section 9.2.1](https://www.rfc-editor.org/rfc/rfc9110#section-9.2.1)
+    pub fn is_safe(&self) -> bool {
+        matches!(self, Self::Get | Self::Head | Self::Options | Self::Trace)
+    }
+
+    /// Returns true if repeating a request has the same intended effect as sending it once, so
+    /// it may be retried automatically after a connection failure.
+    /// [RFC 9110 Section 9.2.2](https://www.rfc-editor.org/rfc/rfc9110#section-9.2.2)
+    pub fn is_idempotent(&self) -> bool {
+        self.is_safe() || matches!(self, Self::Put | Self::Delete)
+    }
+}
+
+impl TryFrom<&[u8]> for Method {
+    type Error = ParseError;
+
+    /// Matches a method token, which is case-sensitive.
+    ///
+    /// ```rust
+    /// # use rask::parser::{Method, ParseError};
+    /// assert_eq!(Ok(Method::Get), Method::try_from(b"GET" as &[u8]));
+    /// assert_eq!(Err(ParseError::Method), Method::try_from(b"get" as &[u8]));
+    /// ```
+    fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
+        match value {
+            b"GET" => Ok(Self::Get),
+            b"HEAD" => Ok(Self::Head),
+            b"POST" => Ok(Self::Post),
+            b"PUT" => Ok(Self::Put),
+            b"DELETE" => Ok(Self::Delete),
+            b"CONNECT" => Ok(Self::Connect),
+            b"OPTIONS" => Ok(Self::Options),
+            b"TRACE" => Ok(Self::Trace),
+            _ => Err(ParseError::Method),
+        }
+    }
+}
+
+impl TryFrom<&str> for Method {
+    type Error = ParseError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        Self::try_from(value.as_bytes())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Method;
+    use crate::parser::ParseError;
+
+    #[test]
+    fn try_from_matches_standard_methods() {
+        let methods = [
+            Method::Get,
+            Method::Head,
+            Method::Post,
+            Method::Put,
+            Method::Delete,
+            Method::Connect,
+            Method::Options,
+            Method::Trace,
+        ];
+
+        for method in methods {
+            let token = method.to_string();
+            assert_eq!(Ok(&method), Method::try_from(token.as_bytes()).as_ref());
+            assert_eq!(Ok(method), Method::try_from(token.as_str()));
+        }
+    }
+
+    #[test]
+    fn safe_and_idempotent_match_rfc_tables() {
+        let cases = [
+            (Method::Get, true, true),
+            (Method::Head, true, true),
+            (Method::Post, false, false),
+            (Method::Put, false, true),
+            (Method::Delete, false, true),
+            (Method::Connect, false, false),
+            (Method::Options, true, true),
+            (Method::Trace, true, true),
+        ];
+
+        for (method, safe, idempotent) in cases {
+            assert_eq!(safe, method.is_safe(), "{}", method);
+            assert_eq!(idempotent, method.is_idempotent(), "{}", method);
+        }
+    }
+
+    #[test]
+    fn try_from_rejects_extension_methods() {
+        assert_eq!(Err(ParseError::Method), Method::try_from("PATCH"));
+        assert_eq!(Err(ParseError::Method), Method::try_from(b"" as &[u8]));
+        assert_eq!(Err(ParseError::Method), Method::try_from("GET "));
+    }
+}
@@ -21,6 +21,7 @@ pub mod h2;
 pub mod method;
 pub mod raw_request;
 pub mod status;
+pub mod uri;
 pub mod version;
 
 pub use method::Method;
@@ -37,16 +38,53 @@ pub enum ParseError {
     Method,
     /// Invalid byte in target.
     Target,
+    /// The request line exceeded its configured length limit.
+    RequestLineTooLong,
     /// Invalid HTTP version.
     Version,
+    /// Well-formed `HTTP/x.y` version that is not supported.
+    UnsupportedVersion,
     /// Invalid byte in header name.
     HeaderName,
+    /// Whitespace between a header name and its colon.
+    WhitespaceBeforeColon,
     /// Invalid byte in header value.
     HeaderValue,
     /// Invalid or missing new line.
     NewLine,
     /// Invalid whitespace
     Whitespace,
+    /// Parsing scanned more bytes than its budget allowed without completing.
+    BudgetExceeded,
+    /// Invalid Content-Length value.
+    ContentLength,
+    /// The message body is framed in a way its version does not permit, or its length cannot be
+    /// determined.
+    ConflictingFraming,
+    /// More headers were received than can be stored.
+    TooManyHeaders,
+    /// The header or trailer section exceeded its configured size limit.
+    HeadersTooLarge,
+    /// The peer closed the connection before the request was complete.
+    Incomplete,
+    /// Invalid hex digits or truncated percent-encoded octet.
+    PercentEncoding,
+    /// Header value continued onto the next line (obs-fold), which is not permitted.
+    ObsFold,
+    /// HTTP/2 frame larger than the maximum frame size advertised to the peer.
+    FrameSize,
+    /// Missing or invalid HTTP/2 client connection preface.
+    Preface,
+    /// HTTP/1.1 request without a `Host` header.
+    MissingHost,
+    /// Request with more than one `Host` header.
+    DuplicateHost,
+    /// Invalid chunk size, or chunk data not followed by a new line.
+    Chunk,
+    /// Trailer field that is not permitted after a chunked body.
+    InvalidTrailer,
+    /// The peer closed the connection before the empty line ending the header section.
+    UnterminatedHeaders,
 }
 
 impl ParseError {
@@ -54,11 +92,66 @@ impl ParseError {
         match *self {
             ParseError::Method => "Invalid token in method",
             ParseError::Target => "Invalid token in target",
+            ParseError::RequestLineTooLong => "Request line too long",
             ParseError::Version => "Invalid version",
+            ParseError::UnsupportedVersion => "Unsupported version",
             ParseError::HeaderName => "Invalid token in header name",
+            ParseError::WhitespaceBeforeColon => "Whitespace between header name and colon",
             ParseError::HeaderValue => "Invalid token in header value",
             ParseError::NewLine => "Invalid or missing new line",
             ParseError::Whitespace => "Invalid whitespace",
+            ParseError::BudgetExceeded => "Parse budget exceeded",
+            ParseError::ContentLength => "Invalid content length",
+            ParseError::ConflictingFraming => "Conflicting message framing",
+            ParseError::TooManyHeaders => "Too many headers",
+            ParseError::HeadersTooLarge => "Headers too large",
+            ParseError::Incomplete => "Incomplete request",
+            ParseError::PercentEncoding => "Invalid percent-encoding",
+            ParseError::ObsFold => "Obsolete line folding in header value",
+            ParseError::FrameSize => "Frame exceeds maximum size",
+            ParseError::Preface => "Invalid connection preface",
+            ParseError::MissingHost => "Missing Host header",
+            ParseError::DuplicateHost => "Duplicate Host header",
+            ParseError::Chunk => "Invalid chunked transfer coding",
+            ParseError::InvalidTrailer => "Disallowed trailer field",
+            ParseError::UnterminatedHeaders => "Header section not terminated by an empty line",
+        }
+    }
+
+    /// The status code of the response that should be sent to the client for this error.
+    ///
+    /// ```rust
+    /// # use rask::parser::ParseError;
+    /// assert_eq!(400, ParseError::BudgetExceeded.response_status() as u16);
+    /// ```
+    pub fn response_status(&self) -> status::Status {
+        match *self {
+            ParseError::Method
+            | ParseError::Target
+            | ParseError::Version
+            | ParseError::HeaderName
+            | ParseError::WhitespaceBeforeColon
+            | ParseError::HeaderValue
+            | ParseError::NewLine
+            | ParseError::Whitespace
+            | ParseError::BudgetExceeded
+            | ParseError::ContentLength
+            | ParseError::ConflictingFraming
+            | ParseError::TooManyHeaders
+            | ParseError::HeadersTooLarge
+            | ParseError::Incomplete
+            | ParseError::PercentEncoding
+            | ParseError::ObsFold
+            | ParseError::FrameSize
+            | ParseError::Preface
+            | ParseError::MissingHost
+            | ParseError::DuplicateHost
+            | ParseError::Chunk
+            | ParseError::InvalidTrailer
+            | ParseError::UnterminatedHeaders => status::Status::BadRequest,
+            ParseError::UnsupportedVersion => status::Status::HTTPVersionNotSupported,
+            // the method and version are bounded, so only the target can be too long
+            ParseError::RequestLineTooLong => status::Status::UriTooLong,
         }
     }
 }
@@ -71,6 +164,23 @@ impl Display for ParseError {
 
 impl std::error::Error for ParseError {}
 
+/// Decodes every percent-encoded octet (`%XX`) in `input`, leaving other bytes untouched. The
+/// decoded bytes are not validated as UTF-8. Fails with [`ParseError::PercentEncoding`] if a `%`
+/// is not followed by two hex digits.
+/// [RFC 3986 Section 2.1](https://www.rfc-editor.org/rfc/rfc3986#section-2.1)
+///
+/// ```rust
+/// # use rask::parser::percent_decode;
+/// assert_eq!(Ok(b"/a b/c".to_vec()), percent_decode(b"/a%20b%2Fc"));
+/// ```
+pub fn percent_decode(input: &[u8]) -> Result<Vec<u8>, ParseError> {
+    if !uri::is_valid_percent_encoding(input) {
+        return Err(ParseError::PercentEncoding);
+    }
+
+    Ok(uri::decode(input).into_owned())
+}
+
 /// Status of a parse operation. Determines if the operation completed, or reached the end of the
 /// buffer.
 #[derive(Debug, PartialEq, Eq)]
@@ -83,3 +193,87 @@ pub enum Status<T> {
 
 /// Result whose Err variant is `ParseError`
 pub type ParseResult<T> = std::result::Result<Status<T>, ParseError>;
+
+/// Instruction set used by the vectorized scans of the request parser
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum SimdLevel {
+    /// 256-bit AVX2 vectors, falling back to SSSE3 for the remainder of a buffer
+    Avx2,
+    /// 128-bit SSSE3 vectors
+    Ssse3,
+    /// 128-bit NEON vectors, available on every aarch64 CPU
+    Neon,
+    /// No vector instructions
+    Scalar,
+}
+
+/// Returns the widest instruction set the parser selects on the running CPU. Support for x86
+/// extensions is detected at runtime, so a binary built for a baseline target still takes the
+/// vectorized path where it runs on a capable CPU.
+///
+/// ```rust
+/// # use rask::parser::{active_simd, SimdLevel};
+/// // e.g. "parsing with Avx2"
+/// println!("parsing with {:?}", active_simd());
+/// # assert_eq!(cfg!(target_arch = "aarch64"), active_simd() == SimdLevel::Neon);
+/// ```
+pub fn active_simd() -> SimdLevel {
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    {
+        if h1::request::has_avx2() {
+            return SimdLevel::Avx2;
+        }
+
+        if h1::request::has_ssse3() {
+            return SimdLevel::Ssse3;
+        }
+    }
+
+    if cfg!(target_arch = "aarch64") {
+        SimdLevel::Neon
+    } else {
+        SimdLevel::Scalar
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{active_simd, percent_decode, ParseError, SimdLevel};
+
+    #[test]
+    fn percent_decode_decodes_escapes() {
+        assert_eq!(Ok(b"/a/b".to_vec()), percent_decode(b"/a%2Fb"));
+        assert_eq!(Ok(b"/a/b".to_vec()), percent_decode(b"/a%2fb"));
+        assert_eq!(Ok(vec![b'/', 0xff]), percent_decode(b"/%FF"));
+        assert_eq!(Ok(b"/plain+path".to_vec()), percent_decode(b"/plain+path"));
+    }
+
+    #[test]
+    fn active_simd_matches_host() {
+        let simd = active_simd();
+
+        #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+        {
+            assert_eq!(
+                std::is_x86_feature_detected!("avx2"),
+                simd == SimdLevel::Avx2
+            );
+            if simd == SimdLevel::Scalar {
+                assert!(!std::is_x86_feature_detected!("ssse3"));
+            }
+        }
+
+        #[cfg(target_arch = "aarch64")]
+        assert_eq!(SimdLevel::Neon, simd);
+
+        assert_eq!(simd, active_simd());
+    }
+
+    #[test]
+    fn percent_decode_rejects_malformed_escapes() {
+        for input in [&b"/%zz"[..], b"/%", b"/%2", b"/%2g"] {
+            assert_eq!(Err(ParseError::PercentEncoding), percent_decode(input));
+        }
+    }
+}
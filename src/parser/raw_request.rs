@@ -71,10 +71,9 @@ impl<'a> RawRequest<'a> {
     /// TODO
     #[inline]
     pub fn current(&self) -> Option<u8> {
-        return self
-            .inner
+        self.inner
             .get(if self.pos == 0 { 0 } else { self.pos - 1 })
-            .copied();
+            .copied()
     }
 
     /// to_vec
@@ -85,7 +84,41 @@ impl<'a> RawRequest<'a> {
     /// TODO
     #[inline]
     pub fn peek(&self) -> Option<u8> {
-        return self.inner.get(self.pos).copied();
+        self.inner.get(self.pos).copied()
+    }
+
+    /// Returns the current position, to later return to with [`RawRequest::reset_to`], such as
+    /// when a speculative parse fails.
+    ///
+    /// A checkpoint is only valid until the next call to [`RawRequest::slice`] or
+    /// [`RawRequest::slice_skip`], which rebase the request onto the remaining bytes and restart
+    /// positions from zero.
+    #[inline]
+    pub fn checkpoint(&self) -> usize {
+        self.pos
+    }
+
+    /// Returns to a position taken with [`RawRequest::checkpoint`], so the bytes after it are
+    /// parsed again. Positions past the end of the request are clamped to it.
+    ///
+    /// The checkpoint must have been taken since the last call to [`RawRequest::slice`] or
+    /// [`RawRequest::slice_skip`], otherwise it refers to different bytes.
+    #[inline]
+    pub fn reset_to(&mut self, pos: usize) {
+        self.pos = self.inner.len().min(pos);
+    }
+
+    /// Returns the next `n` bytes without advancing, or `None` if fewer than `n` remain.
+    #[inline]
+    pub fn peek_n(&self, n: usize) -> Option<&'a [u8]> {
+        let end = self.pos.checked_add(n)?;
+        self.inner.get(self.pos..end)
+    }
+
+    /// Returns true if the remaining bytes begin with `needle`, without advancing.
+    #[inline]
+    pub fn starts_with(&self, needle: &[u8]) -> bool {
+        self.peek_n(needle.len()) == Some(needle)
     }
 
     /// TODO
@@ -97,7 +130,7 @@ impl<'a> RawRequest<'a> {
     /// TODO
     #[inline]
     pub fn slice(&mut self) -> &'a [u8] {
-        return self.slice_skip(0).expect("slice_skip shall not fail");
+        self.slice_skip(0).expect("slice_skip shall not fail")
     }
 
     /// TODO
@@ -160,6 +193,27 @@ impl<'a> RawRequest<'a> {
             // }
         }
     }
+
+    /// Advances past every byte for which `predicate` holds, returning the consumed slice, or
+    /// `None` if the first byte does not match. The dual of [`RawRequest::take_until`], except
+    /// that reaching the end of the input returns the bytes matched so far. Named so as not to be
+    /// shadowed by [`Iterator::take_while`].
+    #[inline]
+    pub fn take_matching<F>(&mut self, mut predicate: F) -> Option<&'a [u8]>
+    where
+        F: FnMut(u8) -> bool,
+    {
+        while self.peek().is_some_and(&mut predicate) {
+            self.next();
+        }
+
+        let slice = self.slice();
+        if slice.is_empty() {
+            None
+        } else {
+            Some(slice)
+        }
+    }
 }
 
 impl<'a> Iterator for RawRequest<'a> {
@@ -258,6 +312,100 @@ mod test {
         assert_eq!(Err(Error::Skip), req.slice_skip(1));
     }
 
+    #[test]
+    fn raw_request_reset_to_reparses_from_checkpoint() {
+        let mut req = RawRequest::new(b"PRI * HTTP/2.0\r\n");
+        let checkpoint = req.checkpoint();
+
+        // speculatively parse as a GET, then fall back
+        assert_ne!(Some(&b'G'), req.next());
+        req.advance(3);
+        assert_eq!(4, req.pos());
+
+        req.reset_to(checkpoint);
+        assert_eq!(0, req.pos());
+        assert!(req.starts_with(b"PRI "));
+        assert_eq!(Some(b"PRI" as &[u8]), req.take_until(|b| b == b' '));
+
+        req.reset_to(usize::MAX);
+        assert!(req.is_empty());
+    }
+
+    #[test]
+    fn raw_request_peek_n_does_not_advance() {
+        let mut req = RawRequest::new(b"GET / HTTP/1.1");
+        req.next();
+        assert_eq!(Some(b"ET" as &[u8]), req.peek_n(2));
+        assert_eq!(Some(b"" as &[u8]), req.peek_n(0));
+        assert_eq!(Some(b"ET / HTTP/1.1" as &[u8]), req.peek_n(13));
+        assert_eq!(1, req.pos());
+    }
+
+    #[test]
+    fn raw_request_peek_n_returns_none_past_end() {
+        let mut req = RawRequest::new(b"\r\n\r");
+        assert_eq!(None, req.peek_n(4));
+        req.advance(2);
+        assert_eq!(Some(b"\r" as &[u8]), req.peek_n(1));
+        assert_eq!(None, req.peek_n(2));
+        assert_eq!(None, req.peek_n(usize::MAX));
+    }
+
+    #[test]
+    fn raw_request_starts_with_checks_remaining_bytes() {
+        let mut req = RawRequest::new(b"HTTP/1.1\r\n");
+        assert!(req.starts_with(b"HTTP/"));
+        assert!(req.starts_with(b""));
+        assert!(!req.starts_with(b"HTTP/1.1\r\n\r\n"));
+        req.advance(8);
+        assert!(req.starts_with(b"\r\n"));
+        assert!(!req.starts_with(b"\r\n\r\n"));
+    }
+
+    #[test]
+    fn raw_request_take_until_consumes_up_to_match() {
+        let mut req = RawRequest::new(b"GET / HTTP/1.1");
+        assert_eq!(Some(b"GET" as &[u8]), req.take_until(|b| b == b' '));
+        assert_eq!(Some(b' '), req.peek());
+        assert_eq!(None, req.take_until(|b| b == b' '));
+        assert_eq!(0, req.pos());
+    }
+
+    #[test]
+    fn raw_request_take_until_returns_none_without_match() {
+        let mut req = RawRequest::new(b"GET");
+        assert_eq!(None, req.take_until(|b| b == b' '));
+        assert_eq!(0, req.len());
+    }
+
+    #[test]
+    fn raw_request_take_matching_consumes_matching_elements() {
+        let mut req = RawRequest::new(b"GET / HTTP/1.1");
+        assert_eq!(
+            Some(b"GET" as &[u8]),
+            req.take_matching(|b| b.is_ascii_uppercase())
+        );
+        assert_eq!(Some(b' '), req.peek());
+        assert_eq!(0, req.pos());
+        assert_eq!(11, req.len());
+    }
+
+    #[test]
+    fn raw_request_take_matching_returns_none_when_first_element_does_not_match() {
+        let mut req = RawRequest::new(b"GET / HTTP/1.1");
+        assert_eq!(None, req.take_matching(|b| b == b' '));
+        assert_eq!(Some(b'G'), req.peek());
+        assert_eq!(14, req.len());
+    }
+
+    #[test]
+    fn raw_request_take_matching_returns_remainder_when_all_elements_match() {
+        let mut req = RawRequest::new(b"GET");
+        assert_eq!(Some(b"GET" as &[u8]), req.take_matching(|_| true));
+        assert_eq!(0, req.len());
+        assert_eq!(None, req.take_matching(|_| true));
+    }
+
     #[test]
     fn raw_request_slice_leaves_empty_slice_when_all_elements_consumed() {
         let mut req = RawRequest::new(b"GET");
@@ -98,8 +98,233 @@ pub enum Status {
     HTTPVersionNotSupported = 505,
 }
 
+impl Status {
+    /// Returns the canonical reason phrase for the status code.
+    ///
+    /// ```rust
+    /// # use rask::parser::status::Status;
+    /// assert_eq!("Not Found", Status::NotFound.reason());
+    /// ```
+    pub fn reason(&self) -> &'static str {
+        match self {
+            Self::Continue => "Continue",
+            Self::SwitchingProtocols => "Switching Protocols",
+            Self::Ok => "OK",
+            Self::Created => "Created",
+            Self::Accepted => "Accepted",
+            Self::NonAuthoritativeInformation => "Non-Authoritative Information",
+            Self::NoContent => "No Content",
+            Self::ResetContent => "Reset Content",
+            Self::PartialContent => "Partial Content",
+            Self::MultipleChoices => "Multiple Choices",
+            Self::MovedPermanently => "Moved Permanently",
+            Self::Found => "Found",
+            Self::SeeOther => "See Other",
+            Self::NotModified => "Not Modified",
+            Self::UseProxy => "Use Proxy",
+            Self::TemporaryRedirect => "Temporary Redirect",
+            Self::PermanentRedirect => "Permanent Redirect",
+            Self::BadRequest => "Bad Request",
+            Self::Unauthorized => "Unauthorized",
+            Self::PaymentRequired => "Payment Required",
+            Self::Forbidden => "Forbidden",
+            Self::NotFound => "Not Found",
+            Self::MethodNotAllowed => "Method Not Allowed",
+            Self::NotAcceptable => "Not Acceptable",
+            Self::ProxyAuthenticationRequired => "Proxy Authentication Required",
+            Self::RequestTimeout => "Request Timeout",
+            Self::Conflict => "Conflict",
+            Self::Gone => "Gone",
+            Self::LengthRequired => "Length Required",
+            Self::PreconditionFailed => "Precondition Failed",
+            Self::ContentTooLarge => "Content Too Large",
+            Self::UriTooLong => "URI Too Long",
+            Self::UnsupportedMediaType => "Unsupported Media Type",
+            Self::RangeNotSatisfiable => "Range Not Satisfiable",
+            Self::ExpectationFailed => "Expectation Failed",
+            Self::MisdirectedRequest => "Misdirected Request",
+            Self::UnprocessableContent => "Unprocessable Content",
+            Self::UpgradeRequired => "Upgrade Required",
+            Self::InternalServerError => "Internal Server Error",
+            Self::NotImplemented => "Not Implemented",
+            Self::BadGateway => "Bad Gateway",
+            Self::ServiceUnavailable => "Service Unavailable",
+            Self::GatewayTimeout => "Gateway Timeout",
+            Self::HTTPVersionNotSupported => "HTTP Version Not Supported",
+        }
+    }
+
+    /// Returns the numeric status code
+    #[inline]
+    pub fn as_u16(&self) -> u16 {
+        *self as u16
+    }
+
+    /// Returns the status for a numeric code, or `None` if the code is not known.
+    ///
+    /// ```rust
+    /// # use rask::parser::status::Status;
+    /// assert_eq!(Some(404), Status::from_u16(404).map(|status| status.as_u16()));
+    /// assert!(Status::from_u16(299).is_none());
+    /// ```
+    pub fn from_u16(code: u16) -> Option<Self> {
+        Some(match code {
+            100 => Self::Continue,
+            101 => Self::SwitchingProtocols,
+            200 => Self::Ok,
+            201 => Self::Created,
+            202 => Self::Accepted,
+            203 => Self::NonAuthoritativeInformation,
+            204 => Self::NoContent,
+            205 => Self::ResetContent,
+            206 => Self::PartialContent,
+            300 => Self::MultipleChoices,
+            301 => Self::MovedPermanently,
+            302 => Self::Found,
+            303 => Self::SeeOther,
+            304 => Self::NotModified,
+            305 => Self::UseProxy,
+            307 => Self::TemporaryRedirect,
+            308 => Self::PermanentRedirect,
+            400 => Self::BadRequest,
+            401 => Self::Unauthorized,
+            402 => Self::PaymentRequired,
+            403 => Self::Forbidden,
+            404 => Self::NotFound,
+            405 => Self::MethodNotAllowed,
+            406 => Self::NotAcceptable,
+            407 => Self::ProxyAuthenticationRequired,
+            408 => Self::RequestTimeout,
+            409 => Self::Conflict,
+            410 => Self::Gone,
+            411 => Self::LengthRequired,
+            412 => Self::PreconditionFailed,
+            413 => Self::ContentTooLarge,
+            414 => Self::UriTooLong,
+            415 => Self::UnsupportedMediaType,
+            416 => Self::RangeNotSatisfiable,
+            417 => Self::ExpectationFailed,
+            421 => Self::MisdirectedRequest,
+            422 => Self::UnprocessableContent,
+            426 => Self::UpgradeRequired,
+            500 => Self::InternalServerError,
+            501 => Self::NotImplemented,
+            502 => Self::BadGateway,
+            503 => Self::ServiceUnavailable,
+            504 => Self::GatewayTimeout,
+            505 => Self::HTTPVersionNotSupported,
+            _ => return None,
+        })
+    }
+
+    /// Returns true for 1xx informational responses
+    #[inline]
+    pub fn is_informational(&self) -> bool {
+        (100..200).contains(&self.as_u16())
+    }
+
+    /// Returns true for 2xx successful responses
+    #[inline]
+    pub fn is_success(&self) -> bool {
+        (200..300).contains(&self.as_u16())
+    }
+
+    /// Returns true for 3xx redirection responses
+    #[inline]
+    pub fn is_redirection(&self) -> bool {
+        (300..400).contains(&self.as_u16())
+    }
+
+    /// Returns true for 4xx client error responses
+    #[inline]
+    pub fn is_client_error(&self) -> bool {
+        (400..500).contains(&self.as_u16())
+    }
+
+    /// Returns true for 5xx server error responses
+    #[inline]
+    pub fn is_server_error(&self) -> bool {
+        (500..600).contains(&self.as_u16())
+    }
+}
+
 impl Display for Status {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        f.write_fmt(format_args!("{}", *self as u16))
+        f.write_fmt(format_args!("{}", self.as_u16()))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Status;
+
+    #[test]
+    fn reason_matches_registry() {
+        let cases = [
+            (Status::Continue, 100, "Continue"),
+            (Status::Ok, 200, "OK"),
+            (
+                Status::NonAuthoritativeInformation,
+                203,
+                "Non-Authoritative Information",
+            ),
+            (Status::NotFound, 404, "Not Found"),
+            (Status::ContentTooLarge, 413, "Content Too Large"),
+            (Status::UriTooLong, 414, "URI Too Long"),
+            (
+                Status::HTTPVersionNotSupported,
+                505,
+                "HTTP Version Not Supported",
+            ),
+        ];
+
+        for (status, code, reason) in cases {
+            assert_eq!(code, status.as_u16());
+            assert_eq!(reason, status.reason());
+        }
+    }
+
+    #[test]
+    fn from_u16_round_trips_known_codes() {
+        for code in 0..1000 {
+            if let Some(status) = Status::from_u16(code) {
+                assert_eq!(code, status.as_u16());
+            }
+        }
+
+        assert!(matches!(Status::from_u16(200), Some(Status::Ok)));
+        assert!(Status::from_u16(306).is_none());
+        assert!(Status::from_u16(600).is_none());
+    }
+
+    #[test]
+    fn classification_follows_status_class() {
+        let cases = [
+            (
+                Status::SwitchingProtocols,
+                [true, false, false, false, false],
+            ),
+            (Status::NoContent, [false, true, false, false, false]),
+            (
+                Status::PermanentRedirect,
+                [false, false, true, false, false],
+            ),
+            (
+                Status::ExpectationFailed,
+                [false, false, false, true, false],
+            ),
+            (Status::GatewayTimeout, [false, false, false, false, true]),
+        ];
+
+        for (status, expected) in cases {
+            let classes = [
+                status.is_informational(),
+                status.is_success(),
+                status.is_redirection(),
+                status.is_client_error(),
+                status.is_server_error(),
+            ];
+            assert_eq!(expected, classes, "{}", status);
+        }
     }
 }
@@ -0,0 +1,470 @@
+// Copyright 2022 Ryan Seipp
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Structured request target
+//! [RFC 3986](https://www.rfc-editor.org/rfc/rfc3986)
+
+use std::borrow::Cow;
+
+use super::ParseError;
+
+/// Structured view over a request target, borrowing from the request buffer.
+///
+/// Supports each request-target form defined by RFC 9112 Section 3.2. Origin-form and
+/// asterisk-form targets have no scheme or authority, and authority-form targets have an empty
+/// path.
+///
+/// ```rust
+/// # use rask::parser::uri::Uri;
+/// let uri = Uri::parse(b"http://www.example.org/api/forecast?days=16").unwrap();
+/// assert_eq!(Some(b"http" as &[u8]), uri.scheme());
+/// assert_eq!(Some(b"www.example.org" as &[u8]), uri.authority());
+/// assert_eq!(b"/api/forecast", uri.path());
+/// assert_eq!(Some(b"days=16" as &[u8]), uri.query());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Uri<'a> {
+    scheme: Option<&'a [u8]>,
+    authority: Option<&'a [u8]>,
+    path: &'a [u8],
+    query: Option<&'a [u8]>,
+}
+
+impl<'a> Uri<'a> {
+    /// Parses a request target into its components. Fails with [`ParseError::Target`] if the
+    /// target is empty, or contains a malformed percent-encoded octet.
+    pub fn parse(target: &'a [u8]) -> Result<Self, ParseError> {
+        if target.is_empty() {
+            return Err(ParseError::Target);
+        }
+
+        let (scheme, rest) = match split_scheme(target) {
+            Some((scheme, rest)) => (Some(scheme), rest),
+            None => (None, target),
+        };
+
+        let (authority, rest) = if let Some(rest) = rest.strip_prefix(b"//") {
+            let end = rest
+                .iter()
+                .position(|&b| b == b'/' || b == b'?')
+                .unwrap_or(rest.len());
+            (Some(&rest[..end]), &rest[end..])
+        } else if scheme.is_none() && rest[0] != b'/' && rest != b"*" {
+            // authority-form: the entire target is the authority
+            (Some(rest), &rest[rest.len()..])
+        } else {
+            (None, rest)
+        };
+
+        let (path, query) = match rest.iter().position(|&b| b == b'?') {
+            Some(n) => (&rest[..n], Some(&rest[n + 1..])),
+            None => (rest, None),
+        };
+
+        if !is_valid_percent_encoding(path) || !query.map_or(true, is_valid_percent_encoding) {
+            return Err(ParseError::Target);
+        }
+
+        Ok(Self {
+            scheme,
+            authority,
+            path,
+            query,
+        })
+    }
+
+    /// Returns the scheme of an absolute-form target
+    #[inline]
+    pub fn scheme(&self) -> Option<&'a [u8]> {
+        self.scheme
+    }
+
+    /// Returns the authority of an absolute-form or authority-form target
+    #[inline]
+    pub fn authority(&self) -> Option<&'a [u8]> {
+        self.authority
+    }
+
+    /// Returns the raw, percent-encoded path
+    #[inline]
+    pub fn path(&self) -> &'a [u8] {
+        self.path
+    }
+
+    /// Returns the raw, percent-encoded query, excluding the leading `?`
+    #[inline]
+    pub fn query(&self) -> Option<&'a [u8]> {
+        self.query
+    }
+
+    /// Returns an iterator over the `/` separated segments of the path, with percent-encoded
+    /// octets decoded. The leading `/` does not produce an empty segment, but a trailing one
+    /// does.
+    ///
+    /// ```rust
+    /// # use rask::parser::uri::Uri;
+    /// let uri = Uri::parse(b"/files/hello%20world/").unwrap();
+    /// let segments: Vec<_> = uri.segments().collect();
+    /// assert_eq!(segments, [b"files" as &[u8], b"hello world", b""]);
+    /// ```
+    #[inline]
+    pub fn segments(&self) -> Segments<'a> {
+        let path = self.path.strip_prefix(b"/").unwrap_or(self.path);
+        Segments {
+            remaining: if path.is_empty() { None } else { Some(path) },
+        }
+    }
+}
+
+impl Uri<'_> {
+    /// Returns the path with `.` and `..` segments resolved, as described by the
+    /// remove_dot_segments algorithm of
+    /// [RFC 3986 Section 5.2.4](https://www.rfc-editor.org/rfc/rfc3986#section-5.2.4).
+    ///
+    /// Percent-encoded dots (`%2e`) are decoded before resolving, so encoded traversal is resolved
+    /// as well. Attempts to traverse above the root are clamped to the root, so the result never
+    /// escapes it. The path is borrowed when no segments needed resolving.
+    ///
+    /// Paths containing a percent-encoded `/` (`%2f`) or `\` (`%5c`) fail with
+    /// [`ParseError::Target`]. They are not segment separators here, but become ones once the
+    /// path is decoded, such as by a file server mapping it onto the filesystem, where
+    /// `/a/..%2f..%2fetc/passwd` would escape the root.
+    ///
+    /// ```rust
+    /// # use rask::parser::{uri::Uri, ParseError};
+    /// let uri = Uri::parse(b"/a/../b/./c").unwrap();
+    /// assert_eq!(b"/b/c" as &[u8], &*uri.normalized_path().unwrap());
+    ///
+    /// let uri = Uri::parse(b"/a/..%2f..%2fetc/passwd").unwrap();
+    /// assert_eq!(Err(ParseError::Target), uri.normalized_path());
+    /// ```
+    pub fn normalized_path(&self) -> Result<Cow<'_, [u8]>, ParseError> {
+        if contains_encoded_separator(self.path) {
+            return Err(ParseError::Target);
+        }
+        if !self.path.contains(&b'.') && !contains_encoded_dot(self.path) {
+            return Ok(Cow::Borrowed(self.path));
+        }
+
+        let decoded = decode_dots(self.path);
+        let mut input: &[u8] = &decoded;
+        let mut output = Vec::with_capacity(input.len());
+
+        while !input.is_empty() {
+            if let Some(rest) = input
+                .strip_prefix(b"../")
+                .or_else(|| input.strip_prefix(b"./"))
+            {
+                input = rest;
+            } else if input.starts_with(b"/./") {
+                input = &input[2..];
+            } else if input == b"/." {
+                input = b"/";
+            } else if input.starts_with(b"/../") {
+                input = &input[3..];
+                pop_segment(&mut output);
+            } else if input == b"/.." {
+                input = b"/";
+                pop_segment(&mut output);
+            } else if input == b"." || input == b".." {
+                input = &[];
+            } else {
+                let end = input[1..]
+                    .iter()
+                    .position(|&b| b == b'/')
+                    .map_or(input.len(), |n| n + 1);
+                output.extend_from_slice(&input[..end]);
+                input = &input[end..];
+            }
+        }
+
+        if output == self.path {
+            Ok(Cow::Borrowed(self.path))
+        } else {
+            Ok(Cow::Owned(output))
+        }
+    }
+}
+
+/// Removes the last segment, and its preceding `/`, from `output`.
+#[inline]
+fn pop_segment(output: &mut Vec<u8>) {
+    let start = output.iter().rposition(|&b| b == b'/').unwrap_or(0);
+    output.truncate(start);
+}
+
+/// Returns true if `buf` contains a percent-encoded `/` or `\`
+#[inline]
+fn contains_encoded_separator(buf: &[u8]) -> bool {
+    buf.windows(3).any(|w| {
+        w[0] == b'%' && (w[1..].eq_ignore_ascii_case(b"2f") || w[1..].eq_ignore_ascii_case(b"5c"))
+    })
+}
+
+#[inline]
+fn contains_encoded_dot(buf: &[u8]) -> bool {
+    buf.windows(3)
+        .any(|w| w[0] == b'%' && w[1] == b'2' && (w[2] == b'e' || w[2] == b'E'))
+}
+
+/// Decodes only percent-encoded dots, leaving other octets encoded so that an encoded `/` is not
+/// mistaken for a segment separator.
+#[inline]
+fn decode_dots(buf: &[u8]) -> Cow<'_, [u8]> {
+    if !contains_encoded_dot(buf) {
+        return Cow::Borrowed(buf);
+    }
+
+    let mut decoded = Vec::with_capacity(buf.len());
+    let mut pos = 0;
+    while pos < buf.len() {
+        if contains_encoded_dot(&buf[pos..(pos + 3).min(buf.len())]) {
+            decoded.push(b'.');
+            pos += 3;
+        } else {
+            decoded.push(buf[pos]);
+            pos += 1;
+        }
+    }
+
+    Cow::Owned(decoded)
+}
+
+/// Iterator over the decoded segments of a [`Uri`] path
+#[derive(Debug, Clone)]
+pub struct Segments<'a> {
+    remaining: Option<&'a [u8]>,
+}
+
+impl<'a> Iterator for Segments<'a> {
+    type Item = Cow<'a, [u8]>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let remaining = self.remaining?;
+        let segment = match remaining.iter().position(|&b| b == b'/') {
+            Some(n) => {
+                self.remaining = Some(&remaining[n + 1..]);
+                &remaining[..n]
+            }
+            None => {
+                self.remaining = None;
+                remaining
+            }
+        };
+
+        Some(decode(segment))
+    }
+}
+
+/// Splits `scheme ":"` from the front of an absolute-URI.
+/// ```abnf
+/// scheme = ALPHA *( ALPHA / DIGIT / "+" / "-" / "." )
+/// ```
+#[inline]
+fn split_scheme(target: &[u8]) -> Option<(&[u8], &[u8])> {
+    if !target[0].is_ascii_alphabetic() {
+        return None;
+    }
+
+    let end = target
+        .iter()
+        .position(|&b| !(b.is_ascii_alphanumeric() || b == b'+' || b == b'-' || b == b'.'))?;
+
+    if target[end] == b':' && target[end + 1..].starts_with(b"//") {
+        Some((&target[..end], &target[end + 1..]))
+    } else {
+        None
+    }
+}
+
+#[inline]
+pub(crate) fn is_valid_percent_encoding(buf: &[u8]) -> bool {
+    let mut pos = 0;
+    while let Some(n) = buf[pos..].iter().position(|&b| b == b'%') {
+        pos += n;
+        match buf.get(pos + 1..pos + 3) {
+            Some([hi, lo]) if hi.is_ascii_hexdigit() && lo.is_ascii_hexdigit() => pos += 3,
+            _ => return false,
+        }
+    }
+
+    true
+}
+
+/// Decodes an already validated percent-encoded slice.
+#[inline]
+pub(crate) fn decode(buf: &[u8]) -> Cow<'_, [u8]> {
+    if !buf.contains(&b'%') {
+        return Cow::Borrowed(buf);
+    }
+
+    let mut decoded = Vec::with_capacity(buf.len());
+    let mut iter = buf.iter();
+    while let Some(&b) = iter.next() {
+        if b == b'%' {
+            let hi = hex_value(*iter.next().unwrap_or(&b'0'));
+            let lo = hex_value(*iter.next().unwrap_or(&b'0'));
+            decoded.push(hi << 4 | lo);
+        } else {
+            decoded.push(b);
+        }
+    }
+
+    Cow::Owned(decoded)
+}
+
+#[inline]
+fn hex_value(b: u8) -> u8 {
+    match b {
+        b'0'..=b'9' => b - b'0',
+        b'a'..=b'f' => b - b'a' + 10,
+        b'A'..=b'F' => b - b'A' + 10,
+        _ => 0,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::borrow::Cow;
+
+    use super::Uri;
+    use crate::parser::ParseError;
+
+    #[test]
+    fn origin_form_segments() {
+        let uri = Uri::parse(b"/api/v1.0/weather/forecast").unwrap();
+        let segments: Vec<Cow<'_, [u8]>> = uri.segments().collect();
+
+        assert_eq!(None, uri.scheme());
+        assert_eq!(None, uri.authority());
+        assert_eq!(None, uri.query());
+        assert_eq!(
+            segments,
+            [b"api" as &[u8], b"v1.0", b"weather", b"forecast"]
+        );
+        assert!(segments.iter().all(|s| matches!(s, Cow::Borrowed(_))));
+    }
+
+    #[test]
+    fn origin_form_splits_query() {
+        let uri = Uri::parse(b"/log?format=json&hasfast=true").unwrap();
+
+        assert_eq!(b"/log", uri.path());
+        assert_eq!(Some(b"format=json&hasfast=true" as &[u8]), uri.query());
+    }
+
+    #[test]
+    fn root_path_has_no_segments() {
+        let uri = Uri::parse(b"/").unwrap();
+
+        assert_eq!(0, uri.segments().count());
+    }
+
+    #[test]
+    fn segments_are_percent_decoded() {
+        let uri = Uri::parse(b"/a%2Fb/%41%62c").unwrap();
+        let segments: Vec<Cow<'_, [u8]>> = uri.segments().collect();
+
+        assert_eq!(segments, [b"a/b" as &[u8], b"Abc"]);
+    }
+
+    #[test]
+    fn absolute_form_without_path() {
+        let uri = Uri::parse(b"https://example.org:8443?x=1").unwrap();
+
+        assert_eq!(Some(b"https" as &[u8]), uri.scheme());
+        assert_eq!(Some(b"example.org:8443" as &[u8]), uri.authority());
+        assert_eq!(b"", uri.path());
+        assert_eq!(Some(b"x=1" as &[u8]), uri.query());
+    }
+
+    #[test]
+    fn authority_form() {
+        let uri = Uri::parse(b"www.example.org:443").unwrap();
+
+        assert_eq!(None, uri.scheme());
+        assert_eq!(Some(b"www.example.org:443" as &[u8]), uri.authority());
+        assert_eq!(b"", uri.path());
+        assert_eq!(0, uri.segments().count());
+    }
+
+    #[test]
+    fn asterisk_form() {
+        let uri = Uri::parse(b"*").unwrap();
+
+        assert_eq!(None, uri.authority());
+        assert_eq!(b"*", uri.path());
+    }
+
+    #[test]
+    fn normalized_path_removes_dot_segments() {
+        let cases: [(&[u8], &[u8]); 8] = [
+            (b"/a/../b/./c", b"/b/c"),
+            (b"/a/b/c/./../../g", b"/a/g"),
+            (b"/a/b/..", b"/a/"),
+            (b"/a/./", b"/a/"),
+            (b"/.", b"/"),
+            (b"/..", b"/"),
+            (b"/mid/content=5/../6", b"/mid/6"),
+            (b"/a/.b/..c/", b"/a/.b/..c/"),
+        ];
+
+        for (path, expected) in cases {
+            let uri = Uri::parse(path).unwrap();
+            assert_eq!(expected, &*uri.normalized_path().unwrap());
+        }
+    }
+
+    #[test]
+    fn normalized_path_clamps_traversal_at_root() {
+        let cases: [(&[u8], &[u8]); 4] = [
+            (b"/../../etc/passwd", b"/etc/passwd"),
+            (b"/a/../../../etc/passwd", b"/etc/passwd"),
+            (b"/%2e%2e/%2E%2e/etc/passwd", b"/etc/passwd"),
+            (b"/static/%2e%2E/%2e%2e/etc/passwd", b"/etc/passwd"),
+        ];
+
+        for (path, expected) in cases {
+            let uri = Uri::parse(path).unwrap();
+            assert_eq!(expected, &*uri.normalized_path().unwrap());
+        }
+    }
+
+    #[test]
+    fn normalized_path_rejects_encoded_separators() {
+        for path in [
+            &b"/a/..%2f..%2f..%2fetc/passwd"[..],
+            b"/a/..%2F..%2Fetc/passwd",
+            b"/a/..%5c..%5Cetc/passwd",
+            b"/a%2fb",
+        ] {
+            let uri = Uri::parse(path).unwrap();
+            assert_eq!(Err(ParseError::Target), uri.normalized_path());
+        }
+    }
+
+    #[test]
+    fn normalized_path_borrows_when_unchanged() {
+        let uri = Uri::parse(b"/api/v1.0/weather/forecast").unwrap();
+
+        assert!(matches!(uri.normalized_path(), Ok(Cow::Borrowed(_))));
+    }
+
+    #[test]
+    fn rejects_empty_and_malformed_targets() {
+        assert_eq!(Err(ParseError::Target), Uri::parse(b""));
+        assert_eq!(Err(ParseError::Target), Uri::parse(b"/a%2"));
+        assert_eq!(Err(ParseError::Target), Uri::parse(b"/a?b=%zz"));
+    }
+}
@@ -0,0 +1,136 @@
+// Copyright 2022 Ryan Seipp
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Small, fast pseudorandom number generator for IDs and nonces.
+//!
+//! The generator is **not** cryptographically secure. Its output is predictable to anyone who
+//! observes enough of it, so it must not be used for keys, session tokens, or anything else an
+//! attacker benefits from guessing.
+
+use std::cell::Cell;
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// xorshift64* generator.
+/// [An experimental exploration of Marsaglia's xorshift generators, scrambled](https://arxiv.org/abs/1402.6246)
+#[derive(Debug, Clone)]
+pub(crate) struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    /// Creates a generator seeded from the process' randomly keyed hasher state and the current
+    /// time, so that every generator produces a different sequence.
+    pub(crate) fn new() -> Self {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_nanos() as u64);
+
+        let mut hasher = RandomState::new().build_hasher();
+        hasher.write_u64(nanos);
+        Self::with_seed(hasher.finish())
+    }
+
+    /// Creates a generator which always produces the same sequence for the same `seed`.
+    pub(crate) fn with_seed(seed: u64) -> Self {
+        // xorshift never leaves the all-zero state, so scramble the seed with a SplitMix64 step.
+        // The step is a bijection, so exactly one seed maps to zero and is replaced
+        let mut z = seed.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^= z >> 31;
+
+        Self {
+            state: if z == 0 { 0x9e37_79b9_7f4a_7c15 } else { z },
+        }
+    }
+
+    /// Returns the next number in the sequence
+    #[inline]
+    pub(crate) fn next_u64(&mut self) -> u64 {
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+        self.state.wrapping_mul(0x2545_f491_4f6c_dd1d)
+    }
+
+    /// Fills `dest` with random bytes
+    pub(crate) fn fill_bytes(&mut self, dest: &mut [u8]) {
+        let mut chunks = dest.chunks_exact_mut(8);
+        for chunk in &mut chunks {
+            chunk.copy_from_slice(&self.next_u64().to_le_bytes());
+        }
+
+        let remainder = chunks.into_remainder();
+        let len = remainder.len();
+        remainder.copy_from_slice(&self.next_u64().to_le_bytes()[..len]);
+    }
+}
+
+thread_local! {
+    static THREAD_RNG: Cell<Option<Rng>> = const { Cell::new(None) };
+}
+
+/// Returns the next number from a generator local to the current thread, seeded on first use.
+pub(crate) fn next_u64() -> u64 {
+    THREAD_RNG.with(|cell| {
+        let mut rng = cell.take().unwrap_or_else(Rng::new);
+        let n = rng.next_u64();
+        cell.set(Some(rng));
+        n
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::Rng;
+
+    #[test]
+    fn same_seed_reproduces_sequence() {
+        let mut a = Rng::with_seed(42);
+        let mut b = Rng::with_seed(42);
+        for _ in 0..64 {
+            assert_eq!(a.next_u64(), b.next_u64());
+        }
+
+        let mut a_bytes = [0; 21];
+        let mut b_bytes = [0; 21];
+        a.fill_bytes(&mut a_bytes);
+        b.fill_bytes(&mut b_bytes);
+        assert_eq!(a_bytes, b_bytes);
+        assert_ne!([0; 21], a_bytes);
+    }
+
+    #[test]
+    fn different_seeds_diverge() {
+        let mut a = Rng::with_seed(0);
+        let mut b = Rng::with_seed(1);
+
+        assert_ne!(a.next_u64(), b.next_u64());
+        assert_ne!(0, Rng::with_seed(0).state);
+    }
+
+    #[test]
+    fn fill_bytes_matches_next_u64() {
+        let mut expected = Rng::with_seed(7);
+        let first = expected.next_u64().to_le_bytes();
+        let second = expected.next_u64().to_le_bytes();
+
+        let mut bytes = [0; 11];
+        Rng::with_seed(7).fill_bytes(&mut bytes);
+        assert_eq!(first, bytes[..8]);
+        assert_eq!(second[..3], bytes[8..]);
+    }
+}
@@ -0,0 +1,176 @@
+// Copyright 2022 Ryan Seipp
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Ordering of responses to pipelined requests
+
+use std::collections::BTreeMap;
+
+/// Releases the responses to pipelined requests in the order the requests were received. HTTP/1.1
+/// has no other way to match a response to its request, so when requests are handled
+/// concurrently, such as by different workers, a response completed early must wait for those
+/// to every earlier request.
+/// [RFC 9112 Section 9.3.2](https://www.rfc-editor.org/rfc/rfc9112#section-9.3.2)
+///
+/// ```rust
+/// # use rask::sequencer::ResponseSequencer;
+/// let mut sequencer = ResponseSequencer::new();
+/// let first = sequencer.next_sequence();
+/// let second = sequencer.next_sequence();
+///
+/// sequencer.complete(second, "second");
+/// assert_eq!(None, sequencer.pop_ready());
+///
+/// sequencer.complete(first, "first");
+/// assert_eq!(Some("first"), sequencer.pop_ready());
+/// assert_eq!(Some("second"), sequencer.pop_ready());
+/// ```
+#[derive(Debug)]
+pub struct ResponseSequencer<T> {
+    next_sequence: u64,
+    next_ready: u64,
+    completed: BTreeMap<u64, T>,
+}
+
+impl<T> ResponseSequencer<T> {
+    /// Creates a sequencer with no requests in flight
+    pub fn new() -> Self {
+        Self {
+            next_sequence: 0,
+            next_ready: 0,
+            completed: BTreeMap::new(),
+        }
+    }
+
+    /// Assigns the sequence number of the next request received
+    pub fn next_sequence(&mut self) -> u64 {
+        let sequence = self.next_sequence;
+        self.next_sequence += 1;
+        sequence
+    }
+
+    /// Records the response to the request numbered `sequence`. It is held until the responses
+    /// to every earlier request have been released.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `sequence` was never assigned, or its response has already been recorded.
+    pub fn complete(&mut self, sequence: u64, response: T) {
+        assert!(
+            (self.next_ready..self.next_sequence).contains(&sequence),
+            "response recorded for unassigned or released sequence {}",
+            sequence
+        );
+
+        let previous = self.completed.insert(sequence, response);
+        assert!(
+            previous.is_none(),
+            "response recorded twice for sequence {}",
+            sequence
+        );
+    }
+
+    /// Releases the response to the earliest request that has not been released, if it has
+    /// been completed.
+    pub fn pop_ready(&mut self) -> Option<T> {
+        let response = self.completed.remove(&self.next_ready)?;
+        self.next_ready += 1;
+        Some(response)
+    }
+
+    /// Returns the earliest request whose response has not been recorded, or `None` if every
+    /// request in flight has one.
+    pub fn next_unanswered(&self) -> Option<u64> {
+        (self.next_ready..self.next_sequence)
+            .find(|sequence| !self.completed.contains_key(sequence))
+    }
+
+    /// Returns the number of requests whose responses have not been released
+    pub fn in_flight(&self) -> u64 {
+        self.next_sequence - self.next_ready
+    }
+}
+
+impl<T> Default for ResponseSequencer<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::ResponseSequencer;
+
+    #[test]
+    fn releases_in_request_order() {
+        let mut sequencer = ResponseSequencer::new();
+        let sequences: Vec<u64> = (0..4).map(|_| sequencer.next_sequence()).collect();
+
+        let mut released = Vec::new();
+        for sequence in [2, 0, 3, 1] {
+            sequencer.complete(sequences[sequence], sequence);
+            while let Some(response) = sequencer.pop_ready() {
+                released.push(response);
+            }
+        }
+
+        assert_eq!(vec![0, 1, 2, 3], released);
+        assert_eq!(0, sequencer.in_flight());
+    }
+
+    #[test]
+    fn counts_requests_in_flight() {
+        let mut sequencer = ResponseSequencer::new();
+        let first = sequencer.next_sequence();
+        sequencer.next_sequence();
+        assert_eq!(2, sequencer.in_flight());
+
+        sequencer.complete(first, ());
+        assert_eq!(2, sequencer.in_flight());
+
+        sequencer.pop_ready();
+        assert_eq!(1, sequencer.in_flight());
+    }
+
+    #[test]
+    fn finds_earliest_unanswered_request() {
+        let mut sequencer = ResponseSequencer::new();
+        assert_eq!(None, sequencer.next_unanswered());
+
+        let first = sequencer.next_sequence();
+        let second = sequencer.next_sequence();
+        sequencer.complete(first, ());
+        assert_eq!(Some(second), sequencer.next_unanswered());
+
+        sequencer.complete(second, ());
+        assert_eq!(None, sequencer.next_unanswered());
+    }
+
+    #[test]
+    #[should_panic(expected = "recorded twice")]
+    fn duplicate_response_panics() {
+        let mut sequencer = ResponseSequencer::new();
+        let sequence = sequencer.next_sequence();
+
+        sequencer.complete(sequence, ());
+        sequencer.complete(sequence, ());
+    }
+
+    #[test]
+    #[should_panic(expected = "unassigned")]
+    fn unassigned_sequence_panics() {
+        let mut sequencer = ResponseSequencer::<()>::new();
+
+        sequencer.complete(0, ());
+    }
+}